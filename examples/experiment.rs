@@ -36,13 +36,14 @@ fn setup_scene() {
     let _material = async_material.await().unwrap();
 
     let mut mesh_transform = Transform::new();
-    let _mesh_renderer = MeshRenderer::new(&mesh, &mesh_transform);
+    MeshRenderer::new(&mesh, &mesh_transform).forget();
+    mesh.forget(); // The mesh renderer needs the mesh to stay loaded.
 
     let mut camera_transform = Transform::new();
     camera_transform.set_position(Point::new(0.0, 0.0, 10.0));
-    let camera = Camera::new(&camera_transform); // TODO: Don't drop the camera, it needs to stay in scope.
+    Camera::new(&camera_transform).forget(); // TODO: Don't drop the camera, it needs to stay in scope.
 
-    let light = DirectionalLight::new(Vector3::new(1.0, -1.0, -1.0), Color::rgb(1.0, 1.0, 1.0), 0.25);
+    DirectionalLight::new(Vector3::new(1.0, -1.0, -1.0), Color::rgb(1.0, 1.0, 1.0), 0.25).forget();
 
     let mut time: f32 = 0.0;
     engine::run_each_frame(move || {
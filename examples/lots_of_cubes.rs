@@ -60,4 +60,7 @@ fn setup_scene() {
             mesh_transform.forget();
         }
     }
+
+    // The mesh renderers need the mesh to stay loaded.
+    mesh.forget();
 }
@@ -226,6 +226,39 @@ gl_proc!(glBindBuffer:
     ///   `gen_buffers`.
     fn bind_buffer(target: BufferTarget, buffer: BufferName));
 
+gl_proc!(glBindFramebuffer:
+    /// Binds a framebuffer to a framebuffer target.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBindFramebuffer)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Binds the framebuffer object with name `framebuffer` to the framebuffer target specified by
+    /// `target`. Binding `Framebuffer` binds the framebuffer for both drawing and reading. Binding
+    /// `FramebufferName::null()` breaks the existing binding and restores the default framebuffer.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if `framebuffer` is not zero or the name of a
+    ///   framebuffer previously returned from a call to `gen_framebuffers`.
+    fn bind_framebuffer(target: FramebufferTarget, framebuffer: FramebufferName));
+
+gl_proc!(glBindRenderbuffer:
+    /// Binds a renderbuffer to a renderbuffer target.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glBindRenderbuffer)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Binds the renderbuffer object with name `renderbuffer` to the renderbuffer target
+    /// specified by `target`. Binding `RenderbufferName::null()` breaks the existing binding.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if `renderbuffer` is not zero or the name of a
+    ///   renderbuffer previously returned from a call to `gen_renderbuffers`.
+    fn bind_renderbuffer(target: RenderbufferTarget, renderbuffer: RenderbufferName));
+
 gl_proc!(glBindTexture:
     /// Binds a named texture to a texturing target.
     ///
@@ -435,6 +468,18 @@ gl_proc!(glBufferData:
     ///   specified size​.
     fn buffer_data_raw(target: BufferTarget, size: isize, data: *const (), usage: BufferUsage));
 
+gl_proc!(glCheckFramebufferStatus:
+    /// Checks the completeness status of a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glCheckFramebufferStatus)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Returns the completeness status of the framebuffer bound to `target`. A framebuffer must be
+    /// complete before it can be drawn to. If an error occurs `FramebufferStatus::Error` is
+    /// returned.
+    fn check_framebuffer_status(target: FramebufferTarget) -> FramebufferStatus);
+
 gl_proc!(glClear:
     /// Clears buffers to preset values.
     ///
@@ -617,6 +662,25 @@ gl_proc!(glDeleteBuffers:
     /// `GL_INVALID_VALUE` is generated if `num_buffers` is negative.
     fn delete_buffers(num_buffers: i32, buffers: *const BufferName));
 
+gl_proc!(glDeleteFramebuffers:
+    /// Deletes framebuffer objects.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDeleteFramebuffers)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Deletes the `count` framebuffer objects whose names are stored in the array addressed by
+    /// `framebuffers`. If a framebuffer that is currently bound is deleted the binding reverts to
+    /// 0 (the default framebuffer). Attachments are detached but not deleted.
+    ///
+    /// `delete_framebuffers` silently ignores 0's and names that do not correspond to existing
+    /// framebuffer objects.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `count` is negative.
+    fn delete_framebuffers(count: i32, framebuffers: *const FramebufferName));
+
 gl_proc!(glDeleteProgram:
     /// Deletes a program object.
     ///
@@ -640,6 +704,25 @@ gl_proc!(glDeleteProgram:
     /// `get_program_param` with arguments `program_object` and `DeleteStatus`.
     fn delete_program(program_object: ProgramObject));
 
+gl_proc!(glDeleteRenderbuffers:
+    /// Deletes renderbuffer objects.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDeleteRenderbuffers)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Deletes the `count` renderbuffer objects whose names are stored in the array addressed by
+    /// `renderbuffers`. If a renderbuffer that is attached to the currently bound framebuffer is
+    /// deleted it is detached from the framebuffer.
+    ///
+    /// `delete_renderbuffers` silently ignores 0's and names that do not correspond to existing
+    /// renderbuffer objects.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `count` is negative.
+    fn delete_renderbuffers(count: i32, renderbuffers: *const RenderbufferName));
+
 gl_proc!(glDeleteShader:
     /// Deletes a shader object.
     ///
@@ -738,6 +821,18 @@ gl_proc!(glDepthFunc:
     /// testing is disabled or if no depth buffer exists it is as if the depth test always passes.
     fn depth_func(func: Comparison));
 
+gl_proc!(glDepthMask:
+    /// Enables or disables writing into the depth buffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDepthMask)
+    ///
+    /// Core since version 1.0
+    ///
+    /// If `flag` is `False` depth testing still happens, but the depth buffer isn't updated by
+    /// draw calls. Clearing the depth buffer also respects the mask. Initially depth writing is
+    /// enabled.
+    fn depth_mask(flag: Boolean));
+
 gl_proc!(glDetachShader:
     /// Detaches a shader object from a program object to which it is attached.
     ///
@@ -826,6 +921,35 @@ gl_proc!(glDrawArrays:
     ///    object.
     fn draw_arrays(mode: DrawMode, first: i32, count: i32));
 
+gl_proc!(glDrawArraysInstanced:
+    /// Draws multiple instances of a range of elements.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawArraysInstanced)
+    ///
+    /// Core since version 3.1
+    ///
+    /// Behaves identically to `draw_arrays` except that `instance_count` instances of the range
+    /// of elements are executed. Vertex attributes with a non-zero divisor (see
+    /// `vertex_attrib_divisor`) advance once per instance rather than once per vertex.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `count` or `instance_count` is negative.
+    /// - `GL_INVALID_OPERATION` is generated if a non-zero buffer object name is bound to an enabled
+    ///    array and the buffer object's data store is currently mapped.
+    fn draw_arrays_instanced(mode: DrawMode, first: i32, count: i32, instance_count: i32));
+
+gl_proc!(glDrawBuffer:
+    /// Specifies which color buffer is drawn into.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawBuffer)
+    ///
+    /// Core since version 1.0
+    ///
+    /// Selects the color buffer of the currently bound framebuffer that fragment colors are
+    /// written to. Framebuffers that only have a depth attachment must use `ColorBuffer::None`.
+    fn draw_buffer(buffer: ColorBuffer));
+
 gl_proc!(glDrawElements:
     /// Renders primitives from array data.
     ///
@@ -867,6 +991,30 @@ gl_proc!(glDrawElements:
     ///   mapped.
     fn draw_elements(mode: DrawMode, count: i32, index_type: IndexType, offset: usize));
 
+gl_proc!(glDrawElementsInstanced:
+    /// Draws multiple instances of a set of elements.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glDrawElementsInstanced)
+    ///
+    /// Core since version 3.1
+    ///
+    /// Behaves identically to `draw_elements` except that `instance_count` instances of the set
+    /// of elements are executed. Vertex attributes with a non-zero divisor (see
+    /// `vertex_attrib_divisor`) advance once per instance rather than once per vertex.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `count` or `instance_count` is negative.
+    /// - `GL_INVALID_OPERATION` is generated if a non-zero buffer object name is bound to an
+    ///   enabled array or the element array and the buffer object's data store is currently
+    ///   mapped.
+    fn draw_elements_instanced(
+        mode: DrawMode,
+        count: i32,
+        index_type: IndexType,
+        offset: usize,
+        instance_count: i32));
+
 gl_proc!(glEnable:
     /// Enables server-side GL capabilities.
     ///
@@ -914,6 +1062,51 @@ gl_proc!(glFlush:
     /// TODO: Add documentation.
     fn flush());
 
+gl_proc!(glFramebufferRenderbuffer:
+    /// Attaches a renderbuffer to a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glFramebufferRenderbuffer)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Attaches `renderbuffer` to `attachment` of the framebuffer bound to `target`. If
+    /// `renderbuffer` is `RenderbufferName::null()` any renderbuffer attached to `attachment` is
+    /// detached.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if zero is bound to `target`.
+    /// - `GL_INVALID_OPERATION` is generated if `renderbuffer` is not zero or the name of an
+    ///   existing renderbuffer object.
+    fn framebuffer_renderbuffer(
+        target: FramebufferTarget,
+        attachment: FramebufferAttachment,
+        renderbuffer_target: RenderbufferTarget,
+        renderbuffer: RenderbufferName));
+
+gl_proc!(glFramebufferTexture2D:
+    /// Attaches a level of a texture to a framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glFramebufferTexture)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Attaches mip level `level` of `texture` to `attachment` of the framebuffer bound to
+    /// `target`. If `texture` is `TextureObject::null()` any texture attached to `attachment` is
+    /// detached.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_OPERATION` is generated if zero is bound to `target`.
+    /// - `GL_INVALID_OPERATION` is generated if `texture` is not zero or the name of an existing
+    ///   texture object.
+    fn framebuffer_texture_2d(
+        target: FramebufferTarget,
+        attachment: FramebufferAttachment,
+        texture_target: Texture2dTarget,
+        texture: TextureObject,
+        level: i32));
+
 gl_proc!(glFrontFace:
     /// Defines front- and back-facing polygons.
     ///
@@ -960,6 +1153,38 @@ gl_proc!(glGenBuffers:
     /// `GL_INVALID_VALUE` is generated if `num_buffers`​ is negative.
     fn gen_buffers(num_buffers: i32, buffers: *mut BufferName));
 
+gl_proc!(glGenFramebuffers:
+    /// Generates framebuffer object names.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGenFramebuffers)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Returns `count` framebuffer object names in `framebuffers`. No framebuffer objects are
+    /// associated with the returned names until they are first bound by calling
+    /// `bind_framebuffer`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `count` is negative.
+    fn gen_framebuffers(count: i32, framebuffers: *mut FramebufferName));
+
+gl_proc!(glGenRenderbuffers:
+    /// Generates renderbuffer object names.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glGenRenderbuffers)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Returns `count` renderbuffer object names in `renderbuffers`. No renderbuffer objects are
+    /// associated with the returned names until they are first bound by calling
+    /// `bind_renderbuffer`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `count` is negative.
+    fn gen_renderbuffers(count: i32, renderbuffers: *mut RenderbufferName));
+
 gl_proc!(glGenTextures:
     /// Generates texture names.
     ///
@@ -1074,6 +1299,8 @@ gl_proc!(glGetIntegerv:
     ///   supported by the current context.
     /// - GL_NUM_EXTENSIONS - `params` returns one value, the number of extensions supported by
     ///   the GL implementation for the current context. See `get_string`.
+    /// - `Viewport` - `params` returns four values, the `x` and `y` window coordinates of the
+    ///   viewport followed by its width and height.
     fn get_integers(name: IntegerName, params: *mut i32));
 
 gl_proc!(glGetProgramInfoLog:
@@ -2104,6 +2331,77 @@ gl_proc!(glQueryCounter:
     ///   from a previous call to `gen_queries()`.
     fn query_counter(query: QueryObject, target: QueryCounterTarget));
 
+gl_proc!(glReadBuffer:
+    /// Selects the color buffer that pixels are read from.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glReadBuffer)
+    ///
+    /// Core since version 1.0
+    ///
+    /// Selects the color buffer of the currently bound framebuffer used as the source for
+    /// subsequent reads. Framebuffers that only have a depth attachment must use
+    /// `ColorBuffer::None`.
+    fn read_buffer(buffer: ColorBuffer));
+
+gl_proc!(glReadPixels:
+    /// Reads a block of pixels from the framebuffer.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glReadPixels)
+    ///
+    /// Core since version 1.0
+    ///
+    /// Reads the pixels in the rectangle with its lower left corner at `x`, `y` from the color
+    /// buffer selected with `read_buffer()`, converting them to `format` and `data_type`. `data`
+    /// must have room for `width * height` pixels, with each row padded to the pack alignment
+    /// (4 bytes by default).
+    fn read_pixels(
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: TextureFormat,
+        data_type: TextureDataType,
+        data: *mut ()));
+
+gl_proc!(glRenderbufferStorage:
+    /// Establishes the data storage, format, and dimensions of a renderbuffer's image.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glRenderbufferStorage)
+    ///
+    /// Core since version 3.0
+    ///
+    /// Allocates storage of `width` by `height` pixels in `internal_format` for the renderbuffer
+    /// bound to `target`, deleting any existing storage.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if either `width` or `height` is negative or greater
+    ///   than `GL_MAX_RENDERBUFFER_SIZE`.
+    /// - `GL_OUT_OF_MEMORY` is generated if the GL is unable to create a data store of the
+    ///   requested size.
+    fn renderbuffer_storage(
+        target: RenderbufferTarget,
+        internal_format: TextureInternalFormat,
+        width: i32,
+        height: i32));
+
+gl_proc!(glScissor:
+    /// Defines the scissor box.
+    ///
+    /// [Wiki Page](https://www.opengl.org/wiki/GLAPI/glScissor)
+    ///
+    /// Core since 1.0
+    ///
+    /// `x` and `y` specify the lower left corner of the scissor box in window coordinates, and
+    /// `width` and `height` specify its size. When the scissor test is enabled with
+    /// `ServerCapability::ScissorTest` only pixels inside the scissor box can be modified by
+    /// drawing commands, including `clear()`.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if either width or height is negative.
+    fn scissor(x: i32, y: i32, width: i32, height: i32));
+
 gl_proc!(glShaderSource:
     /// Replaces the source code in a shader object.
     ///
@@ -2316,6 +2614,22 @@ gl_proc!(glUseProgram:
     /// - `GL_INVALID_OPERATION` is generated if transform feedback mode is active.
     fn use_program(program: ProgramObject));
 
+gl_proc!(glVertexAttribDivisor:
+    /// Modifies the rate at which generic vertex attributes advance during instanced rendering.
+    ///
+    /// [Wiki page](https://www.opengl.org/wiki/GLAPI/glVertexAttribDivisor)
+    ///
+    /// Core since version 3.3
+    ///
+    /// If `divisor` is zero the attribute advances once per vertex. Otherwise it advances once
+    /// per `divisor` instances of the set of vertices being rendered.
+    ///
+    /// # Errors
+    ///
+    /// - `GL_INVALID_VALUE` is generated if `attrib` is greater than or equal to
+    ///   `GL_MAX_VERTEX_ATTRIBS`.
+    fn vertex_attrib_divisor(attrib: AttributeLocation, divisor: u32));
+
 gl_proc!(glVertexAttribPointer:
     /// Defines an array of generic vertex attribute data.
     ///
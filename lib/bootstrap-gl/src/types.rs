@@ -125,6 +125,16 @@ impl BitOr for ClearBufferMask {
     }
 }
 
+/// The color buffers that can be selected for drawing or reading.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorBuffer {
+    None = 0,
+    Front = 0x0404,
+    Back = 0x0405,
+    ColorAttachment0 = 0x8CE0,
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Comparison {
@@ -247,6 +257,53 @@ impl Default for Face {
     fn default() -> Face { Face::Back }
 }
 
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramebufferAttachment {
+    Color0 = 0x8CE0,
+    Depth = 0x8D00,
+    Stencil = 0x8D20,
+    DepthStencil = 0x821A,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FramebufferName(pub u32);
+
+impl FramebufferName {
+    pub const fn null() -> FramebufferName {
+        FramebufferName(0)
+    }
+
+    pub fn is_null(self) -> bool {
+        self == FramebufferName(0)
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramebufferStatus {
+    /// Returned if an error occurs while checking the framebuffer's status.
+    Error = 0,
+    Complete = 0x8CD5,
+    Undefined = 0x8219,
+    IncompleteAttachment = 0x8CD6,
+    IncompleteMissingAttachment = 0x8CD7,
+    IncompleteDrawBuffer = 0x8CDB,
+    IncompleteReadBuffer = 0x8CDC,
+    Unsupported = 0x8CDD,
+    IncompleteMultisample = 0x8D56,
+    IncompleteLayerTargets = 0x8DA8,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramebufferTarget {
+    Framebuffer = 0x8D40,
+    Read = 0x8CA8,
+    Draw = 0x8CA9,
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GlType {
@@ -290,6 +347,9 @@ pub enum IntegerName {
     MajorVersion = 0x821B,
     MinorVersion = 0x821C,
     NumExtensions = 0x821D,
+
+    // Version 1.0
+    Viewport = 0x0BA2,
 }
 
 #[repr(u32)]
@@ -369,6 +429,26 @@ pub enum QueryType {
     TimeElapsed = 0x88BF,
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderbufferName(pub u32);
+
+impl RenderbufferName {
+    pub const fn null() -> RenderbufferName {
+        RenderbufferName(0)
+    }
+
+    pub fn is_null(self) -> bool {
+        self == RenderbufferName(0)
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderbufferTarget {
+    Renderbuffer = 0x8D41,
+}
+
 #[repr(u32)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ServerCapability {
@@ -542,6 +622,7 @@ pub enum TextureFormat {
     Rgba = 0x1908,
     Bgr = 0x80E0,
     Bgra = 0x80E1,
+    DepthComponent = 0x1902,
     // GL_COLOR_INDEX,
     // GL_RED,
     // GL_GREEN,
@@ -558,6 +639,7 @@ impl TextureFormat {
             TextureFormat::Rgba => 4,
             TextureFormat::Bgr => 3,
             TextureFormat::Bgra => 4,
+            TextureFormat::DepthComponent => 1,
         }
     }
 }
@@ -582,10 +664,10 @@ pub enum TextureInternalFormat {
     // GL_COMPRESSED_INTENSITY,
     // GL_COMPRESSED_RGB,
     // GL_COMPRESSED_RGBA,
-    // GL_DEPTH_COMPONENT,
-    // GL_DEPTH_COMPONENT16,
-    // GL_DEPTH_COMPONENT24,
-    // GL_DEPTH_COMPONENT32,
+    DepthComponent = 0x1902,
+    DepthComponent16 = 0x81A5,
+    DepthComponent24 = 0x81A6,
+    DepthComponent32 = 0x81A7,
     // GL_LUMINANCE,
     // GL_LUMINANCE4,
     // GL_LUMINANCE8,
@@ -617,6 +699,8 @@ pub enum TextureInternalFormat {
     // GL_RGB10_A2,
     // GL_RGBA12,
     // GL_RGBA16,
+    Rgba16f = 0x881A,
+    Rgba32f = 0x8814,
     // GL_SLUMINANCE,
     // GL_SLUMINANCE8,
     // GL_SLUMINANCE_ALPHA,
@@ -102,6 +102,24 @@ pub unsafe fn swap_buffers(context: Context) {
     }
 }
 
+/// Gets the size in pixels of the client area of the window that `context` draws to.
+pub unsafe fn drawable_size(context: Context) -> (i32, i32) {
+    let (device_context, _) = context;
+    let hwnd = user32::WindowFromDC(device_context);
+
+    let mut rect: RECT = mem::zeroed();
+    if user32::GetClientRect(hwnd, &mut rect) != TRUE {
+        panic!(
+            "Failed to get client rect, dc: {:?}, hwnd: {:?}, last error: 0x:{:X}",
+            device_context,
+            hwnd,
+            kernel32::GetLastError(),
+        );
+    }
+
+    (rect.right - rect.left, rect.bottom - rect.top)
+}
+
 pub unsafe fn make_current(context: Context) -> Context {
     let old_device_context = opengl32::wglGetCurrentDC();
     let old_render_context = opengl32::wglGetCurrentContext();
@@ -172,6 +172,10 @@ impl Window {
         (0, 0, 1, 1)
     }
 
+    pub fn client_size(&self) -> (i32, i32) {
+        (1, 1)
+    }
+
     pub fn inner(&self) -> WindowInner {
         WindowInner(self.app)
     }
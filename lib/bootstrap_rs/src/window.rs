@@ -33,6 +33,15 @@ impl Window {
         self.0.get_rect()
     }
 
+    /// Gets the width and height in pixels of the client area of the window, the part that gets
+    /// drawn to.
+    ///
+    /// Unlike the bounds returned by `get_rect()` this doesn't include the window's title bar and
+    /// borders.
+    pub fn client_size(&self) -> (i32, i32) {
+        self.0.client_size()
+    }
+
     /// Creates a message pump for the window.
     ///
     /// A message pump allows message processing for a window to be offloaded to a worker thread
@@ -159,6 +159,16 @@ impl Window {
         (rect.top, rect.left, rect.bottom, rect.right)
     }
 
+    pub fn client_size(&self) -> (i32, i32) {
+        let mut rect: RECT = unsafe { mem::uninitialized() };
+        let result = unsafe {
+            user32::GetClientRect(self.handle, &mut rect)
+        };
+
+        assert!(result != 0, "Failed to get client rect");
+        (rect.right - rect.left, rect.bottom - rect.top)
+    }
+
     pub fn inner(&self) -> WindowInner {
         self.inner.clone()
     }
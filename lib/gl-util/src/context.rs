@@ -1,4 +1,5 @@
 use bootstrap::window::Window;
+use framebuffer::Framebuffer;
 use gl;
 use gl::*;
 use std::cell::RefCell;
@@ -53,6 +54,9 @@ impl Context {
                 gl::create_context(device_context)
                 .ok_or(Error::UnableToCreateRenderContext)?;
 
+            // The initial viewport covers the entire drawable area of the window.
+            let mut viewport = [0i32; 4];
+
             {
                 let _guard = ::context::ContextGuard::new(context);
 
@@ -75,6 +79,8 @@ impl Context {
                 gl::gen_vertex_arrays::load();
                 gl::enable(ServerCapability::FramebufferSrgb);
                 gl::enable(ServerCapability::Blend);
+
+                gl::get_integers(IntegerName::Viewport, viewport.as_mut_ptr());
             }
 
             let inner = Rc::new(RefCell::new(ContextInner {
@@ -86,13 +92,17 @@ impl Context {
                 server_blend_enabled: true,
 
                 bound_vertex_array: None,
+                bound_framebuffer: FramebufferName::null(),
                 front_polygon_mode: PolygonMode::default(),
                 back_polygon_mode: PolygonMode::default(),
                 program: None,
                 cull_mode: Face::default(),
                 winding_order: WindingOrder::default(),
                 depth_test: Comparison::Less,
+                depth_write: true,
                 blend: Default::default(),
+                viewport: (viewport[0], viewport[1], viewport[2], viewport[3]),
+                clear_color: [0.0, 0.0, 0.0, 0.0],
             }));
 
             Ok(Context {
@@ -104,10 +114,126 @@ impl Context {
 
     /// TODO: Take clear mask (and values) as parameters.
     pub fn clear(&self) {
+        let mut inner = self.inner.borrow_mut();
         let _guard = ::context::ContextGuard::new(self.raw);
+
+        // Clearing respects the depth mask, so make sure depth writes are enabled.
+        inner.depth_write(true);
         unsafe { gl::clear(ClearBufferMask::Color | ClearBufferMask::Depth); }
     }
 
+    /// Clears the area of the framebuffer covered by the current viewport.
+    ///
+    /// If `color` is `None` the color buffer is left untouched. The rest of the framebuffer is
+    /// never affected, which makes it possible to have multiple viewports that each clear their
+    /// own region.
+    pub fn clear_viewport(&self, color: Option<[f32; 4]>, depth: bool) {
+        let mask = match (color, depth) {
+            (Some(_), true) => ClearBufferMask::Color | ClearBufferMask::Depth,
+            (Some(_), false) => ClearBufferMask::Color,
+            (None, true) => ClearBufferMask::Depth,
+            (None, false) => return,
+        };
+
+        let mut inner = self.inner.borrow_mut();
+        let _guard = ::context::ContextGuard::new(self.raw);
+        let (x, y, width, height) = inner.viewport;
+
+        // Clearing respects the depth mask, so make sure depth writes are enabled.
+        if depth {
+            inner.depth_write(true);
+        }
+
+        unsafe {
+            if let Some(color) = color {
+                gl::clear_color(color[0], color[1], color[2], color[3]);
+            }
+
+            // `clear()` ignores the viewport, so we use the scissor test to limit it.
+            gl::enable(ServerCapability::ScissorTest);
+            gl::scissor(x, y, width, height);
+            gl::clear(mask);
+            gl::disable(ServerCapability::ScissorTest);
+
+            if color.is_some() {
+                let clear_color = inner.clear_color;
+                gl::clear_color(clear_color[0], clear_color[1], clear_color[2], clear_color[3]);
+            }
+        }
+    }
+
+    /// Sets the region of the framebuffer that draw calls render to, in pixels.
+    ///
+    /// `x` and `y` specify the lower left corner of the viewport.
+    pub fn set_viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.viewport != (x, y, width, height) {
+            let _guard = ::context::ContextGuard::new(self.raw);
+            unsafe { gl::viewport(x, y, width, height); }
+            inner.viewport = (x, y, width, height);
+        }
+    }
+
+    /// Sets the framebuffer that draw calls and clears render to.
+    ///
+    /// If `framebuffer` is `None` rendering goes to the default framebuffer, i.e. the window.
+    /// The viewport isn't changed, so it should be set to cover the new target.
+    pub fn set_framebuffer(&self, framebuffer: Option<&Framebuffer>) {
+        let mut inner = self.inner.borrow_mut();
+        let _guard = ::context::ContextGuard::new(self.raw);
+        inner.bind_framebuffer(framebuffer.map_or(FramebufferName::null(), Framebuffer::inner));
+    }
+
+    /// Reads the color of every pixel in `framebuffer`, or in the window's back buffer if
+    /// `framebuffer` is `None`.
+    ///
+    /// The pixels are returned as RGBA bytes, one row at a time starting from the bottom of the
+    /// image. The back buffer's contents are undefined after `swap_buffers()`, so the window has
+    /// to be read after drawing the frame but before swapping the buffers.
+    pub fn read_pixels(&self, framebuffer: Option<&Framebuffer>) -> Vec<u8> {
+        let mut inner = self.inner.borrow_mut();
+        let _guard = ::context::ContextGuard::new(self.raw);
+
+        let (width, height) = match framebuffer {
+            Some(framebuffer) => framebuffer.size(),
+            None => {
+                let (width, height) = unsafe { gl::platform::drawable_size(self.raw) };
+                (width as usize, height as usize)
+            },
+        };
+
+        // Rows of RGBA pixels are always 4 byte aligned, so they don't need any padding.
+        let mut pixels = vec![0u8; width * height * 4];
+
+        let previous = inner.bound_framebuffer();
+        inner.bind_framebuffer(framebuffer.map_or(FramebufferName::null(), Framebuffer::inner));
+
+        // The default framebuffer is read from the back buffer unless told otherwise.
+        unsafe {
+            gl::read_pixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                TextureFormat::Rgba,
+                TextureDataType::u8,
+                pixels.as_mut_ptr() as *mut ());
+        }
+
+        inner.bind_framebuffer(previous);
+
+        pixels
+    }
+
+    /// Gets the size in pixels of the default framebuffer, i.e. the window that the context was
+    /// created for.
+    ///
+    /// The size is queried from the window each time, so it stays correct when the window is
+    /// resized.
+    pub fn default_framebuffer_size(&self) -> (i32, i32) {
+        unsafe { gl::platform::drawable_size(self.raw) }
+    }
+
     pub fn swap_buffers(&self) {
         let _guard = ::context::ContextGuard::new(self.raw);
         unsafe { gl::platform::swap_buffers(self.raw); }
@@ -132,13 +258,20 @@ pub(crate) struct ContextInner {
     server_blend_enabled: bool,
 
     bound_vertex_array: Option<VertexArrayName>,
+    bound_framebuffer: FramebufferName,
     front_polygon_mode: PolygonMode,
     back_polygon_mode: PolygonMode,
     program: Option<ProgramObject>,
     cull_mode: Face,
     winding_order: WindingOrder,
     depth_test: Comparison,
+    depth_write: bool,
     blend: (SourceFactor, DestFactor),
+    viewport: (i32, i32, i32, i32),
+
+    /// The clear color used by `clear()`. `clear_viewport()` changes the clear color temporarily,
+    /// so it uses this to restore it afterwards.
+    clear_color: [f32; 4],
 }
 
 impl ContextInner {
@@ -160,6 +293,17 @@ impl ContextInner {
         }
     }
 
+    pub(crate) fn bound_framebuffer(&self) -> FramebufferName {
+        self.bound_framebuffer
+    }
+
+    pub(crate) fn bind_framebuffer(&mut self, framebuffer_name: FramebufferName) {
+        if framebuffer_name != self.bound_framebuffer {
+            unsafe { gl::bind_framebuffer(FramebufferTarget::Framebuffer, framebuffer_name); }
+            self.bound_framebuffer = framebuffer_name;
+        }
+    }
+
     pub(crate) fn polygon_mode(&mut self, mode: PolygonMode) {
         if mode != self.front_polygon_mode || mode != self.back_polygon_mode {
             unsafe { gl::polygon_mode(Face::FrontAndBack, mode); }
@@ -220,6 +364,13 @@ impl ContextInner {
         }
     }
 
+    pub(crate) fn depth_write(&mut self, enabled: bool) {
+        if enabled != self.depth_write {
+            unsafe { gl::depth_mask(enabled.into()); }
+            self.depth_write = enabled;
+        }
+    }
+
     pub(crate) fn blend(&mut self, source_factor: SourceFactor, dest_factor: DestFactor) {
         if (source_factor, dest_factor) != self.blend {
             unsafe { gl::blend_func(source_factor, dest_factor); }
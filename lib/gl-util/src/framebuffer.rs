@@ -0,0 +1,216 @@
+//! Framebuffers for rendering into textures instead of the window.
+
+use context::{Context, ContextInner};
+use gl;
+use gl::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use texture::Texture2d;
+
+pub use gl::FramebufferStatus;
+
+/// A framebuffer that draws into textures instead of the window.
+///
+/// Use `Context::set_framebuffer()` to make draw calls render into the framebuffer. The
+/// framebuffer doesn't take ownership of its attachments, so they must outlive it.
+#[derive(Debug)]
+pub struct Framebuffer {
+    framebuffer_name: FramebufferName,
+    width: usize,
+    height: usize,
+
+    context: Rc<RefCell<ContextInner>>,
+}
+
+impl Framebuffer {
+    /// Creates a framebuffer that renders color into `color_texture`.
+    ///
+    /// If `depth_buffer` is `None` the framebuffer has no depth buffer, so depth testing has no
+    /// effect when drawing to it. `depth_buffer` must be the same size as `color_texture`.
+    pub fn with_color_texture(
+        context: &Context,
+        color_texture: &Texture2d,
+        depth_buffer: Option<&Renderbuffer>,
+    ) -> Result<Framebuffer, Error> {
+        let (width, height) = color_texture.size();
+        if let Some(depth_buffer) = depth_buffer {
+            assert!(
+                depth_buffer.size() == (width, height),
+                "Depth buffer must be the same size as the color texture, color: {:?}, depth: {:?}",
+                (width, height),
+                depth_buffer.size());
+        }
+
+        Framebuffer::new(context, width, height, || unsafe {
+            gl::framebuffer_texture_2d(
+                FramebufferTarget::Framebuffer,
+                FramebufferAttachment::Color0,
+                Texture2dTarget::Texture2d,
+                color_texture.inner(),
+                0);
+
+            if let Some(depth_buffer) = depth_buffer {
+                gl::framebuffer_renderbuffer(
+                    FramebufferTarget::Framebuffer,
+                    FramebufferAttachment::Depth,
+                    RenderbufferTarget::Renderbuffer,
+                    depth_buffer.inner());
+            }
+
+            gl::draw_buffer(ColorBuffer::ColorAttachment0);
+            gl::read_buffer(ColorBuffer::ColorAttachment0);
+        })
+    }
+
+    /// Creates a framebuffer that only renders depth, writing it into `depth_texture`.
+    ///
+    /// `depth_texture` should be created with `Texture2d::depth()`.
+    pub fn with_depth_texture(context: &Context, depth_texture: &Texture2d) -> Result<Framebuffer, Error> {
+        let (width, height) = depth_texture.size();
+        Framebuffer::new(context, width, height, || unsafe {
+            gl::framebuffer_texture_2d(
+                FramebufferTarget::Framebuffer,
+                FramebufferAttachment::Depth,
+                Texture2dTarget::Texture2d,
+                depth_texture.inner(),
+                0);
+
+            // There's no color attachment, so there's no color buffer to draw to or read from.
+            gl::draw_buffer(ColorBuffer::None);
+            gl::read_buffer(ColorBuffer::None);
+        })
+    }
+
+    /// Gets the width and height of the framebuffer in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the OpenGL primitive managed by this object.
+    pub(crate) fn inner(&self) -> FramebufferName {
+        self.framebuffer_name
+    }
+
+    /// Creates a new framebuffer, calling `attach` while the framebuffer is bound to set up its
+    /// attachments.
+    fn new<F>(context: &Context, width: usize, height: usize, attach: F) -> Result<Framebuffer, Error>
+        where F: FnOnce()
+    {
+        let context_inner = context.inner();
+        let mut inner = context_inner.borrow_mut();
+        let _guard = ::context::ContextGuard::new(inner.raw());
+
+        let mut framebuffer_name = FramebufferName::null();
+        unsafe { gl::gen_framebuffers(1, &mut framebuffer_name); }
+
+        if framebuffer_name.is_null() {
+            return Err(Error::FailedToGenerateFramebuffer);
+        }
+
+        let previous = inner.bound_framebuffer();
+        inner.bind_framebuffer(framebuffer_name);
+        attach();
+        let status = unsafe { gl::check_framebuffer_status(FramebufferTarget::Framebuffer) };
+        inner.bind_framebuffer(previous);
+
+        if status != FramebufferStatus::Complete {
+            unsafe { gl::delete_framebuffers(1, &framebuffer_name); }
+            return Err(Error::Incomplete(status));
+        }
+
+        Ok(Framebuffer {
+            framebuffer_name: framebuffer_name,
+            width: width,
+            height: height,
+
+            context: context_inner.clone(),
+        })
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        let mut context = self.context.borrow_mut();
+        let _guard = ::context::ContextGuard::new(context.raw());
+
+        // Deleting a bound framebuffer reverts to the default framebuffer, so make sure the
+        // context doesn't think it's still bound.
+        if context.bound_framebuffer() == self.framebuffer_name {
+            context.bind_framebuffer(FramebufferName::null());
+        }
+
+        unsafe { gl::delete_framebuffers(1, &self.framebuffer_name); }
+    }
+}
+
+/// Image storage that can be attached to a framebuffer but not sampled like a texture.
+///
+/// Renderbuffers are useful for attachments that are only needed while rendering, such as the
+/// depth buffer when rendering a camera's view into a texture.
+#[derive(Debug)]
+pub struct Renderbuffer {
+    renderbuffer_name: RenderbufferName,
+    width: usize,
+    height: usize,
+
+    context: ::gl::Context,
+}
+
+impl Renderbuffer {
+    /// Creates a renderbuffer for storing depth values.
+    pub fn depth(context: &Context, width: usize, height: usize) -> Result<Renderbuffer, Error> {
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut renderbuffer_name = RenderbufferName::null();
+        unsafe { gl::gen_renderbuffers(1, &mut renderbuffer_name); }
+
+        if renderbuffer_name.is_null() {
+            return Err(Error::FailedToGenerateRenderbuffer);
+        }
+
+        unsafe {
+            gl::bind_renderbuffer(RenderbufferTarget::Renderbuffer, renderbuffer_name);
+            gl::renderbuffer_storage(
+                RenderbufferTarget::Renderbuffer,
+                TextureInternalFormat::DepthComponent24,
+                width as i32,
+                height as i32);
+            gl::bind_renderbuffer(RenderbufferTarget::Renderbuffer, RenderbufferName::null());
+        }
+
+        Ok(Renderbuffer {
+            renderbuffer_name: renderbuffer_name,
+            width: width,
+            height: height,
+
+            context: context,
+        })
+    }
+
+    /// Gets the width and height of the renderbuffer in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the OpenGL primitive managed by this object.
+    pub(crate) fn inner(&self) -> RenderbufferName {
+        self.renderbuffer_name
+    }
+}
+
+impl Drop for Renderbuffer {
+    fn drop(&mut self) {
+        let _guard = ::context::ContextGuard::new(self.context);
+        unsafe { gl::delete_renderbuffers(1, &self.renderbuffer_name); }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    FailedToGenerateFramebuffer,
+    FailedToGenerateRenderbuffer,
+
+    /// The framebuffer's attachments can't be rendered to, e.g. because they're the wrong format.
+    Incomplete(FramebufferStatus),
+}
@@ -34,6 +34,7 @@ pub use gl::{
 };
 
 pub mod context;
+pub mod framebuffer;
 pub mod shader;
 pub mod texture;
 
@@ -71,6 +72,10 @@ pub struct VertexArray {
     vertex_buffer_name: BufferName,
     index_buffer: Option<IndexBuffer>,
 
+    /// The buffer holding per-instance attribute data, created by the first call to
+    /// `set_instance_attrib()`.
+    instance_buffer_name: Option<BufferName>,
+
     /// The number of primitive elments in the buffer.
     ///
     /// Does not reflect the number of vertices in the buffer.
@@ -116,6 +121,7 @@ impl VertexArray {
             vertex_array_name: vertex_array_name,
             vertex_buffer_name: vertex_buffer_name,
             index_buffer: None,
+            instance_buffer_name: None,
 
             vertex_primitive_len: vertex_data.len(),
             elements_per_vertex: 0,
@@ -173,6 +179,10 @@ impl VertexArray {
             let _guard = ::context::ContextGuard::new(context.raw());
             context.bind_vertex_array(self.vertex_array_name);
 
+            // Bind the vertex buffer explicitly, since the instance buffer may have been bound by
+            // `set_instance_attrib()`.
+            gl::bind_buffer(BufferTarget::Array, self.vertex_buffer_name);
+
             gl::enable_vertex_attrib_array(attrib_location);
             gl::vertex_attrib_pointer(
                 attrib_location,
@@ -184,16 +194,86 @@ impl VertexArray {
             );
         }
     }
+
+    /// Declares a per-instance vertex attribute.
+    ///
+    /// Instance attributes are read from a separate buffer that is filled with
+    /// `set_instance_data()`, and advance once per instance when drawing with
+    /// `DrawBuilder::instances()` instead of once per vertex. Attributes larger than 4 elements
+    /// (e.g. matrices) must be declared as one attribute per column.
+    pub fn set_instance_attrib(
+        &mut self,
+        attrib_location: AttributeLocation,
+        layout: AttribLayout,
+    ) {
+        assert!(
+            layout.elements <= 4,
+            "Layout elements must not be more than 4 (was actually {})",
+            layout.elements
+        );
+
+        unsafe {
+            let mut context = self.context.borrow_mut();
+            let _guard = ::context::ContextGuard::new(context.raw());
+            context.bind_vertex_array(self.vertex_array_name);
+
+            let instance_buffer_name = match self.instance_buffer_name {
+                Some(buffer_name) => buffer_name,
+                None => {
+                    let buffer_name = gl::gen_buffer().expect("Failed to create buffer object");
+                    self.instance_buffer_name = Some(buffer_name);
+                    buffer_name
+                },
+            };
+            gl::bind_buffer(BufferTarget::Array, instance_buffer_name);
+
+            gl::enable_vertex_attrib_array(attrib_location);
+            gl::vertex_attrib_pointer(
+                attrib_location,
+                layout.elements as i32,
+                GlType::Float,
+                False,
+                (layout.stride * mem::size_of::<f32>()) as i32,
+                layout.offset * mem::size_of::<f32>(),
+            );
+            gl::vertex_attrib_divisor(attrib_location, 1);
+        }
+    }
+
+    /// Replaces the contents of the instance buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no instance attributes have been declared with `set_instance_attrib()`.
+    pub fn set_instance_data(&self, instance_data: &[f32]) {
+        let instance_buffer_name = self.instance_buffer_name.expect("Vertex array has no instance attributes");
+
+        unsafe {
+            let context = self.context.borrow_mut();
+            let _guard = ::context::ContextGuard::new(context.raw());
+
+            gl::bind_buffer(BufferTarget::Array, instance_buffer_name);
+            gl::buffer_data(
+                BufferTarget::Array,
+                instance_data,
+                BufferUsage::StreamDraw,
+            );
+        }
+    }
 }
 
 impl Drop for VertexArray {
     fn drop(&mut self) {
         let mut context = self.context.borrow_mut();
         let _guard = ::context::ContextGuard::new(context.raw());
-        let buffers = &mut [self.vertex_buffer_name, self.index_buffer.clone().map_or(BufferName::null(), |buf| buf.name)];
+        let buffers = &mut [
+            self.vertex_buffer_name,
+            self.index_buffer.clone().map_or(BufferName::null(), |buf| buf.name),
+            self.instance_buffer_name.unwrap_or(BufferName::null()),
+        ];
         unsafe {
             gl::delete_vertex_arrays(1, &mut self.vertex_array_name);
-            gl::delete_buffers(2, buffers.as_ptr());
+            gl::delete_buffers(3, buffers.as_ptr());
         }
         context.unbind_vertex_array(self.vertex_array_name);
     }
@@ -220,8 +300,10 @@ pub struct DrawBuilder<'a> {
     program: Option<&'a Program>,
     cull: Option<Face>,
     depth_test: Option<Comparison>,
+    depth_write: bool,
     winding_order: WindingOrder,
     blend: (SourceFactor, DestFactor),
+    instances: Option<usize>,
     uniforms: HashMap<UniformLocation, UniformValue<'a>>,
 
     context: Rc<RefCell<ContextInner>>,
@@ -238,8 +320,10 @@ impl<'a> DrawBuilder<'a> {
             program: None,
             cull: None,
             depth_test: None,
+            depth_write: true,
             winding_order: WindingOrder::default(),
             blend: Default::default(),
+            instances: None,
             uniforms: HashMap::new(),
 
             context: context.inner(),
@@ -270,6 +354,15 @@ impl<'a> DrawBuilder<'a> {
         self
     }
 
+    /// Sets whether the draw call writes into the depth buffer, defaults to `true`.
+    ///
+    /// Disabling depth writes still allows the draw call to be depth tested, which is useful for
+    /// transparent geometry that shouldn't hide what's drawn behind it afterwards.
+    pub fn depth_write(&mut self, enabled: bool) -> &mut DrawBuilder<'a> {
+        self.depth_write = enabled;
+        self
+    }
+
     pub fn winding(&mut self, winding_order: WindingOrder) -> &mut DrawBuilder<'a> {
         self.winding_order = winding_order;
         self
@@ -284,6 +377,15 @@ impl<'a> DrawBuilder<'a> {
         self
     }
 
+    /// Draws `count` instances of the vertex array in a single draw call.
+    ///
+    /// Per-instance data is read from the vertex array's instance attributes, see
+    /// `VertexArray::set_instance_attrib()`.
+    pub fn instances(&mut self, count: usize) -> &mut DrawBuilder<'a> {
+        self.instances = Some(count);
+        self
+    }
+
     /// Sets the value of a uniform variable in the shader program.
     ///
     /// `uniform()` will silently ignore uniform variables that do not exist in the shader program,
@@ -338,6 +440,7 @@ impl<'a> DrawBuilder<'a> {
         } else {
             context.enable_server_depth_test(false);
         }
+        context.depth_write(self.depth_write);
 
         let (source_factor, dest_factor) = self.blend;
         context.blend(source_factor, dest_factor);
@@ -354,20 +457,41 @@ impl<'a> DrawBuilder<'a> {
             // first.
             context.bind_vertex_array(self.vertex_array.vertex_array_name);
 
-            if let Some(indices) = self.vertex_array.index_buffer.as_ref() {
-                gl::draw_elements(
-                    self.draw_mode,
-                    indices.primitive_len as i32,
-                    IndexType::UnsignedInt,
-                    0,
-                );
-            } else {
-                let vertex_len = self.vertex_array.vertex_primitive_len / self.vertex_array.elements_per_vertex;
-                gl::draw_arrays(
-                    self.draw_mode,
-                    0,
-                    vertex_len as i32,
-                );
+            match (self.vertex_array.index_buffer.as_ref(), self.instances) {
+                (Some(indices), None) => {
+                    gl::draw_elements(
+                        self.draw_mode,
+                        indices.primitive_len as i32,
+                        IndexType::UnsignedInt,
+                        0,
+                    );
+                },
+                (Some(indices), Some(instances)) => {
+                    gl::draw_elements_instanced(
+                        self.draw_mode,
+                        indices.primitive_len as i32,
+                        IndexType::UnsignedInt,
+                        0,
+                        instances as i32,
+                    );
+                },
+                (None, None) => {
+                    let vertex_len = self.vertex_array.vertex_primitive_len / self.vertex_array.elements_per_vertex;
+                    gl::draw_arrays(
+                        self.draw_mode,
+                        0,
+                        vertex_len as i32,
+                    );
+                },
+                (None, Some(instances)) => {
+                    let vertex_len = self.vertex_array.vertex_primitive_len / self.vertex_array.elements_per_vertex;
+                    gl::draw_arrays_instanced(
+                        self.draw_mode,
+                        0,
+                        vertex_len as i32,
+                        instances as i32,
+                    );
+                },
             }
         }
     }
@@ -421,6 +545,13 @@ impl<'a> DrawBuilder<'a> {
                 },
                 _ => panic!("Unsupported matrix data length: {}", matrix.data.len()),
             },
+            UniformValue::Matrix4v(ref matrices) => unsafe {
+                gl::uniform_matrix_f32x4v(
+                    location,
+                    matrices.data.len() as i32,
+                    matrices.transpose.into(),
+                    matrices.data.as_ptr() as *const _)
+            },
             UniformValue::Texture(texture) => {
                 unsafe {
                     texture::set_active_texture(*active_texture as u32);
@@ -448,6 +579,7 @@ pub enum UniformValue<'a> {
     I32x1v(&'a [i32]),
     U32(u32),
     Matrix(GlMatrix<'a>),
+    Matrix4v(GlMatrix4Array<'a>),
     Texture(&'a Texture2d),
 }
 
@@ -541,6 +673,12 @@ impl<'a> From<GlMatrix<'a>> for UniformValue<'a> {
     }
 }
 
+impl<'a> From<GlMatrix4Array<'a>> for UniformValue<'a> {
+    fn from(matrices: GlMatrix4Array<'a>) -> UniformValue<'a> {
+        UniformValue::Matrix4v(matrices)
+    }
+}
+
 impl<'a> From<&'a Texture2d> for UniformValue<'a> {
     fn from(from: &'a Texture2d) -> UniformValue<'a> {
         UniformValue::Texture(from)
@@ -552,3 +690,10 @@ pub struct GlMatrix<'a> {
     pub data: &'a [f32],
     pub transpose: bool,
 }
+
+/// An array of 4x4 matrices, used to set a `mat4` array uniform.
+#[derive(Debug, Clone)]
+pub struct GlMatrix4Array<'a> {
+    pub data: &'a [[f32; 16]],
+    pub transpose: bool,
+}
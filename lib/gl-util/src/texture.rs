@@ -8,6 +8,8 @@ pub use gl::{
 #[derive(Debug)]
 pub struct Texture2d {
     texture_object: TextureObject,
+    width: usize,
+    height: usize,
 
     context: ::gl::Context,
 }
@@ -72,6 +74,112 @@ impl Texture2d {
 
         Ok(Texture2d {
             texture_object: texture_object,
+            width: width,
+            height: height,
+
+            context: context,
+        })
+    }
+
+    /// Constructs a new `Texture2d` for storing depth values, e.g. as a framebuffer's depth
+    /// attachment.
+    ///
+    /// The texture's contents are initially undefined.
+    pub fn depth(context: &Context, width: usize, height: usize) -> Result<Texture2d, Error> {
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut texture_object = TextureObject::null();
+        unsafe { gl::gen_textures(1, &mut texture_object); }
+
+        // Check if the texture object was successfully created.
+        if texture_object.is_null() {
+            return Err(Error::FailedToGenerateTexture);
+        }
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, texture_object);
+            gl::texture_image_2d(
+                Texture2dTarget::Texture2d,
+                0,
+                TextureInternalFormat::DepthComponent24,
+                width as i32,
+                height as i32,
+                0,
+                TextureFormat::DepthComponent,
+                TextureDataType::f32,
+                ::std::ptr::null());
+
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MinFilter,
+                TextureFilterFunction::Nearest.into());
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MagFilter,
+                TextureFilterFunction::Nearest.into());
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+
+        Ok(Texture2d {
+            texture_object: texture_object,
+            width: width,
+            height: height,
+
+            context: context,
+        })
+    }
+
+    /// Constructs a new RGBA `Texture2d` for rendering into, e.g. as a framebuffer's color
+    /// attachment.
+    ///
+    /// Unlike other textures, render targets use linear filtering so that they can be resampled
+    /// smoothly when drawn back to the screen. The texture's contents are initially undefined.
+    pub fn render_target(
+        context: &Context,
+        internal_format: TextureInternalFormat,
+        width: usize,
+        height: usize,
+    ) -> Result<Texture2d, Error> {
+        let context = context.raw();
+        let _guard = ::context::ContextGuard::new(context);
+
+        let mut texture_object = TextureObject::null();
+        unsafe { gl::gen_textures(1, &mut texture_object); }
+
+        // Check if the texture object was successfully created.
+        if texture_object.is_null() {
+            return Err(Error::FailedToGenerateTexture);
+        }
+
+        unsafe {
+            gl::bind_texture(TextureBindTarget::Texture2d, texture_object);
+            gl::texture_image_2d(
+                Texture2dTarget::Texture2d,
+                0,
+                internal_format,
+                width as i32,
+                height as i32,
+                0,
+                TextureFormat::Rgba,
+                TextureDataType::f32,
+                ::std::ptr::null());
+
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MinFilter,
+                TextureFilterFunction::Linear.into());
+            gl::texture_parameter_i32(
+                TextureParameterTarget::Texture2d,
+                TextureParameterName::MagFilter,
+                TextureFilterFunction::Linear.into());
+            gl::bind_texture(TextureBindTarget::Texture2d, TextureObject::null());
+        }
+
+        Ok(Texture2d {
+            texture_object: texture_object,
+            width: width,
+            height: height,
 
             context: context,
         })
@@ -80,11 +188,18 @@ impl Texture2d {
     pub fn empty(context: &Context) -> Texture2d {
         Texture2d {
             texture_object: TextureObject::null(),
+            width: 0,
+            height: 0,
 
             context: context.raw(),
         }
     }
 
+    /// Gets the width and height of the texture in pixels.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
     /// Returns the OpenGL primitive managed by this object.
     pub(crate) fn inner(&self) -> TextureObject {
         self.texture_object
@@ -98,6 +98,7 @@ impl<'a> Lexer<'a> {
                 let span = Span::new(start_index, end_index);
 
                 let token = match word {
+                    "blend" => Token::Blend,
                     "property" => Token::Property,
                     "program" => Token::Program,
                     _ => Token::Identifier,
@@ -10,6 +10,10 @@ use std::path::Path;
 pub struct MaterialSource {
     pub properties: Vec<PropertySource>,
     pub programs: Vec<ProgramSource>,
+
+    /// How the material's output is blended with what's already been drawn, declared with a
+    /// `blend` item such as `blend alpha;`. Defaults to `BlendMode::Opaque`.
+    pub blend: BlendMode,
 }
 
 impl MaterialSource {
@@ -76,6 +80,40 @@ pub enum PropertyType {
     Vector3,
 }
 
+/// Specifies how a material's output color is combined with the color already in the
+/// framebuffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// The output replaces what's already there. Declared as `blend opaque;`.
+    Opaque,
+
+    /// The output is mixed with what's already there based on the output's alpha. Declared as
+    /// `blend alpha;`.
+    Alpha,
+
+    /// The output is added to what's already there, scaled by the output's alpha. Declared as
+    /// `blend additive;`.
+    Additive,
+
+    /// Like `Alpha`, but the output's color has already been multiplied by its alpha. Declared
+    /// as `blend premultiplied;`.
+    Premultiplied,
+}
+
+impl BlendMode {
+    /// Checks if the blend mode makes the material transparent, i.e. anything other than
+    /// `Opaque`.
+    pub fn is_transparent(&self) -> bool {
+        *self != BlendMode::Opaque
+    }
+}
+
+impl Default for BlendMode {
+    fn default() -> BlendMode {
+        BlendMode::Opaque
+    }
+}
+
 /// Represents an error in parsing a material source file.
 #[derive(Debug)]
 pub enum Error {
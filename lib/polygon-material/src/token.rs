@@ -4,6 +4,7 @@ use std::ops::Index;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Token {
     /* Keywords */
+    Blend,
     Program,
     Property,
 
@@ -3,7 +3,7 @@
 extern crate polygon_material as material;
 
 use material::lexer::{Error as TokenError, ErrorData, Lexer};
-use material::material_source::{PropertySource, PropertyType, ProgramSource, MaterialSource, Error as MaterialSourceError};
+use material::material_source::{BlendMode, PropertySource, PropertyType, ProgramSource, MaterialSource, Error as MaterialSourceError};
 use material::parser::Error as ParseError;
 use material::token::*;
 
@@ -77,6 +77,7 @@ fn lex_properties() {
             }
         ],
         programs: vec![],
+        blend: BlendMode::Opaque,
     });
 
     verify_lexer(SOURCE, EXPECTED_TOKENS, expected_material);
@@ -133,6 +134,37 @@ fn lex_program() {
             ProgramSource::Vertex(" foo.bar(); ".to_string()),
             ProgramSource::Fragment("\n            fn program keyworkds do_stuff() {\n                bar.foo();\n            }\n        ".to_string()),
         ],
+        blend: BlendMode::Opaque,
+    });
+
+    verify_lexer(SOURCE, EXPECTED_TOKENS, expected_material);
+}
+
+#[test]
+fn lex_blend() {
+    static SOURCE: &'static str = r#"
+        blend alpha;
+        program frag { @color = vec4(1.0); }
+    "#;
+
+    static EXPECTED_TOKENS: &'static [Result<(Token, &'static str), (ErrorData, &'static str)>] = &[
+        Ok((Token::Blend, "blend")),
+        Ok((Token::Identifier, "alpha")),
+        Ok((Token::SemiColon, ";")),
+
+        Ok((Token::Program, "program")),
+        Ok((Token::Identifier, "frag")),
+        Ok((Token::ProgramLiteral, " @color = vec4(1.0); ")),
+
+        Ok((Token::EndOfFile, "")),
+    ];
+
+    let expected_material = Ok(MaterialSource {
+        properties: vec![],
+        programs: vec![
+            ProgramSource::Fragment(" @color = vec4(1.0); ".to_string()),
+        ],
+        blend: BlendMode::Alpha,
     });
 
     verify_lexer(SOURCE, EXPECTED_TOKENS, expected_material);
@@ -1,6 +1,7 @@
+use matrix::Matrix3;
 use quaternion::Quaternion;
 use std::ops::{Add, AddAssign, Sub, SubAssign, Div, DivAssign, Mul, MulAssign};
-use super::{IsZero, Dot, PI};
+use super::{IsZero, Lerp, PI};
 use vector::Vector3;
 
 /// An orientation in 3D space.
@@ -42,34 +43,36 @@ impl Orientation {
     }
 
     /// Creates an orientation that rotates an object to look in the specified direction.
+    ///
+    /// The resulting orientation has its forward direction (global negative z axis) pointing
+    /// along `forward`, and its up direction as close to `up` as possible while remaining
+    /// perpendicular to `forward`. If `forward` and `up` are parallel then an arbitrary
+    /// perpendicular up direction is used.
     pub fn look_rotation(forward: Vector3, up: Vector3) -> Orientation {
         assert!(!forward.is_zero());
         assert!(!up.is_zero());
 
-        let source = Vector3::forward();
-        let forward = forward.normalized();
-        let up = up.normalized();
-
-        let dot = source.dot(forward);
+        let back = -forward.normalized();
 
-        if (dot + 1.0).is_zero() {
-            // vector a and b point exactly in the opposite direction,
-            // so it is a 180 degrees turn around the up-axis
-            return Orientation::axis_angle(up, PI)
+        // If `up` is parallel to `forward` then there are infinitely many valid orientations, so
+        // we fall back to whichever global axis is least aligned with `forward`.
+        let mut right = Vector3::cross(up, back);
+        if right.is_zero() {
+            let fallback = if back.x.abs() < 0.9 { Vector3::right() } else { Vector3::up() };
+            right = Vector3::cross(fallback, back);
         }
-
-        if (dot - 1.0).is_zero() {
-            // Vector a and b point exactly in the same direction
-            // so we return the identity quaternion.
-            return Orientation::new()
+        let right = right.normalized();
+        let up = Vector3::cross(back, right);
+
+        // Build the rotation matrix with the new basis vectors as its columns.
+        let mut matrix = Matrix3::identity();
+        for row in 0..3 {
+            matrix[row][0] = right[row];
+            matrix[row][1] = up[row];
+            matrix[row][2] = back[row];
         }
 
-        // let rot_angle = dot.acos();
-        // let rot_axis = Vector3::cross(source, forward).normalized();// source.cross(forward).normalized();
-        // return Orientation::axis_angle(rot_axis, rot_angle)
-
-        // TODO: Correctly take the up vector into account.
-        unimplemented!();
+        matrix.into()
     }
 
     /// Creates a quaternion from a set of euler angles.
@@ -95,6 +98,18 @@ impl Orientation {
         }
     }
 
+    /// Spherically interpolates between two orientations.
+    ///
+    /// See `Quaternion::slerp()` for more details.
+    pub fn slerp(from: Orientation, to: Orientation, t: f32) -> Orientation {
+        Orientation(Quaternion::slerp(from.0, to.0, t))
+    }
+
+    /// Gets the orientation representing the opposite rotation.
+    pub fn inverse(self) -> Orientation {
+        Orientation(self.0.conjugate())
+    }
+
     /// Retrieves the rotation represented by the quaternion as euler angles.
     pub fn as_eulers(mut self) -> Vector3 {
         self.0.normalize();
@@ -186,6 +201,66 @@ impl Default for Orientation {
     }
 }
 
+impl From<Matrix3> for Orientation {
+    /// Creates an orientation from a pure rotation matrix.
+    ///
+    /// The matrix must be orthonormal (i.e. contain no scale or skew), otherwise the resulting
+    /// orientation is meaningless.
+    fn from(m: Matrix3) -> Orientation {
+        // Implementation taken from here: http://www.euclideanspace.com/maths/geometry/rotations/conversions/matrixToQuaternion/
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = 0.5 / (trace + 1.0).sqrt();
+            Quaternion::new(
+                Vector3::new(
+                    (m[2][1] - m[1][2]) * s,
+                    (m[0][2] - m[2][0]) * s,
+                    (m[1][0] - m[0][1]) * s,
+                ),
+                0.25 / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Quaternion::new(
+                Vector3::new(
+                    0.25 * s,
+                    (m[0][1] + m[1][0]) / s,
+                    (m[0][2] + m[2][0]) / s,
+                ),
+                (m[2][1] - m[1][2]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Quaternion::new(
+                Vector3::new(
+                    (m[0][1] + m[1][0]) / s,
+                    0.25 * s,
+                    (m[1][2] + m[2][1]) / s,
+                ),
+                (m[0][2] - m[2][0]) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Quaternion::new(
+                Vector3::new(
+                    (m[0][2] + m[2][0]) / s,
+                    (m[1][2] + m[2][1]) / s,
+                    0.25 * s,
+                ),
+                (m[1][0] - m[0][1]) / s,
+            )
+        };
+
+        Orientation(q.normalized())
+    }
+}
+
+impl Lerp for Orientation {
+    fn lerp(t: f32, from: Orientation, to: Orientation) -> Orientation {
+        Orientation::slerp(from, to, t)
+    }
+}
+
 impl Add for Orientation {
     type Output = Orientation;
 
@@ -4,6 +4,7 @@ use std::mem;
 use std::f32;
 use std::slice;
 
+use Lerp;
 use vector::Vector3;
 
 /// A point in 3D space.
@@ -115,6 +116,12 @@ impl Sub for Point {
     }
 }
 
+impl Lerp for Point {
+    fn lerp(t: f32, from: Point, to: Point) -> Point {
+        from + (to - from) * t
+    }
+}
+
 impl AddAssign<Vector3> for Point {
     fn add_assign(&mut self, rhs: Vector3) {
         self.x += rhs.x;
@@ -99,6 +99,36 @@ impl Quaternion {
         first + (second - first) * t
     }
 
+    /// Spherically interpolates between two unit quaternions.
+    ///
+    /// Unlike `lerp()`, the result is always normalized and the rotation progresses at a
+    /// constant angular velocity as `t` goes from 0 to 1. Interpolation always takes the shortest
+    /// path between the two rotations.
+    pub fn slerp(first: Quaternion, second: Quaternion, t: f32) -> Quaternion {
+        // `second` and `-second` represent the same rotation, pick whichever is closer to `first`
+        // so that we take the shortest path.
+        let mut dot = Quaternion::dot(first, second);
+        let second = if dot < 0.0 {
+            dot = -dot;
+            second * -1.0
+        } else {
+            second
+        };
+
+        // If the quaternions are very close together then `sin(theta)` approaches zero, so we
+        // fall back to linear interpolation to avoid dividing by zero.
+        if dot > 0.9995 {
+            return Quaternion::lerp(first, second, t).normalized();
+        }
+
+        let theta = dot.acos();
+        let sin_theta = theta.sin();
+        let first_weight = ((1.0 - t) * theta).sin() / sin_theta;
+        let second_weight = (t * theta).sin() / sin_theta;
+
+        (first * first_weight + second * second_weight).normalized()
+    }
+
     pub fn inverse(self) -> Quaternion {
         (1.0 / self.len_sqr()) * self.conjugate()
     }
@@ -1,4 +1,5 @@
 extern crate test;
 
 mod matrix_test;
+mod orientation_test;
 mod quaternion_test;
@@ -0,0 +1,38 @@
+use orientation::Orientation;
+use vector::Vector3;
+use super::super::{Dot, IsZero};
+
+#[test]
+fn look_rotation() {
+    let directions = [
+        Vector3::new(1.0, 0.0, 0.0),
+        Vector3::new(0.0, 0.0, 1.0),
+        Vector3::new(0.0, 0.0, -1.0),
+        Vector3::new(1.0, 2.0, -3.0),
+    ];
+
+    for &direction in &directions {
+        let orientation = Orientation::look_rotation(direction, Vector3::up());
+
+        // Forward should point at the target and up should be perpendicular to it, leaning
+        // towards the requested up direction.
+        assert!((orientation.forward() - direction.normalized()).is_zero());
+        assert!(orientation.up().dot(direction).is_zero());
+        assert!(orientation.up().dot(Vector3::up()) > 0.0);
+    }
+}
+
+#[test]
+fn look_rotation_parallel_up() {
+    // When forward and up are parallel any perpendicular up is valid.
+    let orientation = Orientation::look_rotation(Vector3::up(), Vector3::up());
+    assert!((orientation.forward() - Vector3::up()).is_zero());
+    assert!(orientation.up().dot(Vector3::up()).is_zero());
+}
+
+#[test]
+fn inverse() {
+    let orientation = Orientation::from_eulers(0.5, 1.0, -0.25);
+    let vector = Vector3::new(1.0, 2.0, 3.0);
+    assert!((orientation.inverse() * (orientation * vector) - vector).is_zero());
+}
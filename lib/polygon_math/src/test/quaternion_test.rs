@@ -1,8 +1,10 @@
 use std::f32::consts::PI;
 
 use quaternion::Quaternion;
+use orientation::Orientation;
 use vector::Vector3;
 use matrix::Matrix4;
+use super::super::IsZero;
 
 #[test]
 fn multiplication() {
@@ -31,3 +33,52 @@ fn as_matrix() {
     assert_eq!(Quaternion::axis_angle(Vector3::new(0.0, 1.0, 0.0), 0.5).as_matrix(), Matrix4::rotation(0.0, 0.5, 0.0));
     assert_eq!(Quaternion::axis_angle(Vector3::new(0.0, 0.0, 1.0), 0.5).as_matrix(), Matrix4::rotation(0.0, 0.0, 0.5));
 }
+
+/// Creates a quaternion rotating `angle` radians around `axis`.
+fn rotation(axis: Vector3, angle: f32) -> Quaternion {
+    Quaternion::from(Orientation::axis_angle(axis, angle))
+}
+
+#[test]
+fn slerp_endpoints() {
+    let first = rotation(Vector3::new(1.0, 0.0, 0.0), 0.5);
+    let second = rotation(Vector3::new(0.0, 1.0, 0.0), 2.0);
+
+    assert!((Quaternion::slerp(first, second, 0.0) - first).is_zero());
+    assert!((Quaternion::slerp(first, second, 1.0) - second).is_zero());
+}
+
+#[test]
+fn slerp_constant_velocity() {
+    // Interpolating around a single axis should progress the angle linearly with `t`.
+    let axis = Vector3::new(0.0, 1.0, 0.0);
+    let first = Quaternion::identity();
+    let second = rotation(axis, PI * 0.5);
+
+    for &t in &[0.1, 0.25, 0.5, 0.9] {
+        let expected = rotation(axis, PI * 0.5 * t);
+        assert!((Quaternion::slerp(first, second, t) - expected).is_zero());
+    }
+}
+
+#[test]
+fn slerp_shortest_path() {
+    // `-second` represents the same rotation as `second`, so both should give the same result.
+    let first = Quaternion::identity();
+    let second = rotation(Vector3::new(0.0, 0.0, 1.0), 1.0);
+    let halfway = rotation(Vector3::new(0.0, 0.0, 1.0), 0.5);
+
+    assert!((Quaternion::slerp(first, second, 0.5) - halfway).is_zero());
+    assert!((Quaternion::slerp(first, second * -1.0, 0.5) - halfway).is_zero());
+}
+
+#[test]
+fn slerp_nearly_equal() {
+    // Very close rotations fall back to linear interpolation, which must still be normalized.
+    let first = rotation(Vector3::new(1.0, 0.0, 0.0), 0.5);
+    let second = rotation(Vector3::new(1.0, 0.0, 0.0), 0.5001);
+
+    let result = Quaternion::slerp(first, second, 0.5);
+    assert!((result.len() - 1.0).is_zero());
+    assert!((result - first).is_zero());
+}
@@ -219,6 +219,12 @@ impl Dot<Vector3> for [f32; 3] {
 //     }
 // }
 
+impl Lerp for Vector3 {
+    fn lerp(t: f32, from: Vector3, to: Vector3) -> Vector3 {
+        from + (to - from) * t
+    }
+}
+
 impl AddAssign for Vector3 {
     fn add_assign(&mut self, rhs: Vector3) {
         self.x += rhs.x;
@@ -0,0 +1,96 @@
+extern crate bootstrap_rs as bootstrap;
+extern crate polygon;
+
+use bootstrap::window::*;
+use polygon::*;
+use polygon::anchor::*;
+use polygon::camera::*;
+use polygon::geometry::primitive::*;
+use polygon::light::*;
+use polygon::material::*;
+use polygon::math::*;
+use polygon::mesh_instance::*;
+
+mod utils;
+
+fn main() {
+    // Open a window and create the renderer instance.
+    let mut window = Window::new("Primitives").unwrap();
+    let mut renderer = RendererBuilder::new(&window).build();
+
+    // Generate one of each primitive, laid out in two rows of four.
+    let primitives = [
+        Primitive::Cube { size: 1.2 },
+        Primitive::UvSphere { radius: 0.8, segments: 24, rings: 12 },
+        Primitive::Icosphere { radius: 0.8, subdivisions: 2 },
+        Primitive::Plane { width: 1.5, depth: 1.5, subdivisions: 4 },
+        Primitive::Cylinder { radius: 0.6, height: 1.4, segments: 24 },
+        Primitive::Cone { radius: 0.7, height: 1.4, segments: 24 },
+        Primitive::Capsule { radius: 0.5, height: 1.6, segments: 24, rings: 6 },
+        Primitive::Torus { radius: 0.6, tube_radius: 0.25, segments: 32, tube_segments: 16 },
+    ];
+
+    let texture = utils::load_texture("resources/textures/structured.bmp");
+    let gpu_texture = renderer.register_texture(&texture);
+
+    let material_source =
+        MaterialSource::from_file("resources/materials/texture_diffuse_lit.material").unwrap();
+    let mut material = renderer.build_material(material_source).unwrap();
+    material.set_color("surface_color", Color::rgb(1.0, 1.0, 1.0));
+    material.set_f32("surface_shininess", 4.0);
+    material.set_texture("surface_diffuse", gpu_texture);
+
+    let mut anchor_ids = Vec::new();
+    for (index, primitive) in primitives.iter().enumerate() {
+        let gpu_mesh = renderer.register_mesh(&primitive.build());
+
+        let mut anchor = Anchor::new();
+        anchor.set_position(primitive_position(index));
+        let anchor_id = renderer.register_anchor(anchor);
+        anchor_ids.push(anchor_id);
+
+        let mut mesh_instance = MeshInstance::with_owned_material(gpu_mesh, material.clone());
+        mesh_instance.set_anchor(anchor_id);
+        renderer.register_mesh_instance(mesh_instance);
+    }
+
+    // Create a camera and an anchor for it.
+    let mut camera_anchor = Anchor::new();
+    camera_anchor.set_position(Point::new(0.0, 0.0, 8.0));
+    let camera_anchor_id = renderer.register_anchor(camera_anchor);
+
+    let mut camera = Camera::default();
+    camera.set_anchor(camera_anchor_id);
+    renderer.register_camera(camera);
+
+    let light = Light::directional(Vector3::new(1.0, -1.0, -1.0), 1.0, Color::rgb(1.0, 1.0, 1.0));
+    renderer.register_light(light);
+    renderer.set_ambient_light(Color::rgb(0.2, 0.2, 0.2));
+
+    let mut t: f32 = 0.0;
+    'outer: loop {
+        while let Some(message) = window.next_message() {
+            match message {
+                Message::Close => break 'outer,
+                _ => {},
+            }
+        }
+
+        // Spin the primitives, tilted towards the camera so that their tops are visible.
+        for &anchor_id in &anchor_ids {
+            let anchor = renderer.get_anchor_mut(anchor_id).unwrap();
+            anchor.set_orientation(Orientation::from_eulers(0.5, t, 0.0));
+        }
+
+        renderer.draw();
+
+        t += 0.005;
+    }
+}
+
+/// Gets the position of the `index`th primitive in the grid.
+fn primitive_position(index: usize) -> Point {
+    let column = (index % 4) as f32;
+    let row = (index / 4) as f32;
+    Point::new(column * 2.0 - 3.0, 1.0 - row * 2.0, 0.0)
+}
@@ -1,5 +1,7 @@
 use anchor::AnchorId;
 use math::*;
+use post_process::PostEffect;
+use texture::GpuTexture;
 
 /// A camera in the scene.
 #[derive(Debug, Clone)]
@@ -10,6 +12,16 @@ pub struct Camera
     near: f32,
     far: f32,
 
+    projection: Projection,
+    size: f32,
+
+    viewport: Viewport,
+    depth: i32,
+    clear: ClearFlags,
+    layer_mask: u32,
+    target: Option<GpuTexture>,
+    post_effects: Option<Vec<PostEffect>>,
+
     anchor: Option<AnchorId>,
 }
 
@@ -22,15 +34,44 @@ impl Camera
             near: near,
             far: far,
 
+            projection: Projection::Perspective,
+            size: 1.0,
+
+            viewport: Viewport::default(),
+            depth: 0,
+            clear: ClearFlags::default(),
+            layer_mask: !0,
+            target: None,
+            post_effects: None,
+
             anchor: None,
         }
     }
 
+    /// Creates a new camera that uses an orthographic projection.
+    ///
+    /// `size` is half the height of the view volume in world units, the width is determined by
+    /// `aspect`.
+    pub fn orthographic(size: f32, aspect: f32, near: f32, far: f32) -> Camera {
+        Camera {
+            projection: Projection::Orthographic,
+            size: size,
+            .. Camera::new(PI / 3.0, aspect, near, far)
+        }
+    }
+
     /// Calculates the projection matrix for the camera.
     ///
     /// The projection matrix is the matrix that converts from camera space to
     /// clip space. This effectively converts the viewing frustrum into a unit cube.
     pub fn projection_matrix(&self) -> Matrix4 {
+        match self.projection {
+            Projection::Perspective => self.perspective_matrix(),
+            Projection::Orthographic => self.orthographic_matrix(),
+        }
+    }
+
+    fn perspective_matrix(&self) -> Matrix4 {
         let height = 2.0 * self.near * (self.fov * 0.5).tan();
         let width = self.aspect * height;
 
@@ -43,6 +84,78 @@ impl Camera
         projection
     }
 
+    fn orthographic_matrix(&self) -> Matrix4 {
+        let height = 2.0 * self.size;
+        let width = self.aspect * height;
+
+        let mut projection = Matrix4::new();
+        projection[0][0] = 2.0 / width;
+        projection[1][1] = 2.0 / height;
+        projection[2][2] = -2.0 / (self.far - self.near);
+        projection[2][3] = -(self.far + self.near) / (self.far - self.near);
+        projection[3][3] = 1.0;
+        projection
+    }
+
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.aspect
+    }
+
+    pub fn near(&self) -> f32 {
+        self.near
+    }
+
+    pub fn far(&self) -> f32 {
+        self.far
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
+    /// Gets the render order of the camera. Cameras with a lower depth are rendered first.
+    pub fn depth(&self) -> i32 {
+        self.depth
+    }
+
+    pub fn clear(&self) -> ClearFlags {
+        self.clear
+    }
+
+    /// Gets the set of layers that the camera renders, where bit `n` represents layer `n`.
+    pub fn layer_mask(&self) -> u32 {
+        self.layer_mask
+    }
+
+    /// Checks if the camera renders objects in the specified layer.
+    pub fn renders_layer(&self, layer: u32) -> bool {
+        debug_assert!(layer < 32, "Layer must be less than 32: {}", layer);
+        self.layer_mask & (1 << layer) != 0
+    }
+
+    /// Gets the texture the camera renders into, or `None` if it renders to the screen.
+    pub fn target(&self) -> Option<GpuTexture> {
+        self.target
+    }
+
+    /// Gets the post effects applied to the camera's image, or `None` if the camera uses the
+    /// renderer's default post effects.
+    pub fn post_effects(&self) -> Option<&[PostEffect]> {
+        self.post_effects.as_ref().map(|effects| &**effects)
+    }
+
     pub fn anchor(&self) -> Option<AnchorId> {
         self.anchor
     }
@@ -53,7 +166,7 @@ impl Camera
 
     pub fn set_fov(&mut self, fov: f32) {
         debug_assert!(fov > 0.0, "Field of view must be non-negative: {}", fov);
-        debug_assert!(fov < PI * 2.0, "Field of view must be less than 180 degrees: {}", fov);
+        debug_assert!(fov < PI, "Field of view must be less than 180 degrees: {}", fov);
         self.fov = fov;
     }
 
@@ -73,6 +186,63 @@ impl Camera
         debug_assert!(far > self.near, "Far plane distance must be greater than near plane distance, near: {}, far: {}", self.near, far);
         self.far = far;
     }
+
+    /// Sets both clip plane distances at once.
+    ///
+    /// Unlike calling `set_near()` and `set_far()` individually this never passes through an
+    /// invalid state where the near plane is beyond the far plane.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) {
+        debug_assert!(near > 0.0, "Near plane distance must be non-negative: {}", near);
+        debug_assert!(near < far, "Near plane distance must be less than far plane distance, near: {}, far: {}", near, far);
+        self.near = near;
+        self.far = far;
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    /// Sets the half-height of the view volume used by orthographic projection.
+    pub fn set_size(&mut self, size: f32) {
+        debug_assert!(size > 0.0, "Orthographic size must be non-negative: {}", size);
+        self.size = size;
+    }
+
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    pub fn set_depth(&mut self, depth: i32) {
+        self.depth = depth;
+    }
+
+    pub fn set_clear(&mut self, clear: ClearFlags) {
+        self.clear = clear;
+    }
+
+    pub fn set_layer_mask(&mut self, layer_mask: u32) {
+        self.layer_mask = layer_mask;
+    }
+
+    /// Sets the texture that the camera renders into, or `None` to render to the screen.
+    ///
+    /// The texture must have been created with `Renderer::create_render_texture()`. The viewport
+    /// is relative to the texture rather than the screen. Cameras that render into textures are
+    /// drawn before cameras that render to the screen, so other cameras see their output from
+    /// the same frame. A camera shouldn't draw objects using its own target texture.
+    pub fn set_target(&mut self, target: Option<GpuTexture>) {
+        self.target = target;
+    }
+
+    /// Sets the post effects applied to the camera's image, in order.
+    ///
+    /// `None` uses the default post effects set with `RendererBuilder::post_effects()`, and an
+    /// empty list disables post-processing for the camera. With post effects the camera's view
+    /// is always drawn opaquely over its viewport, even if its clear flags don't clear the color
+    /// buffer. See the `post_process` module for details.
+    pub fn set_post_effects(&mut self, post_effects: Option<Vec<PostEffect>>) {
+        self.post_effects = post_effects;
+    }
 }
 
 impl Default for Camera {
@@ -84,11 +254,96 @@ impl Default for Camera {
             near: 0.001,
             far: 1_000.0,
 
+            projection: Projection::Perspective,
+            size: 1.0,
+
+            viewport: Viewport::default(),
+            depth: 0,
+            clear: ClearFlags::default(),
+            layer_mask: !0,
+            target: None,
+            post_effects: None,
+
             anchor: None,
         }
     }
 }
 
+/// The type of projection used by a camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    /// Objects further from the camera appear smaller, controlled by the camera's field of view.
+    Perspective,
+
+    /// Objects appear the same size regardless of distance, controlled by the camera's size.
+    Orthographic,
+}
+
+impl Default for Projection {
+    fn default() -> Projection {
+        Projection::Perspective
+    }
+}
+
+/// The region of the render target that a camera draws to.
+///
+/// All values are normalized to the size of the render target, with `(0, 0)` being the bottom
+/// left corner and `(1, 1)` being the top right. The default viewport covers the whole target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Viewport {
+        Viewport {
+            x: x,
+            y: y,
+            width: width,
+            height: height,
+        }
+    }
+
+    /// Converts the viewport to pixel coordinates for a render target of the specified size,
+    /// returning `(x, y, width, height)`.
+    pub fn to_pixels(&self, target_width: i32, target_height: i32) -> (i32, i32, i32, i32) {
+        let x = (self.x * target_width as f32).round() as i32;
+        let y = (self.y * target_height as f32).round() as i32;
+        let right = ((self.x + self.width) * target_width as f32).round() as i32;
+        let top = ((self.y + self.height) * target_height as f32).round() as i32;
+
+        (x, y, right - x, top - y)
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Viewport {
+        Viewport::new(0.0, 0.0, 1.0, 1.0)
+    }
+}
+
+/// Specifies what a camera clears before rendering.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClearFlags {
+    /// Clears the color buffer to the specified color and clears the depth buffer.
+    Color(Color),
+
+    /// Clears only the depth buffer, drawing on top of whatever cameras rendered previously.
+    Depth,
+
+    /// Doesn't clear anything.
+    Nothing,
+}
+
+impl Default for ClearFlags {
+    fn default() -> ClearFlags {
+        ClearFlags::Color(Color::rgb(0.0, 0.0, 0.0))
+    }
+}
+
 /// Identifies an achor that has been registered with the renderer.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct CameraId(usize);
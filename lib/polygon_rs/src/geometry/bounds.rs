@@ -0,0 +1,139 @@
+//! Bounding volumes for quickly determining which parts of the scene a camera can see.
+
+use math::*;
+
+/// A sphere that contains all of an object's vertices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Point,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Creates a sphere containing all of the points.
+    ///
+    /// The sphere is centered on the bounding box of the points, which gives a tighter fit than
+    /// centering it on the origin for objects that aren't centered on their origin.
+    pub fn from_points(points: &[Point]) -> BoundingSphere {
+        if points.is_empty() {
+            return BoundingSphere {
+                center: Point::origin(),
+                radius: 0.0,
+            };
+        }
+
+        let mut min = Point::max();
+        let mut max = Point::min();
+        for point in points {
+            min.x = min.x.min(point.x);
+            min.y = min.y.min(point.y);
+            min.z = min.z.min(point.z);
+
+            max.x = max.x.max(point.x);
+            max.y = max.y.max(point.y);
+            max.z = max.z.max(point.z);
+        }
+
+        let center = Point::new(
+            (min.x + max.x) * 0.5,
+            (min.y + max.y) * 0.5,
+            (min.z + max.z) * 0.5,
+        );
+
+        let radius = points
+            .iter()
+            .map(|point| point.distance_sqr(&center))
+            .fold(0.0, f32::max)
+            .sqrt();
+
+        BoundingSphere {
+            center: center,
+            radius: radius,
+        }
+    }
+
+    /// Transforms the sphere by the specified matrix.
+    ///
+    /// The radius is scaled by the largest scale along any axis, so the transformed sphere still
+    /// contains the object when it's scaled non-uniformly.
+    pub fn transform(&self, matrix: Matrix4) -> BoundingSphere {
+        let max_scale = matrix.x_part().magnitude()
+            .max(matrix.y_part().magnitude())
+            .max(matrix.z_part().magnitude());
+
+        BoundingSphere {
+            center: self.center * matrix,
+            radius: self.radius * max_scale,
+        }
+    }
+}
+
+/// The volume of space visible to a camera, represented by six planes facing into the volume.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum from a combined view and projection matrix.
+    ///
+    /// The resulting frustum is in world space. If `matrix` is a model-view-projection matrix the
+    /// frustum will instead be in that model's local space.
+    pub fn from_matrix(matrix: Matrix4) -> Frustum {
+        let plane = |row: usize, sign: f32| {
+            Plane::new(
+                matrix[3][0] + matrix[row][0] * sign,
+                matrix[3][1] + matrix[row][1] * sign,
+                matrix[3][2] + matrix[row][2] * sign,
+                matrix[3][3] + matrix[row][3] * sign,
+            )
+        };
+
+        Frustum {
+            planes: [
+                plane(0, 1.0),  // Left.
+                plane(0, -1.0), // Right.
+                plane(1, 1.0),  // Bottom.
+                plane(1, -1.0), // Top.
+                plane(2, 1.0),  // Near.
+                plane(2, -1.0), // Far.
+            ],
+        }
+    }
+
+    /// Checks if any part of the sphere is inside the frustum.
+    ///
+    /// This is conservative: spheres near the corners of the frustum may be reported as visible
+    /// even if they're just outside of it.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance(sphere.center) >= -sphere.radius)
+    }
+}
+
+/// A plane, represented by its unit normal and its signed distance from the origin.
+#[derive(Debug, Clone, Copy)]
+struct Plane {
+    normal: Vector3,
+    offset: f32,
+}
+
+impl Plane {
+    /// Creates a normalized plane from the plane equation `ax + by + cz + d = 0`.
+    fn new(a: f32, b: f32, c: f32, d: f32) -> Plane {
+        let normal = Vector3::new(a, b, c);
+        let magnitude = normal.magnitude();
+
+        Plane {
+            normal: normal * (1.0 / magnitude),
+            offset: d / magnitude,
+        }
+    }
+
+    /// Gets the signed distance from the plane to the point, positive on the side the normal
+    /// faces.
+    fn distance(&self, point: Point) -> f32 {
+        self.normal.dot(point.as_vector3()) + self.offset
+    }
+}
@@ -1,4 +1,7 @@
+use geometry::bounds::BoundingSphere;
 use math::*;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 
 pub type MeshIndex = u32;
 
@@ -10,10 +13,12 @@ pub type MeshIndex = u32;
 pub struct Mesh {
     vertex_data: Vec<f32>,
     indices:     Vec<MeshIndex>,
+    vertex_count: usize,
 
     position: VertexAttribute,
     normal:   Option<VertexAttribute>,
     texcoord: Vec<VertexAttribute>,
+    tangent:  Option<VertexAttribute>,
 }
 
 impl Mesh {
@@ -36,6 +41,29 @@ impl Mesh {
     pub fn texcoord(&self) -> &[VertexAttribute] {
         &*self.texcoord
     }
+
+    /// Gets the tangent attribute, if the mesh was built with `MeshBuilder::generate_tangents()`.
+    ///
+    /// Tangents have four elements, see `generate_tangents()` for what the fourth one means.
+    pub fn tangent(&self) -> Option<VertexAttribute> {
+        self.tangent
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count
+    }
+
+    /// Gets the position of each vertex in the mesh.
+    pub fn positions(&self) -> &[Point] {
+        let start = self.position.offset;
+        let end = start + self.vertex_count * 4;
+        Point::slice_from_f32_slice(&self.vertex_data[start..end])
+    }
+
+    /// Calculates a sphere in the mesh's local space that contains every vertex in the mesh.
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere::from_points(self.positions())
+    }
 }
 
 /// Represents a single vertex in a mesh with all of its supported attributes.
@@ -86,6 +114,12 @@ pub enum BuildMeshError {
         expected: usize,
         actual: usize,
     },
+
+    /// Indicates that generating an attribute required another attribute that the mesh doesn't
+    /// have, e.g. generating tangents for a mesh without texcoords.
+    MissingAttribute {
+        attribute: VertexAttributeType,
+    },
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -107,13 +141,21 @@ pub enum VertexAttributeType {
 /// - Check for different data count for different attributes (e.g. if the position attribute data
 ///   for a different number of elements than the normal attribute).
 /// - Any of the indicies would be out of bounds for the given vertex data.
+///
+/// Normals and tangents can also be generated from the rest of the mesh data, see
+/// `generate_flat_normals()`, `generate_smooth_normals()` and `generate_tangents()`. The
+/// attributes are generated in `build()` once the mesh data has been validated.
 #[derive(Debug, Clone)]
 pub struct MeshBuilder {
     position_data: Vec<Point>,
     normal_data: Vec<Vector3>,
     texcoord_data: Vec<Vector2>,
+    tangent_data: Vec<[f32; 4]>,
 
     indices:  Vec<u32>,
+
+    normal_generation: Option<NormalGeneration>,
+    generate_tangents: bool,
 }
 
 impl MeshBuilder {
@@ -122,7 +164,11 @@ impl MeshBuilder {
             position_data: Vec::new(),
             normal_data:   Vec::new(),
             texcoord_data: Vec::new(),
+            tangent_data:  Vec::new(),
             indices:       Vec::new(),
+
+            normal_generation: None,
+            generate_tangents: false,
         }
     }
 
@@ -170,7 +216,43 @@ impl MeshBuilder {
         self
     }
 
-    pub fn build(self) -> Result<Mesh, BuildMeshError> {
+    /// Generates a normal for each face of the mesh, replacing any normal data.
+    ///
+    /// A vertex can only have one normal, so the vertices are split up so that no two triangles
+    /// share a vertex.
+    pub fn generate_flat_normals(mut self) -> MeshBuilder {
+        self.normal_generation = Some(NormalGeneration::Flat);
+        self
+    }
+
+    /// Generates normals that are smoothed across faces, replacing any normal data.
+    ///
+    /// The normal at each corner of a triangle is the average of the normals of the faces around
+    /// it, weighted by the angle of each face at that corner. Faces are only averaged together
+    /// if their normals are less than `angle_threshold` radians apart, so edges sharper than that
+    /// stay sharp and the vertices along them are split up. Vertices with exactly the same
+    /// position are treated as one, so normals are also smoothed across texture seams.
+    pub fn generate_smooth_normals(mut self, angle_threshold: f32) -> MeshBuilder {
+        self.normal_generation = Some(NormalGeneration::Smooth(angle_threshold));
+        self
+    }
+
+    /// Generates a tangent for each vertex for use in normal mapping.
+    ///
+    /// Tangents are calculated the same way as MikkTSpace: The tangent points along the
+    /// direction of increasing u texture coordinate and is orthogonal to the vertex normal. The
+    /// fourth element gives the handedness of the tangent space, so the bitangent is
+    /// `cross(normal, tangent.xyz) * tangent.w`. Vertices shared by triangles with mirrored
+    /// texture coordinates are split up so that each side gets the correct handedness.
+    ///
+    /// Generating tangents requires texcoords and either normal data or generated normals,
+    /// `build()` returns `BuildMeshError::MissingAttribute` if either is missing.
+    pub fn generate_tangents(mut self) -> MeshBuilder {
+        self.generate_tangents = true;
+        self
+    }
+
+    pub fn build(mut self) -> Result<Mesh, BuildMeshError> {
         // The vertex count is defined by the position data, since position is the only required
         // vertex attribute.
         let vertex_count = self.position_data.len();
@@ -204,10 +286,40 @@ impl MeshBuilder {
 
         // TODO: Check for degenerate triangles? Actually, should that be a failure or a warning?
 
+        // Generate any requested attributes. This can split vertices, so the vertex count may
+        // change.
+        if self.generate_tangents {
+            if self.texcoord_data.len() == 0 {
+                return Err(BuildMeshError::MissingAttribute {
+                    attribute: VertexAttributeType::Texcoord,
+                });
+            }
+
+            if self.normal_data.len() == 0 && self.normal_generation.is_none() {
+                return Err(BuildMeshError::MissingAttribute {
+                    attribute: VertexAttributeType::Normal,
+                });
+            }
+        }
+
+        match self.normal_generation {
+            Some(NormalGeneration::Flat) => self.build_flat_normals(),
+            Some(NormalGeneration::Smooth(threshold)) => self.build_smooth_normals(threshold),
+            None => {},
+        }
+
+        self.tangent_data.clear();
+        if self.generate_tangents {
+            self.build_tangents();
+        }
+
+        let vertex_count = self.position_data.len();
+
         let float_count =
             self.position_data.len() * 4
           + self.normal_data.len() * 3
-          + self.texcoord_data.len() * 2;
+          + self.texcoord_data.len() * 2
+          + self.tangent_data.len() * 4;
 
         // Create the mesh.
         let mut vertex_data = Vec::<f32>::with_capacity(float_count);
@@ -245,14 +357,292 @@ impl MeshBuilder {
             vertex_data.extend(Vector2::as_ref(&*self.texcoord_data));
         }
 
+        // Setup tangent data.
+        let tangent_attrib = if self.tangent_data.len() > 0 {
+            let attrib = VertexAttribute {
+                elements: 4,
+                offset: vertex_data.len(),
+                stride: 0,
+            };
+            for tangent in &self.tangent_data {
+                vertex_data.extend(tangent);
+            }
+
+            Some(attrib)
+        } else {
+            None
+        };
+
         // By our powers combined! We are! A mesh.
         Ok(Mesh {
             vertex_data: vertex_data,
             indices: self.indices,
+            vertex_count: vertex_count,
 
             position: position_attrib,
             normal: normal_attrib,
             texcoord: texcoord_attribs,
+            tangent: tangent_attrib,
         })
     }
+
+    /// Splits every vertex so that each triangle has its own vertices, and gives them the normal
+    /// of their triangle.
+    fn build_flat_normals(&mut self) {
+        let mut sources = Vec::with_capacity(self.indices.len());
+        let mut normals = Vec::with_capacity(self.indices.len());
+        for triangle in 0..self.indices.len() / 3 {
+            let normal = normalize(self.face_normal(triangle)).unwrap_or(Vector3::up());
+            for corner in triangle * 3..triangle * 3 + 3 {
+                sources.push(self.indices[corner]);
+                normals.push(normal);
+            }
+        }
+
+        self.indices = (0..sources.len() as MeshIndex).collect();
+        self.remap_vertices(&sources);
+        self.normal_data = normals;
+    }
+
+    fn build_smooth_normals(&mut self, angle_threshold: f32) {
+        let corner_count = self.indices.len() / 3 * 3;
+        let min_cos = angle_threshold.cos();
+
+        // Gather the normal of each face and the angle at each of its corners.
+        let face_normals: Vec<Vector3> =
+            (0..corner_count / 3)
+            .map(|triangle| normalize(self.face_normal(triangle)).unwrap_or(Vector3::zero()))
+            .collect();
+        let corner_angles: Vec<f32> =
+            (0..corner_count)
+            .map(|corner| self.corner_angle(corner))
+            .collect();
+
+        // Group corners by position so that normals are smoothed between vertices that only
+        // differ in their other attributes.
+        let mut corners_at_position = HashMap::new();
+        for corner in 0..corner_count {
+            let position = self.position_data[self.indices[corner] as usize];
+            corners_at_position
+                .entry(position_key(position))
+                .or_insert(Vec::new())
+                .push(corner);
+        }
+
+        // Corners that share a vertex and end up with the same normal keep sharing a vertex,
+        // otherwise the vertex gets split.
+        let mut vertices = HashMap::new();
+        let mut sources = Vec::new();
+        let mut normals = Vec::new();
+        let mut indices = Vec::with_capacity(corner_count);
+        for corner in 0..corner_count {
+            let vertex = self.indices[corner];
+            let face_normal = face_normals[corner / 3];
+            let position = self.position_data[vertex as usize];
+
+            let mut normal = Vector3::zero();
+            for &other in &corners_at_position[&position_key(position)] {
+                let other_normal = face_normals[other / 3];
+                if face_normal.dot(other_normal) >= min_cos {
+                    normal += other_normal * corner_angles[other];
+                }
+            }
+            let normal = normalize(normal).unwrap_or(Vector3::up());
+
+            let index = match vertices.entry((vertex, position_key(Point::from(normal)))) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    sources.push(vertex);
+                    normals.push(normal);
+                    *entry.insert(sources.len() as MeshIndex - 1)
+                },
+            };
+            indices.push(index);
+        }
+
+        self.indices = indices;
+        self.remap_vertices(&sources);
+        self.normal_data = normals;
+    }
+
+    fn build_tangents(&mut self) {
+        let corner_count = self.indices.len() / 3 * 3;
+
+        // Calculate each corner's contribution to the tangent of its vertex, and the handedness
+        // of its triangle's tangent space. Triangles with degenerate texture coordinates don't
+        // have a tangent space.
+        let mut contributions = Vec::with_capacity(corner_count);
+        let mut handedness = Vec::with_capacity(corner_count);
+        for triangle in 0..corner_count / 3 {
+            let corners = triangle * 3..triangle * 3 + 3;
+            match self.face_tangent(triangle) {
+                Some((tangent, bitangent)) => {
+                    for corner in corners {
+                        let normal = self.normal_data[self.indices[corner] as usize];
+                        let projected = normalize(tangent - normal * normal.dot(tangent))
+                            .unwrap_or(Vector3::zero());
+                        contributions.push(projected * self.corner_angle(corner));
+                        handedness.push(Some(Vector3::cross(normal, tangent).dot(bitangent) >= 0.0));
+                    }
+                },
+                None => {
+                    for _ in corners {
+                        contributions.push(Vector3::zero());
+                        handedness.push(None);
+                    }
+                },
+            }
+        }
+
+        // Split vertices that are shared by triangles with different handedness. Corners without
+        // a tangent space join whichever copy of their vertex already exists.
+        let mut vertices = HashMap::new();
+        let mut sources = Vec::new();
+        let mut tangents = Vec::new();
+        let mut indices = vec![0; corner_count];
+        for &deferred in &[false, true] {
+            for corner in 0..corner_count {
+                if handedness[corner].is_none() != deferred {
+                    continue;
+                }
+
+                let vertex = self.indices[corner];
+                let right_handed = match handedness[corner] {
+                    Some(right_handed) => right_handed,
+                    None => {
+                        vertices.contains_key(&(vertex, true))
+                            || !vertices.contains_key(&(vertex, false))
+                    },
+                };
+
+                let index = match vertices.entry((vertex, right_handed)) {
+                    Entry::Occupied(entry) => *entry.get(),
+                    Entry::Vacant(entry) => {
+                        sources.push(vertex);
+                        tangents.push((Vector3::zero(), right_handed));
+                        *entry.insert(sources.len() as MeshIndex - 1)
+                    },
+                };
+                tangents[index as usize].0 += contributions[corner];
+                indices[corner] = index;
+            }
+        }
+
+        self.indices = indices;
+        self.remap_vertices(&sources);
+
+        // Orthogonalize the averaged tangents against the normals, falling back to an arbitrary
+        // tangent for vertices that didn't get one.
+        self.tangent_data =
+            tangents
+            .iter()
+            .zip(self.normal_data.iter())
+            .map(|(&(tangent, right_handed), &normal)| {
+                let tangent = normalize(tangent - normal * normal.dot(tangent))
+                    .or_else(|| normalize(Vector3::cross(normal, Vector3::right())))
+                    .or_else(|| normalize(Vector3::cross(normal, Vector3::up())))
+                    .unwrap_or(Vector3::right());
+                let w = if right_handed { 1.0 } else { -1.0 };
+                [tangent.x, tangent.y, tangent.z, w]
+            })
+            .collect();
+    }
+
+    /// Replaces the vertex data so that the vertex at index `n` is a copy of the vertex at index
+    /// `sources[n]`. Doesn't touch the indices.
+    fn remap_vertices(&mut self, sources: &[MeshIndex]) {
+        self.position_data = remap(&self.position_data, sources);
+        self.normal_data = remap(&self.normal_data, sources);
+        self.texcoord_data = remap(&self.texcoord_data, sources);
+        self.tangent_data = remap(&self.tangent_data, sources);
+    }
+
+    fn triangle_positions(&self, triangle: usize) -> [Point; 3] {
+        [
+            self.position_data[self.indices[triangle * 3] as usize],
+            self.position_data[self.indices[triangle * 3 + 1] as usize],
+            self.position_data[self.indices[triangle * 3 + 2] as usize],
+        ]
+    }
+
+    /// Calculates the unnormalized normal of a triangle, facing the side it's wound
+    /// counter-clockwise on.
+    fn face_normal(&self, triangle: usize) -> Vector3 {
+        let positions = self.triangle_positions(triangle);
+        Vector3::cross(positions[1] - positions[0], positions[2] - positions[0])
+    }
+
+    /// Calculates the angle in radians of a triangle at one of its corners.
+    fn corner_angle(&self, corner: usize) -> f32 {
+        let positions = self.triangle_positions(corner / 3);
+        let index = corner % 3;
+        let position = positions[index];
+        let first = normalize(positions[(index + 1) % 3] - position);
+        let second = normalize(positions[(index + 2) % 3] - position);
+        match (first, second) {
+            (Some(first), Some(second)) => first.dot(second).max(-1.0).min(1.0).acos(),
+            _ => 0.0,
+        }
+    }
+
+    /// Calculates the directions of increasing u and v texture coordinates across a triangle,
+    /// returning `None` if the triangle's texture coordinates are degenerate.
+    fn face_tangent(&self, triangle: usize) -> Option<(Vector3, Vector3)> {
+        let positions = self.triangle_positions(triangle);
+        let texcoords = [
+            self.texcoord_data[self.indices[triangle * 3] as usize],
+            self.texcoord_data[self.indices[triangle * 3 + 1] as usize],
+            self.texcoord_data[self.indices[triangle * 3 + 2] as usize],
+        ];
+
+        let first_edge = positions[1] - positions[0];
+        let second_edge = positions[2] - positions[0];
+        let first_uv = texcoords[1] - texcoords[0];
+        let second_uv = texcoords[2] - texcoords[0];
+
+        let determinant = first_uv.x * second_uv.y - second_uv.x * first_uv.y;
+        if determinant == 0.0 {
+            return None;
+        }
+
+        let tangent = (first_edge * second_uv.y - second_edge * first_uv.y) / determinant;
+        let bitangent = (second_edge * first_uv.x - first_edge * second_uv.x) / determinant;
+        Some((tangent, bitangent))
+    }
+}
+
+/// The ways `MeshBuilder` can generate normals.
+#[derive(Debug, Clone, Copy)]
+enum NormalGeneration {
+    Flat,
+    Smooth(f32),
+}
+
+/// Normalizes `vector`, returning `None` if it's the zero vector.
+fn normalize(vector: Vector3) -> Option<Vector3> {
+    let magnitude = vector.magnitude();
+    if magnitude > 0.0 {
+        Some(vector / magnitude)
+    } else {
+        None
+    }
+}
+
+/// Converts a position to a key for looking up vertices with exactly the same position.
+fn position_key(position: Point) -> [u32; 3] {
+    // Adding zero turns negative zero into positive zero, so that they produce the same key.
+    [
+        (position.x + 0.0).to_bits(),
+        (position.y + 0.0).to_bits(),
+        (position.z + 0.0).to_bits(),
+    ]
+}
+
+/// Copies the elements of `data` in the order given by `sources`, leaving empty data empty.
+fn remap<T: Copy>(data: &[T], sources: &[MeshIndex]) -> Vec<T> {
+    if data.len() == 0 {
+        Vec::new()
+    } else {
+        sources.iter().map(|&index| data[index as usize]).collect()
+    }
 }
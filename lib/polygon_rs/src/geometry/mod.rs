@@ -1 +1,3 @@
+pub mod bounds;
 pub mod mesh;
+pub mod primitive;
@@ -0,0 +1,456 @@
+//! Generators for common shapes.
+//!
+//! Each generator builds a `Mesh` with normals and texcoords, centered on the origin with the
+//! y axis pointing up. Faces are wound counter-clockwise when seen from outside the shape.
+//!
+//! Curved surfaces are approximated with a number of segments around the shape and rings from
+//! bottom to top, more of either gives a smoother shape at the cost of more vertices. Texcoords
+//! wrap once around curved surfaces, starting at the positive x axis, and go from `v = 0` at the
+//! bottom of the shape to `v = 1` at the top.
+
+use geometry::mesh::{Mesh, MeshBuilder, MeshIndex};
+use math::*;
+use std::cmp;
+use std::collections::HashMap;
+
+/// Describes one of the shapes that can be generated, see the function of the same name for
+/// each shape for details.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    Cube { size: f32 },
+    UvSphere { radius: f32, segments: usize, rings: usize },
+    Icosphere { radius: f32, subdivisions: usize },
+    Plane { width: f32, depth: f32, subdivisions: usize },
+    Cylinder { radius: f32, height: f32, segments: usize },
+    Cone { radius: f32, height: f32, segments: usize },
+    Capsule { radius: f32, height: f32, segments: usize, rings: usize },
+    Torus { radius: f32, tube_radius: f32, segments: usize, tube_segments: usize },
+}
+
+impl Primitive {
+    /// Generates the mesh for the shape.
+    pub fn build(&self) -> Mesh {
+        match *self {
+            Primitive::Cube { size } => cube(size),
+            Primitive::UvSphere { radius, segments, rings } => uv_sphere(radius, segments, rings),
+            Primitive::Icosphere { radius, subdivisions } => icosphere(radius, subdivisions),
+            Primitive::Plane { width, depth, subdivisions } => plane(width, depth, subdivisions),
+            Primitive::Cylinder { radius, height, segments } => cylinder(radius, height, segments),
+            Primitive::Cone { radius, height, segments } => cone(radius, height, segments),
+            Primitive::Capsule { radius, height, segments, rings } => capsule(radius, height, segments, rings),
+            Primitive::Torus { radius, tube_radius, segments, tube_segments } => {
+                torus(radius, tube_radius, segments, tube_segments)
+            },
+        }
+    }
+}
+
+/// Generates a cube with sides of length `size`.
+///
+/// Each face has its own vertices so that the edges are sharp, and the whole texture is mapped
+/// onto every face.
+pub fn cube(size: f32) -> Mesh {
+    // The normal of each face, followed by the directions of the face's u and v texcoord axes.
+    let faces = [
+        (Vector3::right(), Vector3::forward(), Vector3::up()),
+        (Vector3::left(), Vector3::back(), Vector3::up()),
+        (Vector3::up(), Vector3::right(), Vector3::forward()),
+        (Vector3::down(), Vector3::right(), Vector3::back()),
+        (Vector3::back(), Vector3::right(), Vector3::up()),
+        (Vector3::forward(), Vector3::left(), Vector3::up()),
+    ];
+
+    let mut shape = Shape::new();
+    for &(normal, u_axis, v_axis) in &faces {
+        shape.add_grid(1, 1, |u, v| {
+            let offset = normal * 0.5 + u_axis * (u - 0.5) + v_axis * (v - 0.5);
+            (Point::from(offset * size), normal, Vector2::new(u, v))
+        });
+    }
+
+    shape.build()
+}
+
+/// Generates a sphere made of `segments` columns of quads around the y axis and `rings` rows of
+/// quads from pole to pole.
+///
+/// # Panics
+///
+/// Panics if `segments` is less than 3 or `rings` is less than 2.
+pub fn uv_sphere(radius: f32, segments: usize, rings: usize) -> Mesh {
+    assert!(segments >= 3, "A sphere needs at least 3 segments");
+    assert!(rings >= 2, "A sphere needs at least 2 rings");
+
+    let mut shape = Shape::new();
+    shape.add_grid(segments, rings, |u, v| {
+        let normal = sphere_normal(u, v);
+        (Point::from(normal * radius), normal, Vector2::new(u, v))
+    });
+
+    shape.build()
+}
+
+/// Generates a sphere by repeatedly subdividing the faces of an icosahedron.
+///
+/// Unlike `uv_sphere()` the triangles are all close to the same size, so the vertices are spread
+/// evenly over the sphere. Each subdivision splits every triangle into four, a sphere with no
+/// subdivisions is an icosahedron.
+pub fn icosphere(radius: f32, subdivisions: usize) -> Mesh {
+    let t = (1.0 + 5.0f32.sqrt()) * 0.5;
+    let mut positions: Vec<Vector3> = [
+        (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+        (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+        (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+    ]
+        .iter()
+        .map(|&position| Vector3::from(position).normalized())
+        .collect();
+
+    let mut triangles: Vec<[usize; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // Edges are shared by two triangles, so the vertex in the middle of each edge is cached
+        // to be reused by the second triangle.
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |positions: &mut Vec<Vector3>, from: usize, to: usize| {
+            let key = (cmp::min(from, to), cmp::max(from, to));
+            *midpoints.entry(key).or_insert_with(|| {
+                positions.push(((positions[from] + positions[to]) * 0.5).normalized());
+                positions.len() - 1
+            })
+        };
+
+        let mut subdivided = Vec::with_capacity(triangles.len() * 4);
+        for triangle in &triangles {
+            let (a, b, c) = (triangle[0], triangle[1], triangle[2]);
+            let ab = midpoint(&mut positions, a, b);
+            let bc = midpoint(&mut positions, b, c);
+            let ca = midpoint(&mut positions, c, a);
+
+            subdivided.push([a, ab, ca]);
+            subdivided.push([b, bc, ab]);
+            subdivided.push([c, ca, bc]);
+            subdivided.push([ab, bc, ca]);
+        }
+        triangles = subdivided;
+    }
+
+    // The texcoords wrap around the sphere, so triangles crossing the seam where `u` wraps from
+    // 1 back to 0 need their own copies of the vertices on the `u = 0` side, with `u` past 1.
+    // The vertices at the poles have no well defined `u`, so each triangle gets its own copy with
+    // the `u` of the triangle's other corners.
+    let mut shape = Shape::new();
+    let mut vertices = HashMap::new();
+    for triangle in &triangles {
+        let is_pole = |corner: usize| positions[triangle[corner]].y.abs() > 1.0 - EPSILON;
+
+        let mut texcoords = [Vector2::new(0.0, 0.0); 3];
+        for corner in 0..3 {
+            let normal = positions[triangle[corner]];
+            let u = (-normal.z).atan2(normal.x) / TAU;
+            texcoords[corner] = Vector2::new(if u < 0.0 { u + 1.0 } else { u }, 0.5 + normal.y.asin() / PI);
+        }
+
+        let crosses_seam = (0..3).any(|corner| !is_pole(corner) && texcoords[corner].x > 0.75)
+            && (0..3).any(|corner| !is_pole(corner) && texcoords[corner].x < 0.25);
+
+        let mut wrapped = [false; 3];
+        for corner in 0..3 {
+            if crosses_seam && !is_pole(corner) && texcoords[corner].x < 0.5 {
+                texcoords[corner].x += 1.0;
+                wrapped[corner] = true;
+            }
+        }
+
+        let mut corners = [0; 3];
+        for corner in 0..3 {
+            let index = triangle[corner];
+            let position = Point::from(positions[index] * radius);
+
+            if is_pole(corner) {
+                let (first, second) = ((corner + 1) % 3, (corner + 2) % 3);
+                let mut texcoord = texcoords[corner];
+                texcoord.x = (texcoords[first].x + texcoords[second].x) * 0.5;
+                corners[corner] = shape.add_vertex(position, positions[index], texcoord);
+                continue;
+            }
+
+            let texcoord = texcoords[corner];
+            corners[corner] = *vertices.entry((index, wrapped[corner])).or_insert_with(|| {
+                shape.add_vertex(position, positions[index], texcoord)
+            });
+        }
+
+        shape.add_triangle(corners[0], corners[1], corners[2]);
+    }
+
+    shape.build()
+}
+
+/// Generates a flat plane facing up, `width` along the x axis and `depth` along the z axis.
+///
+/// Each side of the plane is split into `subdivisions` quads, which gives more vertices for
+/// lighting or deforming the plane. The texcoords go from `(0, 0)` at the corner closest to
+/// negative x and positive z to `(1, 1)` at the opposite corner.
+///
+/// # Panics
+///
+/// Panics if `subdivisions` is 0.
+pub fn plane(width: f32, depth: f32, subdivisions: usize) -> Mesh {
+    assert!(subdivisions >= 1, "A plane needs at least 1 subdivision");
+
+    let mut shape = Shape::new();
+    shape.add_grid(subdivisions, subdivisions, |u, v| {
+        let position = Point::new((u - 0.5) * width, 0.0, (0.5 - v) * depth);
+        (position, Vector3::up(), Vector2::new(u, v))
+    });
+
+    shape.build()
+}
+
+/// Generates a cylinder around the y axis with flat caps at the top and bottom.
+///
+/// The texture is wrapped once around the side of the cylinder and projected straight down onto
+/// the caps.
+///
+/// # Panics
+///
+/// Panics if `segments` is less than 3.
+pub fn cylinder(radius: f32, height: f32, segments: usize) -> Mesh {
+    assert!(segments >= 3, "A cylinder needs at least 3 segments");
+
+    let mut shape = Shape::new();
+    shape.add_grid(segments, 1, |u, v| {
+        let normal = around_y(u);
+        let position = Point::from(normal * radius) + Vector3::up() * (v - 0.5) * height;
+        (position, normal, Vector2::new(u, v))
+    });
+
+    shape.add_cap(Point::new(0.0, height * 0.5, 0.0), radius, segments, Vector3::up());
+    shape.add_cap(Point::new(0.0, height * -0.5, 0.0), radius, segments, Vector3::down());
+
+    shape.build()
+}
+
+/// Generates a cone around the y axis, with its flat base at the bottom and its tip at the top.
+///
+/// # Panics
+///
+/// Panics if `segments` is less than 3.
+pub fn cone(radius: f32, height: f32, segments: usize) -> Mesh {
+    assert!(segments >= 3, "A cone needs at least 3 segments");
+
+    let mut shape = Shape::new();
+    shape.add_grid(segments, 1, |u, v| {
+        let outward = around_y(u);
+        let normal = (outward * height + Vector3::up() * radius).normalized();
+        let position = Point::from(outward * radius * (1.0 - v)) + Vector3::up() * (v - 0.5) * height;
+        (position, normal, Vector2::new(u, v))
+    });
+
+    shape.add_cap(Point::new(0.0, height * -0.5, 0.0), radius, segments, Vector3::down());
+
+    shape.build()
+}
+
+/// Generates a cylinder around the y axis with a half sphere on each end.
+///
+/// `height` is the total height of the capsule including the rounded ends, so it's at least
+/// `radius * 2`. `rings` is the number of rows of quads in each of the rounded ends.
+///
+/// # Panics
+///
+/// Panics if `segments` is less than 3 or `rings` is 0.
+pub fn capsule(radius: f32, height: f32, segments: usize, rings: usize) -> Mesh {
+    assert!(segments >= 3, "A capsule needs at least 3 segments");
+    assert!(rings >= 1, "A capsule needs at least 1 ring");
+
+    let cylinder_height = (height - radius * 2.0).max(0.0);
+    let total_height = cylinder_height + radius * 2.0;
+
+    // The grid has a row of quads for each ring in the two ends, plus one row for the straight
+    // part in the middle.
+    let rows = rings * 2 + 1;
+    let mut shape = Shape::new();
+    shape.add_grid(segments, rows, |u, v| {
+        let row = (v * rows as f32).round() as usize;
+        let (sphere_v, offset) = if row <= rings {
+            (row as f32 / (rings * 2) as f32, cylinder_height * -0.5)
+        } else {
+            ((row - 1) as f32 / (rings * 2) as f32, cylinder_height * 0.5)
+        };
+
+        let normal = sphere_normal(u, sphere_v);
+        let position = Point::from(normal * radius) + Vector3::up() * offset;
+        let texcoord = Vector2::new(u, position.y / total_height + 0.5);
+        (position, normal, texcoord)
+    });
+
+    shape.build()
+}
+
+/// Generates a ring around the y axis.
+///
+/// `radius` is the distance from the center of the torus to the center of the tube, and
+/// `tube_segments` is the number of quads around the tube. The texture's v axis wraps around the
+/// tube, starting at the outside edge of the torus.
+///
+/// # Panics
+///
+/// Panics if `segments` or `tube_segments` is less than 3.
+pub fn torus(radius: f32, tube_radius: f32, segments: usize, tube_segments: usize) -> Mesh {
+    assert!(segments >= 3, "A torus needs at least 3 segments");
+    assert!(tube_segments >= 3, "A torus needs at least 3 tube segments");
+
+    let mut shape = Shape::new();
+    shape.add_grid(segments, tube_segments, |u, v| {
+        let outward = around_y(u);
+        let angle = v * TAU;
+        let normal = outward * angle.cos() + Vector3::up() * angle.sin();
+        let position = Point::from(outward * radius + normal * tube_radius);
+        (position, normal, Vector2::new(u, v))
+    });
+
+    shape.build()
+}
+
+/// Gets the direction pointing away from the y axis at `u` of the way around it.
+fn around_y(u: f32) -> Vector3 {
+    let angle = u * TAU;
+    Vector3::new(angle.cos(), 0.0, -angle.sin())
+}
+
+/// Gets the point on a unit sphere at `u` of the way around the y axis and `v` of the way from
+/// the bottom pole to the top one.
+fn sphere_normal(u: f32, v: f32) -> Vector3 {
+    // The poles are placed exactly so that the triangles that collapse into them can be found.
+    if v <= 0.0 {
+        return Vector3::down();
+    } else if v >= 1.0 {
+        return Vector3::up();
+    }
+
+    let angle = v * PI;
+    around_y(u) * angle.sin() - Vector3::up() * angle.cos()
+}
+
+/// Gathers the vertices and triangles of a shape.
+struct Shape {
+    positions: Vec<Point>,
+    normals: Vec<Vector3>,
+    texcoords: Vec<Vector2>,
+    indices: Vec<MeshIndex>,
+}
+
+impl Shape {
+    fn new() -> Shape {
+        Shape {
+            positions: Vec::new(),
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Adds a vertex, returning its index.
+    fn add_vertex(&mut self, position: Point, normal: Vector3, texcoord: Vector2) -> MeshIndex {
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.texcoords.push(texcoord);
+        (self.positions.len() - 1) as MeshIndex
+    }
+
+    /// Adds a triangle with counter-clockwise winding.
+    ///
+    /// Triangles with two corners in the same place, e.g. where a grid collapses into the pole of
+    /// a sphere, aren't visible so they're skipped.
+    fn add_triangle(&mut self, a: MeshIndex, b: MeshIndex, c: MeshIndex) {
+        let is_degenerate = {
+            let position = |index: MeshIndex| self.positions[index as usize];
+            position(a) == position(b) || position(b) == position(c) || position(c) == position(a)
+        };
+        if is_degenerate {
+            return;
+        }
+
+        self.indices.extend(&[a, b, c]);
+    }
+
+    /// Adds a grid of `columns` by `rows` quads.
+    ///
+    /// `vertex` gives the position, normal, and texcoord of the vertex at `u` of the way along
+    /// the columns and `v` of the way along the rows, both in the range `[0, 1]`. The quads face
+    /// the side from which the direction of increasing `u` turns counter-clockwise into the
+    /// direction of increasing `v`.
+    fn add_grid<F>(&mut self, columns: usize, rows: usize, vertex: F)
+        where F: Fn(f32, f32) -> (Point, Vector3, Vector2)
+    {
+        let first = self.positions.len() as MeshIndex;
+        for row in 0..rows + 1 {
+            for column in 0..columns + 1 {
+                let (position, normal, texcoord) = vertex(column as f32 / columns as f32, row as f32 / rows as f32);
+                self.add_vertex(position, normal, texcoord);
+            }
+        }
+
+        let index = |column: usize, row: usize| first + (row * (columns + 1) + column) as MeshIndex;
+        for row in 0..rows {
+            for column in 0..columns {
+                let bottom_left = index(column, row);
+                let bottom_right = index(column + 1, row);
+                let top_right = index(column + 1, row + 1);
+                let top_left = index(column, row + 1);
+
+                self.add_triangle(bottom_left, bottom_right, top_right);
+                self.add_triangle(bottom_left, top_right, top_left);
+            }
+        }
+    }
+
+    /// Adds a flat disc around the y axis facing `normal`, which must point straight up or down.
+    ///
+    /// The texture is projected onto the disc along the y axis, the same way `plane()` maps it
+    /// when seen from the side the disc faces.
+    fn add_cap(&mut self, center: Point, radius: f32, segments: usize, normal: Vector3) {
+        let facing_up = normal.y > 0.0;
+        let texcoord = |position: Point| {
+            let u = (position.x - center.x) / (radius * 2.0);
+            let v = (center.z - position.z) / (radius * 2.0);
+            Vector2::new(if facing_up { 0.5 + u } else { 0.5 - u }, 0.5 + v)
+        };
+
+        let center_index = self.add_vertex(center, normal, texcoord(center));
+        let first = self.positions.len() as MeshIndex;
+        for segment in 0..segments {
+            let position = center + around_y(segment as f32 / segments as f32) * radius;
+            self.add_vertex(position, normal, texcoord(position));
+        }
+
+        // The edge runs counter-clockwise when seen from above, so it has to be reversed for caps
+        // facing down.
+        for segment in 0..segments {
+            let current = first + segment as MeshIndex;
+            let next = first + ((segment + 1) % segments) as MeshIndex;
+            if facing_up {
+                self.add_triangle(center_index, current, next);
+            } else {
+                self.add_triangle(center_index, next, current);
+            }
+        }
+    }
+
+    fn build(self) -> Mesh {
+        MeshBuilder::new()
+            .set_position_data(&*self.positions)
+            .set_normal_data(&*self.normals)
+            .set_texcoord_data(&*self.texcoords)
+            .set_indices(&*self.indices)
+            .build()
+            .expect("Generated primitive had invalid mesh data")
+    }
+}
@@ -4,13 +4,16 @@ use {BuildMaterialError, Counter, GpuMesh, Renderer};
 use anchor::*;
 use bootstrap::window::Window;
 use camera::*;
-use geometry::mesh::{Mesh, VertexAttribute};
+use geometry::bounds::{BoundingSphere, Frustum};
+use geometry::mesh::{Mesh, MeshBuilder, VertexAttribute};
 use light::*;
 use material::*;
 use mesh_instance::*;
 use math::*;
+use post_process::PostEffect;
 use self::gl_util::*;
 use self::gl_util::context::{Context, Error as ContextError};
+use self::gl_util::framebuffer::{Framebuffer, Renderbuffer};
 use self::gl_util::shader::*;
 use self::gl_util::shader::Shader as GlShader;
 use self::gl_util::texture::{
@@ -19,13 +22,42 @@ use self::gl_util::texture::{
     TextureInternalFormat,
 };
 use shader::Shader;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::str;
-use stopwatch::Stopwatch;
+use stopwatch::{self, Stopwatch};
 use texture::*;
 
+#[cfg(test)]
+mod test;
+
 static DEFAULT_SHADER_BYTES: &'static [u8] = include_bytes!("../../resources/materials/diffuse_lit.material");
 
+static TONE_MAPPING_BYTES: &'static [u8] = include_bytes!("../../resources/post_process/tone_mapping.material");
+static GAMMA_CORRECTION_BYTES: &'static [u8] = include_bytes!("../../resources/post_process/gamma_correction.material");
+static BLOOM_BYTES: &'static [u8] = include_bytes!("../../resources/post_process/bloom.material");
+static FXAA_BYTES: &'static [u8] = include_bytes!("../../resources/post_process/fxaa.material");
+static COLOR_GRADING_BYTES: &'static [u8] = include_bytes!("../../resources/post_process/color_grading.material");
+
+/// The maximum number of lights that can affect a single mesh instance.
+///
+/// This must match the size of the light uniform arrays in `BUILT_IN_UNIFORMS`.
+const MAX_LIGHTS_PER_OBJECT: usize = 8;
+
+/// The number of shadow map tiles along each side of the shadow atlas.
+///
+/// Each shadow casting light renders its shadow map into one tile of the atlas, so at most
+/// `SHADOW_ATLAS_TILES * SHADOW_ATLAS_TILES` lights cast shadows at once.
+const SHADOW_ATLAS_TILES: usize = 2;
+
+/// The minimum number of visible mesh instances sharing a mesh and material before they're drawn
+/// with a single instanced draw call instead of one draw call each.
+const MIN_INSTANCED_BATCH: usize = 4;
+
+/// The number of `f32` elements of per-instance data for instanced rendering: a 4x4 model
+/// transform followed by a 3x3 normal transform.
+const INSTANCE_ELEMENTS: usize = 16 + 9;
+
 #[derive(Debug)]
 pub struct GlRender {
     context: Context,
@@ -33,12 +65,22 @@ pub struct GlRender {
     shared_materials: HashMap<MaterialId, Material>,
     meshes: HashMap<GpuMesh, MeshData>,
     textures: HashMap<GpuTexture, GlTexture2d>,
+
+    /// The framebuffers for textures created with `create_render_texture()`.
+    render_targets: HashMap<GpuTexture, RenderTarget>,
+
     mesh_instances: HashMap<MeshInstanceId, MeshInstance>,
     anchors: HashMap<AnchorId, Anchor>,
     cameras: HashMap<CameraId, Camera>,
     lights: HashMap<LightId, Light>,
     programs: HashMap<Shader, Program>,
 
+    /// Programs for drawing many mesh instances with one draw call, see `render_instanced()`.
+    ///
+    /// A material's shader may not have an instanced program if its vertex shader couldn't be
+    /// compiled for instancing, in which case its mesh instances are always drawn one at a time.
+    instanced_programs: HashMap<Shader, Program>,
+
     mesh_instances_with_shared_materials: HashMap<MaterialId, Vec<MeshInstanceId>>,
     mesh_instances_with_owned_material: Vec<MeshInstanceId>,
 
@@ -53,6 +95,35 @@ pub struct GlRender {
 
     ambient_color: Color,
 
+    /// The depth-only program used to render shadow maps, stored in `programs`.
+    shadow_shader: Shader,
+    shadow_settings: ShadowSettings,
+
+    /// The texture that shadow maps are rendered into. This is only `None` while the renderer is
+    /// being dropped.
+    shadow_atlas: Option<ShadowAtlas>,
+
+    /// The post effects for cameras that don't specify their own.
+    default_post_effects: Vec<PostEffect>,
+
+    /// The materials for the built-in post effects. This is only `None` while the renderer is
+    /// being created.
+    post_materials: Option<PostMaterials>,
+
+    /// A quad covering the whole viewport, used to draw post effects.
+    post_quad: GpuMesh,
+
+    /// The offscreen buffers for cameras with post effects, keyed by the size of the camera's
+    /// viewport in pixels.
+    post_targets: HashMap<(i32, i32), PostTargets>,
+
+    /// Whether the next call to `draw()` should save the window's contents, see
+    /// `capture_next_frame()`.
+    capture_next_frame: bool,
+
+    /// The width, height, and RGBA pixels of the last frame that was captured.
+    captured_frame: Option<(usize, usize, Vec<u8>)>,
+
     default_material: Material,
 }
 
@@ -70,17 +141,32 @@ impl GlRender {
             context.clear();
         }
 
+        let shadow_program = {
+            let _s = Stopwatch::new("Building shadow program");
+            build_shadow_program(&context)
+        };
+
+        let shadow_settings = ShadowSettings::default();
+        let shadow_atlas = ShadowAtlas::new(&context, shadow_settings.resolution);
+
+        let mut shader_counter = Shader::initial();
+        let shadow_shader = shader_counter.next();
+        let mut programs = HashMap::new();
+        programs.insert(shadow_shader, shadow_program);
+
         let mut renderer = GlRender {
             context: context,
 
             shared_materials: HashMap::new(),
             meshes: HashMap::new(),
             textures: HashMap::new(),
+            render_targets: HashMap::new(),
             mesh_instances: HashMap::new(),
             anchors: HashMap::new(),
             cameras: HashMap::new(),
             lights: HashMap::new(),
-            programs: HashMap::new(),
+            programs: programs,
+            instanced_programs: HashMap::new(),
 
             mesh_instances_with_shared_materials: HashMap::new(),
             mesh_instances_with_owned_material: Vec::new(),
@@ -92,11 +178,23 @@ impl GlRender {
             anchor_counter: AnchorId::initial(),
             camera_counter: CameraId::initial(),
             light_counter: LightId::initial(),
-            shader_counter: Shader::initial(),
+            shader_counter: shader_counter,
 
             ambient_color: Color::rgb(0.01, 0.01, 0.01),
 
-            // Use temporary value and replace it later.
+            shadow_shader: shadow_shader,
+            shadow_settings: shadow_settings,
+            shadow_atlas: Some(shadow_atlas),
+
+            default_post_effects: Vec::new(),
+            post_materials: None,
+            post_targets: HashMap::new(),
+
+            capture_next_frame: false,
+            captured_frame: None,
+
+            // Use temporary values and replace them later.
+            post_quad: GpuMesh::initial(),
             default_material: Material::new(Shader::initial()),
         };
 
@@ -112,16 +210,758 @@ impl GlRender {
         let default_material = renderer.build_material(material_source).unwrap();
         renderer.default_material = default_material;
 
+        {
+            let _s = Stopwatch::new("Building post effect materials");
+
+            renderer.post_materials = Some(PostMaterials {
+                tone_mapping: renderer.build_built_in_material(TONE_MAPPING_BYTES),
+                gamma_correction: renderer.build_built_in_material(GAMMA_CORRECTION_BYTES),
+                bloom: renderer.build_built_in_material(BLOOM_BYTES),
+                fxaa: renderer.build_built_in_material(FXAA_BYTES),
+                color_grading: renderer.build_built_in_material(COLOR_GRADING_BYTES),
+            });
+        }
+
+        // The post effect quad is defined directly in clip space, so it covers the whole viewport
+        // without needing to be transformed.
+        let post_quad = MeshBuilder::new()
+            .set_position_data(&[
+                Point::new(-1.0, -1.0, 0.0),
+                Point::new(1.0, -1.0, 0.0),
+                Point::new(1.0, 1.0, 0.0),
+                Point::new(-1.0, 1.0, 0.0),
+            ])
+            .set_texcoord_data(&[
+                Vector2::new(0.0, 0.0),
+                Vector2::new(1.0, 0.0),
+                Vector2::new(1.0, 1.0),
+                Vector2::new(0.0, 1.0),
+            ])
+            .set_indices(&[0, 1, 2, 0, 2, 3])
+            .build()
+            .unwrap();
+        renderer.post_quad = renderer.register_mesh(&post_quad);
+
         Ok(renderer)
     }
 
+    /// Sets the post effects for cameras that don't specify their own, see
+    /// `Camera::set_post_effects()`.
+    pub fn set_default_post_effects(&mut self, post_effects: Vec<PostEffect>) {
+        self.default_post_effects = post_effects;
+    }
+
+    /// Builds one of the materials that ship with the renderer.
+    fn build_built_in_material(&mut self, bytes: &[u8]) -> Material {
+        let source = str::from_utf8(bytes).unwrap();
+        let material_source = MaterialSource::from_str(source).unwrap();
+        self.build_material(material_source).unwrap()
+    }
+
+    fn render_camera(&self, camera: &Camera) {
+        let _stopwatch = Stopwatch::new("Rendering camera");
+
+        let camera_anchor = camera
+            .anchor()
+            .and_then(|anchor_id| self.anchors.get(&anchor_id))
+            .expect("Camera's anchor wasn't checked by can_render_camera()");
+
+        // Shadow maps are rendered into their own framebuffer, so they have to be rendered before
+        // the camera's viewport is set up.
+        let shadow_maps = self.render_shadow_maps(camera, camera_anchor);
+
+        let (target_width, target_height) = self.target_size(camera);
+        let viewport = camera.viewport().to_pixels(target_width, target_height);
+        let (x, y, width, height) = viewport;
+
+        // Cameras with post effects render the scene into an offscreen buffer, then the post
+        // effects draw the final image into the camera's viewport. There are no buffers for empty
+        // viewports, in which case there's nothing to post-process anyway.
+        let post_effects = self.camera_post_effects(camera);
+        let post_targets = if post_effects.is_empty() {
+            None
+        } else {
+            self.post_targets.get(&(width, height))
+        };
+
+        match post_targets {
+            Some(post_targets) => {
+                self.context.set_framebuffer(Some(&post_targets.buffers[0].framebuffer));
+                self.context.set_viewport(0, 0, width, height);
+
+                // The offscreen buffer doesn't contain what earlier cameras drew, so it's always
+                // cleared completely.
+                let clear_color = match camera.clear() {
+                    ClearFlags::Color(color) => color,
+                    ClearFlags::Depth | ClearFlags::Nothing => Color::rgb(0.0, 0.0, 0.0),
+                };
+                self.context.clear_viewport(Some(clear_color.into()), true);
+            },
+
+            None => {
+                // Restrict rendering to the camera's viewport within its target and clear it.
+                self.context.set_framebuffer(self.target_framebuffer(camera));
+                self.context.set_viewport(x, y, width, height);
+
+                match camera.clear() {
+                    ClearFlags::Color(color) => self.context.clear_viewport(Some(color.into()), true),
+                    ClearFlags::Depth => self.context.clear_viewport(None, true),
+                    ClearFlags::Nothing => {},
+                }
+            },
+        }
+
+        self.render_scene(camera, camera_anchor, &shadow_maps);
+
+        if let Some(post_targets) = post_targets {
+            self.render_post_effects(camera, camera_anchor, &*post_effects, post_targets, viewport);
+        }
+    }
+
+    /// Draws the mesh instances visible to the camera into the current framebuffer and viewport.
+    fn render_scene(&self, camera: &Camera, camera_anchor: &Anchor, shadow_maps: &HashMap<LightId, ShadowMap>) {
+        let lights = self.active_lights(camera_anchor.view_matrix(), shadow_maps);
+
+        // Skip mesh instances that are entirely outside of the camera's view.
+        let frustum = Frustum::from_matrix(camera.projection_matrix() * camera_anchor.view_matrix());
+        let mut drawn = 0;
+        let mut culled = 0;
+        let mut instanced_batches = 0;
+
+        // Transparent mesh instances have to be drawn after all opaque geometry, so they're
+        // collected along with their distance from the camera and drawn at the end.
+        let camera_position = camera_anchor.position();
+        let mut transparent = Vec::new();
+
+        // Render shared materials first.
+        for (material_id, mesh_instances) in &self.mesh_instances_with_shared_materials {
+            let _s = Stopwatch::new("Rendering shared material");
+
+            let material = self.shared_materials.get(material_id).expect("No such material exists");
+            let is_transparent = material.blend_mode().is_transparent();
+            let mut has_setup_material = false;
+
+            // Group the visible mesh instances by mesh and by the lights that affect them, so that
+            // meshes drawn many times can be drawn with a single instanced draw call. The batches
+            // are ordered so that they're drawn in the same order every frame.
+            let mut batches: BTreeMap<(GpuMesh, [Option<usize>; MAX_LIGHTS_PER_OBJECT]), Vec<&MeshInstance>> = BTreeMap::new();
+            for mesh_instance_id in mesh_instances {
+                let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
+                if !mesh_instance.is_visible()
+                    || !camera.renders_layer(mesh_instance.layer())
+                    || !self.has_resources(mesh_instance)
+                {
+                    continue;
+                }
+
+                if !self.in_frustum(mesh_instance, &frustum) {
+                    culled += 1;
+                    continue;
+                }
+                drawn += 1;
+
+                if is_transparent {
+                    let distance = self.camera_distance_sqr(mesh_instance, camera_position);
+                    transparent.push((distance, mesh_instance, material));
+                    continue;
+                }
+
+                let selected_lights = self.select_light_indices(mesh_instance, &lights);
+                batches.entry((*mesh_instance.mesh(), selected_lights)).or_insert(Vec::new()).push(mesh_instance);
+            }
+
+            let can_instance = self.instanced_programs.contains_key(material.shader());
+            for (&(ref mesh, ref selected_lights), batch) in &batches {
+                if can_instance && batch.len() >= MIN_INSTANCED_BATCH {
+                    instanced_batches += 1;
+                    self.render_instanced(
+                        mesh,
+                        &*batch,
+                        material,
+                        camera,
+                        camera_anchor,
+                        &lights,
+                        selected_lights,
+                    );
+                    continue;
+                }
+
+                for mesh_instance in batch {
+                    self.render_mesh_instance(
+                        mesh_instance,
+                        material,
+                        camera,
+                        camera_anchor,
+                        &lights,
+                        selected_lights,
+                        &mut has_setup_material,
+                    );
+                }
+            }
+        }
+
+        // Render meshes with unique materials.
+        for mesh_instance_id in &self.mesh_instances_with_owned_material {
+            let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
+            if !mesh_instance.is_visible()
+                || !camera.renders_layer(mesh_instance.layer())
+                || !self.has_resources(mesh_instance)
+            {
+                continue;
+            }
+
+            if !self.in_frustum(mesh_instance, &frustum) {
+                culled += 1;
+                continue;
+            }
+            drawn += 1;
+
+            let material = mesh_instance.material().expect("Mesh instance was in wrong bucket (was in the owned material bucket, had shared material)");
+            if material.blend_mode().is_transparent() {
+                let distance = self.camera_distance_sqr(mesh_instance, camera_position);
+                transparent.push((distance, mesh_instance, material));
+                continue;
+            }
+
+            self.render_mesh_instance(
+                mesh_instance,
+                material,
+                camera,
+                camera_anchor,
+                &lights,
+                &self.select_light_indices(mesh_instance, &lights),
+                &mut false,
+            );
+        }
+
+        // Render transparent mesh instances from back to front so that each one blends with
+        // everything behind it.
+        transparent.sort_by(|&(left, _, _), &(right, _, _)| {
+            right.partial_cmp(&left).unwrap_or(Ordering::Equal)
+        });
+        for &(_, mesh_instance, material) in &transparent {
+            self.render_mesh_instance(
+                mesh_instance,
+                material,
+                camera,
+                camera_anchor,
+                &lights,
+                &self.select_light_indices(mesh_instance, &lights),
+                &mut false,
+            );
+        }
+
+        stopwatch::counter("Drawn mesh instances", drawn);
+        stopwatch::counter("Transparent mesh instances", transparent.len() as i64);
+        stopwatch::counter("Culled mesh instances", culled);
+        stopwatch::counter("Instanced batches", instanced_batches);
+    }
+
+    /// Applies the post effects to the scene rendered into `post_targets`, drawing the final
+    /// image into `viewport` in the camera's target.
+    fn render_post_effects(
+        &self,
+        camera: &Camera,
+        camera_anchor: &Anchor,
+        post_effects: &[&PostEffect],
+        post_targets: &PostTargets,
+        viewport: (i32, i32, i32, i32),
+    ) {
+        let _stopwatch = Stopwatch::new("Post-processing");
+
+        let (x, y, width, height) = viewport;
+        let texel_size = [1.0 / width as f32, 1.0 / height as f32];
+
+        // Each effect reads the output of the previous one, alternating between the two buffers,
+        // except for the last effect which draws into the camera's target.
+        let mut source = 0;
+        for (index, effect) in post_effects.iter().enumerate() {
+            if index + 1 == post_effects.len() {
+                self.context.set_framebuffer(self.target_framebuffer(camera));
+                self.context.set_viewport(x, y, width, height);
+            } else {
+                self.context.set_framebuffer(Some(&post_targets.buffers[1 - source].framebuffer));
+                self.context.set_viewport(0, 0, width, height);
+            }
+
+            self.render_post_effect(effect, &post_targets.buffers[source].texture, texel_size, camera_anchor);
+            source = 1 - source;
+        }
+
+        stopwatch::counter("Post effects", post_effects.len() as i64);
+    }
+
+    /// Draws a single post effect over the current viewport, reading the image from `source`.
+    fn render_post_effect(
+        &self,
+        effect: &PostEffect,
+        source: &GlTexture2d,
+        texel_size: [f32; 2],
+        camera_anchor: &Anchor,
+    ) {
+        let material = self.post_effect_material(effect);
+        let program = self
+            .programs
+            .get(material.shader())
+            .expect("Post effect material is using a shader that does not exist");
+        let quad = self.meshes.get(&self.post_quad).expect("Post effect quad does not exist");
+
+        let default_texture = GlTexture2d::empty(&self.context);
+        let identity = Matrix4::identity();
+
+        // Post effects cover the whole viewport, so there's no need for depth testing or culling.
+        let mut draw_builder = DrawBuilder::new(&self.context, &quad.vertex_array, DrawMode::Triangles);
+        draw_builder
+        .program(program)
+        .uniform(
+            "model_view_projection",
+            GlMatrix {
+                data: identity.raw_data(),
+                transpose: true,
+            },
+        )
+        .uniform("source_texture", source)
+        .uniform("source_texel_size", texel_size);
+
+        self.apply_material_uniforms(&mut draw_builder, material, camera_anchor, &default_texture);
+
+        // The built-in effects' settings come from the effect rather than from the material, so
+        // they override the material's property values.
+        match *effect {
+            PostEffect::ToneMapping { exposure } => {
+                draw_builder.uniform("exposure", exposure);
+            },
+            PostEffect::GammaCorrection { gamma } => {
+                draw_builder.uniform("gamma", gamma);
+            },
+            PostEffect::Bloom { threshold, intensity } => {
+                draw_builder
+                .uniform("bloom_threshold", threshold)
+                .uniform("bloom_intensity", intensity);
+            },
+            PostEffect::ColorGrading { contrast, saturation, tint } => {
+                draw_builder
+                .uniform("grade_contrast", contrast)
+                .uniform("grade_saturation", saturation)
+                .uniform::<[f32; 4]>("grade_tint", tint.into());
+            },
+            PostEffect::Fxaa | PostEffect::Custom(_) => {},
+        }
+
+        draw_builder.draw();
+    }
+
+    /// Gets the material used to draw a post effect.
+    fn post_effect_material(&self, effect: &PostEffect) -> &Material {
+        let post_materials = self.post_materials.as_ref().expect("Post effect materials haven't been built");
+        match *effect {
+            PostEffect::ToneMapping { .. } => &post_materials.tone_mapping,
+            PostEffect::GammaCorrection { .. } => &post_materials.gamma_correction,
+            PostEffect::Bloom { .. } => &post_materials.bloom,
+            PostEffect::Fxaa => &post_materials.fxaa,
+            PostEffect::ColorGrading { .. } => &post_materials.color_grading,
+            PostEffect::Custom(material_id) => {
+                self.shared_materials.get(&material_id).expect("No such material exists")
+            },
+        }
+    }
+
+    /// Gets the post effects applied to the camera, falling back to the default post effects if
+    /// the camera doesn't specify any.
+    ///
+    /// Custom post effects whose material has been unregistered are skipped.
+    fn camera_post_effects<'a>(&'a self, camera: &'a Camera) -> Vec<&'a PostEffect> {
+        camera.post_effects()
+            .unwrap_or(&*self.default_post_effects)
+            .iter()
+            .filter(|effect| match **effect {
+                PostEffect::Custom(material_id) => self.shared_materials.contains_key(&material_id),
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Creates the offscreen buffers needed by cameras with post effects, and destroys the ones
+    /// that are no longer needed, e.g. because the camera's viewport changed size.
+    fn prepare_post_targets(&mut self) {
+        let mut sizes = Vec::new();
+        for camera in self.cameras.values() {
+            if !self.can_render_camera(camera) || self.camera_post_effects(camera).is_empty() {
+                continue;
+            }
+
+            let (target_width, target_height) = self.target_size(camera);
+            let (_, _, width, height) = camera.viewport().to_pixels(target_width, target_height);
+            if width > 0 && height > 0 {
+                sizes.push((width, height));
+            }
+        }
+
+        self.post_targets.retain(|size, _| sizes.contains(size));
+
+        let context = &self.context;
+        for size in sizes {
+            self.post_targets
+                .entry(size)
+                .or_insert_with(|| PostTargets::new(context, size.0 as usize, size.1 as usize));
+        }
+    }
+
+    /// Checks that the camera has an anchor and that its anchor and target texture are still
+    /// registered.
+    ///
+    /// The camera's anchor determines where it's looking from, so cameras without one are skipped.
+    fn can_render_camera(&self, camera: &Camera) -> bool {
+        let has_anchor = camera.anchor().map_or(false, |anchor_id| self.anchors.contains_key(&anchor_id));
+        let has_target = camera.target().map_or(true, |texture| self.render_targets.contains_key(&texture));
+        has_anchor && has_target
+    }
+
+    /// Checks that the mesh and anchor used by the mesh instance are still registered.
+    fn has_resources(&self, mesh_instance: &MeshInstance) -> bool {
+        self.meshes.contains_key(mesh_instance.mesh())
+            && mesh_instance.anchor().map_or(true, |anchor_id| self.anchors.contains_key(&anchor_id))
+    }
+
+    /// Checks that the light's anchor is still registered, if it has one.
+    fn has_anchor(&self, light: &Light) -> bool {
+        light.anchor().map_or(true, |anchor_id| self.anchors.contains_key(anchor_id))
+    }
+
+    /// Checks if any registered material, or the default material, uses the shader.
+    fn is_shader_in_use(&self, shader: &Shader) -> bool {
+        let uses_shader = |material: &Material| material.shader() == shader;
+        uses_shader(&self.default_material)
+            || self.shared_materials.values().any(&uses_shader)
+            || self.mesh_instances.values().filter_map(MeshInstance::material).any(&uses_shader)
+    }
+
+    /// Gets the framebuffer for the camera's target, or `None` if it renders to the screen.
+    fn target_framebuffer(&self, camera: &Camera) -> Option<&Framebuffer> {
+        camera.target().map(|texture| {
+            &self.render_targets
+                .get(&texture)
+                .expect("Camera's target texture wasn't created with create_render_texture()")
+                .framebuffer
+        })
+    }
+
+    /// Gets the size of the camera's target in pixels.
+    fn target_size(&self, camera: &Camera) -> (i32, i32) {
+        match self.target_framebuffer(camera) {
+            Some(framebuffer) => {
+                let (width, height) = framebuffer.size();
+                (width as i32, height as i32)
+            },
+            None => self.context.default_framebuffer_size(),
+        }
+    }
+
+    /// Renders the shadow maps for the strongest shadow casting lights into the shadow atlas,
+    /// returning the shadow map for each of those lights.
+    ///
+    /// Directional lights' shadow maps are centered on the camera, so the shadow maps have to be
+    /// rendered for each camera.
+    fn render_shadow_maps(&self, camera: &Camera, camera_anchor: &Anchor) -> HashMap<LightId, ShadowMap> {
+        let _stopwatch = Stopwatch::new("Rendering shadow maps");
+
+        let mut shadow_maps = HashMap::new();
+
+        let mut shadow_lights: Vec<(&LightId, &Light)> = self.lights
+            .iter()
+            .filter(|&(_, light)| light.enabled && light.casts_shadows && self.has_anchor(light))
+            .filter(|&(_, light)| match light.data {
+                LightData::Point { .. } => false,
+                LightData::Directional { .. } | LightData::Spot { .. } => true,
+            })
+            .collect();
+
+        if shadow_lights.is_empty() {
+            return shadow_maps;
+        }
+
+        shadow_lights.sort_by(|&(_, left), &(_, right)| {
+            right.strength.partial_cmp(&left.strength).unwrap_or(Ordering::Equal)
+        });
+        shadow_lights.truncate(SHADOW_ATLAS_TILES * SHADOW_ATLAS_TILES);
+
+        let shadow_atlas = self.shadow_atlas();
+        self.context.set_framebuffer(Some(&shadow_atlas.framebuffer));
+
+        // Clear the whole atlas at once, then render each light into its own tile.
+        let (atlas_size, _) = shadow_atlas.framebuffer.size();
+        self.context.set_viewport(0, 0, atlas_size as i32, atlas_size as i32);
+        self.context.clear_viewport(None, true);
+
+        let resolution = self.shadow_settings.resolution;
+        let tile_scale = 1.0 / SHADOW_ATLAS_TILES as f32;
+        let mut shadow_casters = 0;
+
+        for (index, &(light_id, light)) in shadow_lights.iter().enumerate() {
+            let tile_x = index % SHADOW_ATLAS_TILES;
+            let tile_y = index / SHADOW_ATLAS_TILES;
+            self.context.set_viewport(
+                (tile_x * resolution) as i32,
+                (tile_y * resolution) as i32,
+                resolution as i32,
+                resolution as i32,
+            );
+
+            let light_transform = self.shadow_transform(light, camera_anchor);
+            let frustum = Frustum::from_matrix(light_transform);
+
+            for mesh_instance in self.mesh_instances.values() {
+                if !mesh_instance.is_visible()
+                    || !mesh_instance.casts_shadows()
+                    || !camera.renders_layer(mesh_instance.layer())
+                    || !self.has_resources(mesh_instance)
+                    || !self.in_frustum(mesh_instance, &frustum)
+                {
+                    continue;
+                }
+
+                shadow_casters += 1;
+                self.render_shadow_caster(mesh_instance, light_transform);
+            }
+
+            shadow_maps.insert(*light_id, ShadowMap {
+                transform: light_transform,
+                tile: [tile_x as f32 * tile_scale, tile_y as f32 * tile_scale, tile_scale, tile_scale],
+            });
+        }
+
+        self.context.set_framebuffer(None);
+
+        stopwatch::counter("Shadow casters", shadow_casters);
+
+        shadow_maps
+    }
+
+    /// Calculates the transform from world space to the clip space of a light's shadow map.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `light` is a point light.
+    fn shadow_transform(&self, light: &Light, camera_anchor: &Anchor) -> Matrix4 {
+        match light.data {
+            LightData::Directional { direction } => {
+                // Directional lights don't have a position, so the shadow map covers a box
+                // around the camera, seen from the direction of the light.
+                let distance = self.shadow_settings.directional_distance;
+
+                let mut light_anchor = Anchor::new();
+                light_anchor.set_position(camera_anchor.position() - direction * distance);
+                light_anchor.set_orientation(Orientation::look_rotation(direction, Vector3::up()));
+
+                let projection = Camera::orthographic(distance, 1.0, 0.0, distance * 2.0).projection_matrix();
+                projection * light_anchor.view_matrix()
+            },
+
+            LightData::Spot { range, outer_angle, .. } => {
+                let light_anchor = match light.anchor() {
+                    Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                    None => panic!("Cannot render spot light if it's not attached to an anchor"),
+                };
+
+                // The shadow map covers the whole cone, but the field of view is limited to keep
+                // the projection well-behaved for very wide cones.
+                let fov = (outer_angle * 2.0).min(PI * 0.9);
+                let projection = Camera::new(fov, 1.0, range * 0.01, range).projection_matrix();
+                projection * light_anchor.view_matrix()
+            },
+
+            LightData::Point { .. } => panic!("Point lights don't cast shadows"),
+        }
+    }
+
+    /// Draws the depth of a mesh instance into the current shadow map.
+    fn render_shadow_caster(&self, mesh_instance: &MeshInstance, light_transform: Matrix4) {
+        let anchor_id = mesh_instance.anchor().expect("Shadow casting mesh instance had no anchor");
+        let anchor = self.anchors.get(&anchor_id).expect("No such anchor exists");
+        let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+
+        let model_view_projection = light_transform * anchor.matrix();
+        let program = self.programs.get(&self.shadow_shader).expect("Shadow program does not exist");
+
+        DrawBuilder::new(&self.context, &mesh_data.vertex_array, DrawMode::Triangles)
+        .program(program)
+        .cull(Face::Back)
+        .depth_test(Comparison::Less)
+        .uniform(
+            "model_view_projection",
+            GlMatrix {
+                data: model_view_projection.raw_data(),
+                transpose: true,
+            },
+        )
+        .draw();
+    }
+
+    fn shadow_atlas(&self) -> &ShadowAtlas {
+        self.shadow_atlas.as_ref().expect("Shadow atlas has already been destroyed")
+    }
+
+    /// Checks if any part of the mesh instance's bounding sphere is inside the frustum.
+    ///
+    /// Mesh instances without an anchor aren't drawn, so they're never in the frustum.
+    fn in_frustum(&self, mesh_instance: &MeshInstance, frustum: &Frustum) -> bool {
+        let anchor = match mesh_instance.anchor() {
+            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+            None => return false,
+        };
+
+        let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+        frustum.intersects_sphere(&mesh_data.bounding_sphere.transform(anchor.matrix()))
+    }
+
+    /// Selects the lights that affect the mesh instance with `select_lights()`, returning their
+    /// indices in `lights`.
+    ///
+    /// Mesh instances with the same selected lights can share one set of light uniforms, which is
+    /// what allows them to be drawn in the same instanced batch.
+    fn select_light_indices(
+        &self,
+        mesh_instance: &MeshInstance,
+        lights: &[ActiveLight],
+    ) -> [Option<usize>; MAX_LIGHTS_PER_OBJECT] {
+        let _stopwatch = Stopwatch::new("Select lights");
+
+        let anchor = match mesh_instance.anchor() {
+            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+            None => return [None; MAX_LIGHTS_PER_OBJECT],
+        };
+
+        let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+        let bounds = mesh_data.bounding_sphere.transform(anchor.matrix());
+        select_lights(lights, bounds.center, bounds.radius)
+    }
+
+    /// Gets the squared distance from `camera_position` to the center of the mesh instance's
+    /// bounds, used to sort transparent mesh instances.
+    fn camera_distance_sqr(&self, mesh_instance: &MeshInstance, camera_position: Point) -> f32 {
+        let anchor = match mesh_instance.anchor() {
+            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+            None => return 0.0,
+        };
+
+        let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+        let bounds = mesh_data.bounding_sphere.transform(anchor.matrix());
+        bounds.center.distance_sqr(&camera_position)
+    }
+
+    /// Adds a mesh instance to the bucket for its material.
+    ///
+    /// There's no bucket for shared materials that have been unregistered, so mesh instances using
+    /// them aren't drawn.
+    fn add_to_material_bucket(&mut self, id: MeshInstanceId, material: &MaterialType) {
+        match *material {
+            MaterialType::Shared(material_id) => {
+                if let Some(bucket) = self.mesh_instances_with_shared_materials.get_mut(&material_id) {
+                    bucket.push(id);
+                }
+            },
+            MaterialType::Owned(_) => self.mesh_instances_with_owned_material.push(id),
+        }
+    }
+
+    /// Removes a mesh instance from the bucket for its material.
+    ///
+    /// `shared_material` is the mesh instance's shared material, or `None` if it owns its material.
+    fn remove_from_material_bucket(&mut self, id: MeshInstanceId, shared_material: Option<MaterialId>) {
+        match shared_material {
+            Some(material_id) => {
+                if let Some(bucket) = self.mesh_instances_with_shared_materials.get_mut(&material_id) {
+                    bucket.retain(|&other| other != id);
+                }
+            },
+            None => self.mesh_instances_with_owned_material.retain(|&other| other != id),
+        }
+    }
+
+    /// Gathers the enabled lights in the scene, calculating their view space data once so that
+    /// it can be shared by every mesh instance rendered by the camera.
+    ///
+    /// `shadow_maps` are the shadow maps rendered by `render_shadow_maps()` for the camera.
+    fn active_lights(&self, view_transform: Matrix4, shadow_maps: &HashMap<LightId, ShadowMap>) -> Vec<ActiveLight> {
+        let _stopwatch = Stopwatch::new("Gather active lights");
+
+        self.lights
+            .iter()
+            .filter(|&(_, light)| light.enabled && self.has_anchor(light))
+            .map(|(light_id, light)| {
+                let mut active_light = ActiveLight {
+                    light_type: 0,
+                    color: light.color,
+                    strength: light.strength,
+                    position: Point::origin(),
+                    position_view: Point::origin(),
+                    radius: 0.0,
+                    direction: Vector3::zero(),
+                    direction_view: Vector3::zero(),
+                    cone_inner: 0.0,
+                    cone_outer: 0.0,
+                    shadow_map: shadow_maps.get(light_id).cloned(),
+                };
+
+                // Setup data specific to the current type of light.
+                match light.data {
+                    LightData::Point { radius } => {
+                        // Get the light's anchor.
+                        let light_anchor = match light.anchor() {
+                            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                            None => panic!("Cannot render point light if it's not attached to an anchor"),
+                        };
+
+                        active_light.light_type = 1;
+                        active_light.position = light_anchor.position();
+                        active_light.position_view = light_anchor.position() * view_transform;
+                        active_light.radius = radius;
+                    },
+
+                    LightData::Directional { direction } => {
+                        active_light.light_type = 2;
+                        active_light.direction = direction;
+                        active_light.direction_view = direction * view_transform;
+                    },
+
+                    LightData::Spot { range, inner_angle, outer_angle } => {
+                        // Get the light's anchor.
+                        let light_anchor = match light.anchor() {
+                            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+                            None => panic!("Cannot render spot light if it's not attached to an anchor"),
+                        };
+
+                        // Spot lights shine along their anchor's forward axis.
+                        let direction = light_anchor.orientation() * Vector3::forward();
+
+                        active_light.light_type = 3;
+                        active_light.position = light_anchor.position();
+                        active_light.position_view = light_anchor.position() * view_transform;
+                        active_light.radius = range;
+                        active_light.direction = direction;
+                        active_light.direction_view = direction * view_transform;
+                        active_light.cone_inner = inner_angle.cos();
+                        active_light.cone_outer = outer_angle.cos();
+                    },
+                }
+
+                active_light
+            })
+            .collect()
+    }
+
+    /// Draws a single mesh instance.
+    ///
+    /// `selected_lights` are the indices in `lights` of the lights that affect the mesh instance,
+    /// see `select_light_indices()`.
     fn render_mesh_instance(
         &self,
         mesh_instance: &MeshInstance,
         material: &Material,
         camera: &Camera,
         camera_anchor: &Anchor,
-        has_setup_lights: &mut bool,
+        lights: &[ActiveLight],
+        selected_lights: &[Option<usize>],
         has_setup_material: &mut bool,
     ) {
         let _s = Stopwatch::new("Rendering mesh instance");
@@ -151,16 +991,11 @@ impl GlRender {
             inverse_model_view.transpose()
         };
 
-        // The data for the light uniforms must be declared before `draw_builder` so they
-        // can outlive it, since they are borrowed when the uniforms are set.
-        let mut light_type = [0i32; 8];
-        let mut light_strength = [0.0f32; 8];
-        let mut light_color = [Color::rgb(0.0, 0.0, 0.0); 8];
-        let mut light_position = [Point::origin(); 8];
-        let mut light_position_view = [Point::origin(); 8];
-        let mut light_radius = [0.0f32; 8];
-        let mut light_direction = [Vector3::zero(); 8];
-        let mut light_direction_view = [Vector3::zero(); 8];
+        // Each mesh instance uses the lights that affect it the most, so scenes can have any
+        // number of lights as long as no more than 8 of them overlap any one object. The light
+        // uniforms must be declared before `draw_builder` so they can outlive it, since they are
+        // borrowed when the uniforms are set.
+        let light_uniforms = LightUniforms::new(lights, selected_lights);
 
         let program = self
             .programs
@@ -179,6 +1014,27 @@ impl GlRender {
         .cull(Face::Back)
         .depth_test(Comparison::Less);
 
+        // Transparent materials blend with what's behind them and don't write depth, so that
+        // transparent surfaces behind them still get drawn.
+        match material.blend_mode() {
+            BlendMode::Opaque => {},
+            BlendMode::Alpha => {
+                draw_builder
+                .blend(SourceFactor::SourceAlpha, DestFactor::OneMinusSourceAlpha)
+                .depth_write(false);
+            },
+            BlendMode::Additive => {
+                draw_builder
+                .blend(SourceFactor::SourceAlpha, DestFactor::One)
+                .depth_write(false);
+            },
+            BlendMode::Premultiplied => {
+                draw_builder
+                .blend(SourceFactor::One, DestFactor::OneMinusSourceAlpha)
+                .depth_write(false);
+            },
+        }
+
         // Set uniform transforms.
         {
             let _stopwatch = Stopwatch::new("Transform uniforms");
@@ -237,92 +1093,162 @@ impl GlRender {
 
         // Apply material attributes.
         if !*has_setup_material {
-            let _stopwatch = Stopwatch::new("Material uniforms");
+            *has_setup_material = true;
+            self.apply_material_uniforms(&mut draw_builder, material, camera_anchor, &default_texture);
+        }
+
+        // Render all lights in a single pass by sending up to 8 lights at once in arrays.
+        {
+            let _stopwatch = Stopwatch::new("Setup lights");
+            light_uniforms.apply(&mut draw_builder);
+        }
+
+        {
+            let _s = Stopwatch::new("Draw mesh");
+
+            draw_builder.draw();
+        }
+    }
+
+    /// Draws every mesh instance in `mesh_instances` with a single instanced draw call.
+    ///
+    /// All of the mesh instances must use `mesh` and `material`, and `material`'s shader must
+    /// have an instanced program. The batch shares one set of lights, so `selected_lights` must be
+    /// the indices in `lights` that `select_lights()` picks for every one of the mesh instances.
+    fn render_instanced(
+        &self,
+        mesh: &GpuMesh,
+        mesh_instances: &[&MeshInstance],
+        material: &Material,
+        camera: &Camera,
+        camera_anchor: &Anchor,
+        lights: &[ActiveLight],
+        selected_lights: &[Option<usize>],
+    ) {
+        let _s = Stopwatch::new("Rendering instanced batch");
 
-            *has_setup_material = true;
+        let mesh_data = self.meshes.get(mesh).expect("Mesh data does not exist for mesh id");
 
-            // Set uniform colors.
-            draw_builder.uniform::<[f32; 4]>("global_ambient", self.ambient_color.into());
+        let default_texture = GlTexture2d::empty(&self.context);
 
-            // Other uniforms.
-            draw_builder.uniform("camera_position", *camera_anchor.position().as_array());
+        // Fill the instance buffer with each instance's transforms. GLSL expects matrices in
+        // column-major order, so the matrices are transposed.
+        let mut instance_data = Vec::with_capacity(mesh_instances.len() * INSTANCE_ELEMENTS);
+        {
+            let _stopwatch = Stopwatch::new("Build instance data");
 
-            for (name, property) in material.properties() {
-                match *property {
-                    MaterialProperty::Color(ref color) => {
-                        draw_builder.uniform::<[f32; 4]>(name, color.into());
-                    },
-                    MaterialProperty::f32(value) => {
-                        draw_builder.uniform(name, value);
-                    },
-                    MaterialProperty::Vector3(value) => {
-                        draw_builder.uniform::<[f32; 3]>(name, value.into());
-                    },
-                    MaterialProperty::Texture(ref texture) => {
-                        let gl_texture =
-                        self.textures
-                        .get(texture)
-                        .unwrap_or(&default_texture);
-                        draw_builder.uniform(name, gl_texture);
-                    },
-                }
+            for mesh_instance in mesh_instances {
+                let anchor_id = mesh_instance.anchor().expect("Instanced mesh instance had no anchor");
+                let anchor = self.anchors.get(&anchor_id).expect("No such anchor exists");
+
+                let model_transform = anchor.matrix();
+                instance_data.extend_from_slice(model_transform.transpose().raw_data());
+                instance_data.extend_from_slice(anchor.normal_matrix().transpose().raw_data());
             }
+
+            mesh_data.vertex_array.set_instance_data(&*instance_data);
         }
 
-        // Render all lights in a single pass by sending 8 lights at once in arrays.
-        // All light-related uniforms will stay the same for all draws for a given camera,
-        // so we only specify them for the first draw and leave them the same after that.
-        if !*has_setup_lights {
-            *has_setup_lights = true;
+        let view_transform = camera_anchor.view_matrix();
+        let projection_transform = camera.projection_matrix();
+        let inverse_view_transpose = Matrix3::from(camera_anchor.inverse_view_matrix()).transpose();
 
-            let _stopwatch = Stopwatch::new("Setup lights");
+        let light_uniforms = LightUniforms::new(lights, selected_lights);
 
-            // TODO: Support having more than 8 lights active at a time. Maybe pick the 8
-            // most relevant lights? Or simply support more lights at once in the shader.
-            for (index, light) in self.lights.values().take(8).enumerate() {
-                // Setup common light data.
-                light_color[index] = light.color;
-                light_strength[index] = light.strength;
+        let program = self
+            .instanced_programs
+            .get(material.shader())
+            .expect("Material's shader does not have an instanced program");
 
-                // Setup data specific to the current type of light.
-                match light.data {
-                    LightData::Point { radius } => {
-                        // Get the light's anchor.
-                        let light_anchor = match light.anchor() {
-                            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
-                            None => panic!("Cannot render point light if it's not attached to an anchor"),
-                        };
+        let mut draw_builder = DrawBuilder::new(
+            &self.context,
+            &mesh_data.vertex_array,
+            DrawMode::Triangles,
+        );
 
-                        light_type[index] = 1;
-                        light_position[index] = light_anchor.position();
-                        light_position_view[index] = light_anchor.position() * view_transform;
-                        light_radius[index] = radius;
-                    },
+        draw_builder
+        .program(program)
+        .cull(Face::Back)
+        .depth_test(Comparison::Less)
+        .instances(mesh_instances.len());
 
-                    LightData::Directional { direction } => {
-                        light_type[index] = 2;
-                        light_direction[index] = direction;
-                        light_direction_view[index] = direction * view_transform;
-                    },
-                }
-            }
+        draw_builder
+        .uniform(
+            "view_transform",
+            GlMatrix {
+                data: view_transform.raw_data(),
+                transpose: true,
+            },
+        )
+        .uniform(
+            "projection_transform",
+            GlMatrix {
+                data: projection_transform.raw_data(),
+                transpose: true,
+            },
+        )
+        .uniform(
+            "view_inverse_transpose",
+            GlMatrix {
+                data: inverse_view_transpose.raw_data(),
+                transpose: true,
+            },
+        );
 
-            draw_builder.uniform("light_type", &light_type[..]);
-            draw_builder.uniform("light_strength", &light_strength[..]);
-            draw_builder.uniform("light_color", Color::as_slice_of_arrays(&light_color));
-            draw_builder.uniform("light_position", Point::as_slice_of_arrays(&light_position));
-            draw_builder.uniform("light_position_view", Point::as_slice_of_arrays(&light_position_view));
-            draw_builder.uniform("light_radius", &light_radius[..]);
-            draw_builder.uniform("light_direction", Vector3::as_slice_of_arrays(&light_direction));
-            draw_builder.uniform("light_direction_view", Vector3::as_slice_of_arrays(&light_direction_view));
-        }
+        self.apply_material_uniforms(&mut draw_builder, material, camera_anchor, &default_texture);
+        light_uniforms.apply(&mut draw_builder);
 
         {
-            let _s = Stopwatch::new("Draw mesh");
+            let _s = Stopwatch::new("Draw instanced mesh");
 
             draw_builder.draw();
         }
     }
+
+    /// Sets the uniforms for the material's properties, as well as the built-in uniforms that
+    /// are the same for every mesh instance drawn by the camera.
+    fn apply_material_uniforms<'a>(
+        &'a self,
+        draw_builder: &mut DrawBuilder<'a>,
+        material: &'a Material,
+        camera_anchor: &Anchor,
+        default_texture: &'a GlTexture2d,
+    ) {
+        let _stopwatch = Stopwatch::new("Material uniforms");
+
+        // Set uniform colors.
+        draw_builder.uniform::<[f32; 4]>("global_ambient", self.ambient_color.into());
+
+        // Other uniforms.
+        draw_builder.uniform("camera_position", *camera_anchor.position().as_array());
+
+        // Shadow uniforms. The per-light shadow uniforms are set with the other light uniforms.
+        draw_builder
+        .uniform("shadow_atlas", &self.shadow_atlas().depth_texture)
+        .uniform("shadow_bias", self.shadow_settings.bias)
+        .uniform("shadow_pcf_radius", self.shadow_settings.pcf_radius as i32);
+
+        for (name, property) in material.properties() {
+            match *property {
+                MaterialProperty::Color(ref color) => {
+                    draw_builder.uniform::<[f32; 4]>(name, color.into());
+                },
+                MaterialProperty::f32(value) => {
+                    draw_builder.uniform(name, value);
+                },
+                MaterialProperty::Vector3(value) => {
+                    draw_builder.uniform::<[f32; 3]>(name, value.into());
+                },
+                MaterialProperty::Texture(ref texture) => {
+                    let gl_texture =
+                    self.textures
+                    .get(texture)
+                    .unwrap_or(default_texture);
+                    draw_builder.uniform(name, gl_texture);
+                },
+            }
+        }
+    }
 }
 
 impl Drop for GlRender {
@@ -337,12 +1263,16 @@ impl Drop for GlRender {
         // dropped if the context has already been dropped, then we'd get faster shutdown.
         self.shared_materials.clear();
         self.meshes.clear();
+        self.render_targets.clear();
+        self.post_targets.clear();
         self.textures.clear();
         self.mesh_instances.clear();
         self.anchors.clear();
         self.cameras.clear();
         self.lights.clear();
         self.programs.clear();
+        self.instanced_programs.clear();
+        self.shadow_atlas = None;
     }
 }
 
@@ -355,51 +1285,33 @@ impl Renderer for GlRender {
             self.context.clear();
         }
 
-        // TODO: Support rendering multiple cameras.
-        // TODO: Should we warn if there are no cameras?
-        if let Some(camera) = self.cameras.values().next() {
-            let _stopwatch = Stopwatch::new("Rendering camera");
-
-            let camera_anchor = match camera.anchor() {
-                Some(ref anchor_id) => self.anchors.get(anchor_id).expect("No such anchor exists"),
-                None => unimplemented!(),
-            };
-
-            let mut has_setup_lights = false;
+        self.prepare_post_targets();
 
-            // Render shared materials first.
-            for (material_id, mesh_instances) in &self.mesh_instances_with_shared_materials {
-                let _s = Stopwatch::new("Rendering shared material");
+        // Render cameras in order of their depth, that way later cameras draw on top of earlier
+        // ones (e.g. for picture-in-picture). Cameras rendering into textures go first so that
+        // the textures are up to date when the screen is drawn.
+        // TODO: Should we warn if there are no cameras?
+        let mut cameras: Vec<&Camera> = self.cameras
+            .values()
+            .filter(|camera| self.can_render_camera(camera))
+            .collect();
+        cameras.sort_by_key(|camera| (camera.target().is_none(), camera.depth()));
+
+        for camera in cameras {
+            self.render_camera(camera);
+        }
 
-                let material = self.shared_materials.get(material_id).expect("No such material exists");
-                let mut has_setup_material = false;
+        self.context.set_framebuffer(None);
 
-                for mesh_instance_id in mesh_instances {
-                    let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
-                    self.render_mesh_instance(
-                        mesh_instance,
-                        material,
-                        camera,
-                        camera_anchor,
-                        &mut has_setup_lights,
-                        &mut has_setup_material,
-                    );
-                }
-            }
+        // The back buffer's contents are undefined once the buffers have been swapped, so the
+        // frame has to be read before the swap.
+        if self.capture_next_frame {
+            let _stopwatch = Stopwatch::new("Capture frame");
 
-            // Render meshes with unique materials.
-            for mesh_instance_id in &self.mesh_instances_with_owned_material {
-                let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
-                let material = mesh_instance.material().expect("Mesh instance was in wrong bucket (was in the owned material bucket, had shared material)");
-                self.render_mesh_instance(
-                    mesh_instance,
-                    material,
-                    camera,
-                    camera_anchor,
-                    &mut false,
-                    &mut false,
-                );
-            }
+            let (width, height) = self.context.default_framebuffer_size();
+            let pixels = self.context.read_pixels(None);
+            self.captured_frame = Some((width as usize, height as usize, pixels));
+            self.capture_next_frame = false;
         }
 
         {
@@ -441,14 +1353,20 @@ impl Renderer for GlRender {
             uniform_declarations
         };
 
-        static BUILT_IN_UNIFORMS: &'static str = r#"
+        // Transforms that are different for every mesh instance. When drawing instanced these are
+        // calculated in the vertex shader from per-instance attributes instead, see
+        // `INSTANCED_MODEL_TRANSFORMS`.
+        static MODEL_UNIFORMS: &'static str = r#"
             uniform mat4 model_transform;
             uniform mat3 normal_transform;
-            uniform mat4 view_transform;
             uniform mat3 view_normal_transform;
             uniform mat4 model_view_transform;
-            uniform mat4 projection_transform;
             uniform mat4 model_view_projection;
+        "#;
+
+        static BUILT_IN_UNIFORMS: &'static str = r#"
+            uniform mat4 view_transform;
+            uniform mat4 projection_transform;
 
             uniform vec4 global_ambient;
             uniform vec4 camera_position;
@@ -461,16 +1379,64 @@ impl Renderer for GlRender {
             uniform float light_radius[8];
             uniform vec3 light_direction[8];
             uniform vec3 light_direction_view[8];
+            uniform float light_cone_inner[8];
+            uniform float light_cone_outer[8];
+
+            uniform sampler2D shadow_atlas;
+            uniform float shadow_bias;
+            uniform int shadow_pcf_radius;
+            uniform mat4 light_shadow_transform[8];
+            uniform vec4 light_shadow_tile[8];
+
+            uniform sampler2D source_texture;
+            uniform vec2 source_texel_size;
+        "#;
+
+        // Built-in functions available to fragment programs through `@shadow`.
+        static SHADOW_FUNCTIONS: &'static str = r#"
+            // Gets how much of the light at `index` reaches `world_position`, from 0.0 for fully
+            // shadowed to 1.0 for fully lit.
+            float _shadow_factor_(int index, vec4 world_position) {
+                // The tile is empty for lights without a shadow map.
+                vec4 tile = light_shadow_tile[index];
+                if (tile.z == 0.0) {
+                    return 1.0;
+                }
+
+                vec4 light_position = light_shadow_transform[index] * world_position;
+                vec3 coords = light_position.xyz / light_position.w * 0.5 + 0.5;
+
+                // Anything outside of the shadow map isn't shadowed.
+                if (any(lessThan(coords, vec3(0.0))) || any(greaterThan(coords, vec3(1.0)))) {
+                    return 1.0;
+                }
+
+                // Average the shadow test over the neighboring texels to soften the shadow's
+                // edges. Samples are clamped to the tile so they don't read other lights' shadows.
+                vec2 texel = 1.0 / (vec2(textureSize(shadow_atlas, 0)) * tile.zw);
+                float lit = 0.0;
+                for (int x = -shadow_pcf_radius; x <= shadow_pcf_radius; x += 1) {
+                    for (int y = -shadow_pcf_radius; y <= shadow_pcf_radius; y += 1) {
+                        vec2 uv = clamp(coords.xy + vec2(x, y) * texel, vec2(0.0), vec2(1.0));
+                        float depth = texture(shadow_atlas, tile.xy + uv * tile.zw).r;
+                        lit += coords.z - shadow_bias > depth ? 0.0 : 1.0;
+                    }
+                }
+
+                float samples = float((2 * shadow_pcf_radius + 1) * (2 * shadow_pcf_radius + 1));
+                return lit / samples;
+            }
         "#;
 
         // Generate the GLSL source for the vertex shader.
-        let vert_shader = {
+        let vert_main = {
             static DEFAULT_VERT_MAIN: &'static str = r#"
                 @position = model_view_projection * vertex_position;
 
                 @vertex.position = vertex_position;
                 @vertex.normal = vertex_normal;
                 @vertex.uv0 = vertex_uv0;
+                @vertex.tangent = vertex_tangent;
 
                 @vertex.world_position = model_transform * vertex_position;
                 @vertex.world_normal = normalize(normal_transform * vertex_normal);
@@ -489,47 +1455,92 @@ impl Renderer for GlRender {
                 .unwrap_or(DEFAULT_VERT_MAIN);
 
             // Perform text replacements for the various keywords.
-            let replaced_source = raw_source
+            raw_source
                 .replace("@position", "gl_Position")
                 .replace("@vertex.position", "_vertex_position_")
                 .replace("@vertex.normal", "_vertex_normal_")
                 .replace("@vertex.uv0", "_vertex_uv0_")
+                .replace("@vertex.tangent", "_vertex_tangent_")
                 .replace("@vertex.world_position", "_vertex_world_position_")
                 .replace("@vertex.world_normal", "_vertex_world_normal_")
                 .replace("@vertex.view_position", "_vertex_view_position_")
-                .replace("@vertex.view_normal", "_vertex_view_normal_");
-            let replaced_source = format!(r#"
-                    #version 330 core
+                .replace("@vertex.view_normal", "_vertex_view_normal_")
+        };
 
-                    {}
+        // Generates the full vertex shader source, using `model_transforms` to declare the model
+        // transforms and `model_setup` to initialize them at the start of `main()`.
+        let vert_source = |model_transforms: &str, model_setup: &str| format!(r#"
+                #version 330 core
 
-                    {}
+                {}
 
-                    layout(location = 0) in vec4 vertex_position;
-                    layout(location = 1) in vec3 vertex_normal;
-                    layout(location = 2) in vec2 vertex_uv0;
+                {}
 
-                    out vec4 _vertex_position_;
-                    out vec3 _vertex_normal_;
-                    out vec2 _vertex_uv0_;
-                    out vec4 _vertex_world_position_;
-                    out vec3 _vertex_world_normal_;
-                    out vec4 _vertex_view_position_;
-                    out vec3 _vertex_view_normal_;
+                {}
 
-                    void main(void) {{
-                        {}
-                    }}
-                "#,
-                BUILT_IN_UNIFORMS,
-                uniform_declarations,
-                replaced_source);
+                layout(location = 0) in vec4 vertex_position;
+                layout(location = 1) in vec3 vertex_normal;
+                layout(location = 2) in vec2 vertex_uv0;
+                layout(location = 3) in vec4 vertex_tangent;
 
-            GlShader::new(&self.context, replaced_source, ShaderType::Vertex).map_err(|err| BuildMaterialError)?
-        };
+                out vec4 _vertex_position_;
+                out vec3 _vertex_normal_;
+                out vec2 _vertex_uv0_;
+                out vec4 _vertex_tangent_;
+                out vec4 _vertex_world_position_;
+                out vec3 _vertex_world_normal_;
+                out vec4 _vertex_view_position_;
+                out vec3 _vertex_view_normal_;
+
+                void main(void) {{
+                    {}
+
+                    {}
+                }}
+            "#,
+            BUILT_IN_UNIFORMS,
+            model_transforms,
+            uniform_declarations,
+            model_setup,
+            vert_main);
+
+        let vert_shader = GlShader::new(
+            &self.context,
+            vert_source(MODEL_UNIFORMS, ""),
+            ShaderType::Vertex,
+        ).map_err(|err| BuildMaterialError)?;
+
+        // The instanced vertex shader reads the model transforms from per-instance attributes,
+        // which take up attribute locations 4 through 10 (one location per matrix column).
+        static INSTANCED_MODEL_TRANSFORMS: &'static str = r#"
+            uniform mat3 view_inverse_transpose;
+
+            layout(location = 4) in mat4 instance_model_transform;
+            layout(location = 8) in mat3 instance_normal_transform;
+
+            mat4 model_transform;
+            mat3 normal_transform;
+            mat3 view_normal_transform;
+            mat4 model_view_transform;
+            mat4 model_view_projection;
+        "#;
+
+        static INSTANCED_MODEL_SETUP: &'static str = r#"
+            model_transform = instance_model_transform;
+            normal_transform = instance_normal_transform;
+            view_normal_transform = view_inverse_transpose * normal_transform;
+            model_view_transform = view_transform * model_transform;
+            model_view_projection = projection_transform * model_view_transform;
+        "#;
+
+        let instanced_vert_shader = GlShader::new(
+            &self.context,
+            vert_source(INSTANCED_MODEL_TRANSFORMS, INSTANCED_MODEL_SETUP),
+            ShaderType::Vertex,
+        );
 
         // Generate the GLSL source for the fragment shader.
-        let frag_shader = {
+        let frag_source = {
             // Retrieve source string for the fragment shader.
             let raw_source =
                 source
@@ -542,23 +1553,30 @@ impl Renderer for GlRender {
             // Perform text replacements for the various keywords.
             let replaced_source = raw_source
                 .replace("@color", "_fragment_color_")
+                .replace("@shadow", "_shadow_factor_")
                 .replace("@vertex.position", "_vertex_position_")
                 .replace("@vertex.normal", "_vertex_normal_")
                 .replace("@vertex.uv0", "_vertex_uv0_")
+                .replace("@vertex.tangent", "_vertex_tangent_")
                 .replace("@vertex.world_position", "_vertex_world_position_")
                 .replace("@vertex.world_normal", "_vertex_world_normal_")
                 .replace("@vertex.view_position", "_vertex_view_position_")
                 .replace("@vertex.view_normal", "_vertex_view_normal_");
-            let replaced_source = format!(r#"
+            format!(r#"
                     #version 330 core
 
                     {}
 
                     {}
 
+                    {}
+
+                    {}
+
                     in vec4 _vertex_position_;
                     in vec3 _vertex_normal_;
                     in vec2 _vertex_uv0_;
+                    in vec4 _vertex_tangent_;
                     in vec4 _vertex_world_position_;
                     in vec3 _vertex_world_normal_;
                     in vec4 _vertex_view_position_;
@@ -571,21 +1589,35 @@ impl Renderer for GlRender {
                     }}
                 "#,
                 BUILT_IN_UNIFORMS,
+                MODEL_UNIFORMS,
                 uniform_declarations,
-                replaced_source);
-
-            GlShader::new(&self.context, replaced_source, ShaderType::Fragment).map_err(|err| BuildMaterialError)?
+                SHADOW_FUNCTIONS,
+                replaced_source)
         };
 
+        let frag_shader = GlShader::new(&self.context, &*frag_source, ShaderType::Fragment).map_err(|err| BuildMaterialError)?;
+
         let program = Program::new(&self.context, &[vert_shader, frag_shader]).map_err(|err| BuildMaterialError)?;
 
+        // Instancing is an optimization, so if the instanced program can't be built the material's
+        // mesh instances are drawn one at a time instead.
+        let instanced_frag_shader = GlShader::new(&self.context, &*frag_source, ShaderType::Fragment);
+        let instanced_program = match (instanced_vert_shader, instanced_frag_shader) {
+            (Ok(vert_shader), Ok(frag_shader)) => Program::new(&self.context, &[vert_shader, frag_shader]).ok(),
+            _ => None,
+        };
+
         let program_id = self.shader_counter.next();
         self.programs.insert(program_id, program);
+        if let Some(instanced_program) = instanced_program {
+            self.instanced_programs.insert(program_id, instanced_program);
+        }
 
         // BUILD MATERIAL OBJECT
         // =====================
 
         let mut material = Material::new(program_id);
+        material.set_blend_mode(source.blend);
 
         // Add the properties from the material declaration.
         for property in source.properties {
@@ -616,6 +1648,23 @@ impl Renderer for GlRender {
         self.shared_materials.get(&material_id)
     }
 
+    fn unregister_shared_material(&mut self, material_id: MaterialId) {
+        let material = match self.shared_materials.remove(&material_id) {
+            Some(material) => material,
+            None => return,
+        };
+
+        // The mesh instances in the material's bucket stay registered, but without a bucket
+        // they're no longer drawn.
+        self.mesh_instances_with_shared_materials.remove(&material_id);
+
+        // Dropping the programs deletes them on the GPU.
+        if !self.is_shader_in_use(material.shader()) {
+            self.programs.remove(material.shader());
+            self.instanced_programs.remove(material.shader());
+        }
+    }
+
     fn register_mesh(&mut self, mesh: &Mesh) -> GpuMesh {
         // Configure vertex attributes.
         let position = mesh.position();
@@ -638,6 +1687,24 @@ impl Renderer for GlRender {
             vertex_array.set_attrib(AttributeLocation::from_index(2), texcoord.into());
         }
 
+        if let Some(tangent) = mesh.tangent() {
+            vertex_array.set_attrib(AttributeLocation::from_index(3), tangent.into());
+        }
+
+        // Declare the per-instance transforms used for instanced rendering, one attribute for each
+        // matrix column. This must match `INSTANCED_MODEL_TRANSFORMS` in `build_material()`.
+        let columns = [(0, 4), (4, 4), (8, 4), (12, 4), (16, 3), (19, 3), (22, 3)];
+        for (index, &(offset, elements)) in columns.iter().enumerate() {
+            vertex_array.set_instance_attrib(
+                AttributeLocation::from_index(4 + index as u32),
+                AttribLayout {
+                    elements: elements,
+                    stride: INSTANCE_ELEMENTS,
+                    offset: offset,
+                },
+            );
+        }
+
         self.meshes.insert(
             mesh_id,
             MeshData {
@@ -646,11 +1713,17 @@ impl Renderer for GlRender {
                 normal_attribute: mesh.normal(),
                 uv_attribute: None,
                 element_count: mesh.indices().len(),
+                bounding_sphere: mesh.bounding_sphere(),
             });
 
         mesh_id
     }
 
+    fn unregister_mesh(&mut self, mesh: GpuMesh) {
+        // Dropping the vertex array deletes its buffers on the GPU.
+        self.meshes.remove(&mesh);
+    }
+
     fn register_texture(&mut self, texture: &Texture2d) -> GpuTexture {
         let (format, internal_format) = match texture.format() {
             DataFormat::Rgb => (TextureFormat::Rgb, TextureInternalFormat::Rgb),
@@ -709,14 +1782,69 @@ impl Renderer for GlRender {
         texture_id
     }
 
+    fn create_render_texture(&mut self, width: usize, height: usize) -> GpuTexture {
+        let texture = GlTexture2d::new(
+            &self.context,
+            TextureFormat::Rgba,
+            TextureInternalFormat::Rgba,
+            width,
+            height,
+            &*vec![0u8; width * height * 4],
+        ).expect("Unable to create render texture");
+
+        let depth_buffer = Renderbuffer::depth(&self.context, width, height).expect("Unable to create render texture depth buffer");
+        let framebuffer = Framebuffer::with_color_texture(&self.context, &texture, Some(&depth_buffer))
+            .expect("Unable to create render texture framebuffer");
+
+        let texture_id = self.texture_counter.next();
+        self.render_targets.insert(texture_id, RenderTarget {
+            framebuffer: framebuffer,
+            _depth_buffer: depth_buffer,
+        });
+
+        let old = self.textures.insert(texture_id, texture);
+        assert!(old.is_none());
+
+        texture_id
+    }
+
+    fn unregister_texture(&mut self, texture: GpuTexture) {
+        // The render target's framebuffer has to be deleted before the texture it renders into.
+        self.render_targets.remove(&texture);
+        self.textures.remove(&texture);
+    }
+
+    fn capture_next_frame(&mut self) {
+        self.capture_next_frame = true;
+    }
+
+    fn read_pixels(&self, target: Option<GpuTexture>) -> Texture2d {
+        let _stopwatch = Stopwatch::new("Read pixels");
+
+        let (width, height, pixels) = match target {
+            Some(texture) => {
+                let render_target = self.render_targets
+                    .get(&texture)
+                    .expect("Texture wasn't created with create_render_texture()");
+                let (width, height) = render_target.framebuffer.size();
+                (width, height, self.context.read_pixels(Some(&render_target.framebuffer)))
+            },
+            None => {
+                let &(width, height, ref pixels) = self.captured_frame
+                    .as_ref()
+                    .expect("No frame has been captured, call capture_next_frame() before draw()");
+                (width, height, pixels.clone())
+            },
+        };
+
+        Texture2d::new(width, height, DataFormat::Rgba, TextureData::u8(pixels))
+    }
+
     fn register_mesh_instance(&mut self, mesh_instance: MeshInstance) -> MeshInstanceId {
         let mesh_instance_id = self.mesh_instance_counter.next();
 
         // Add the mesh instance to the right bucket based on its material type.
-        match mesh_instance.material_type() {
-            &MaterialType::Shared(id) => self.mesh_instances_with_shared_materials.get_mut(&id).unwrap().push(mesh_instance_id),
-            &MaterialType::Owned(_) => self.mesh_instances_with_owned_material.push(mesh_instance_id),
-        }
+        self.add_to_material_bucket(mesh_instance_id, mesh_instance.material_type());
 
         let old = self.mesh_instances.insert(mesh_instance_id, mesh_instance);
         assert!(old.is_none());
@@ -732,6 +1860,22 @@ impl Renderer for GlRender {
         self.mesh_instances.get_mut(&id)
     }
 
+    fn set_mesh_instance_material(&mut self, id: MeshInstanceId, material: MaterialType) {
+        let old_material = self.mesh_instances.get(&id).expect("No such mesh instance").shared_material();
+        self.remove_from_material_bucket(id, old_material);
+
+        // Add the mesh instance to the bucket for its new material.
+        self.add_to_material_bucket(id, &material);
+
+        self.mesh_instances.get_mut(&id).unwrap().set_material(material);
+    }
+
+    fn unregister_mesh_instance(&mut self, id: MeshInstanceId) {
+        if let Some(mesh_instance) = self.mesh_instances.remove(&id) {
+            self.remove_from_material_bucket(id, mesh_instance.shared_material());
+        }
+    }
+
     fn register_anchor(&mut self, anchor: Anchor) -> AnchorId {
         let anchor_id = self.anchor_counter.next();
 
@@ -749,6 +1893,10 @@ impl Renderer for GlRender {
         self.anchors.get_mut(&anchor_id)
     }
 
+    fn unregister_anchor(&mut self, anchor_id: AnchorId) {
+        self.anchors.remove(&anchor_id);
+    }
+
     fn register_camera(&mut self, camera: Camera) -> CameraId {
         let camera_id = self.camera_counter.next();
 
@@ -766,6 +1914,10 @@ impl Renderer for GlRender {
         self.cameras.get_mut(&camera_id)
     }
 
+    fn unregister_camera(&mut self, camera_id: CameraId) {
+        self.cameras.remove(&camera_id);
+    }
+
     fn register_light(&mut self, light: Light) -> LightId {
         let light_id = self.light_counter.next();
 
@@ -783,9 +1935,21 @@ impl Renderer for GlRender {
         self.lights.get_mut(&light_id)
     }
 
+    fn unregister_light(&mut self, light_id: LightId) {
+        self.lights.remove(&light_id);
+    }
+
     fn set_ambient_light(&mut self, color: Color) {
         self.ambient_color = color;
     }
+
+    fn set_shadow_settings(&mut self, settings: ShadowSettings) {
+        if settings.resolution != self.shadow_settings.resolution {
+            self.shadow_atlas = Some(ShadowAtlas::new(&self.context, settings.resolution));
+        }
+
+        self.shadow_settings = settings;
+    }
 }
 
 unsafe impl Send for GlRender {}
@@ -808,6 +1972,305 @@ struct MeshData {
     normal_attribute: Option<VertexAttribute>,
     uv_attribute: Option<VertexAttribute>,
     element_count: usize,
+
+    /// A sphere containing the whole mesh, in the mesh's local space.
+    bounding_sphere: BoundingSphere,
+}
+
+/// The data for a light as it's sent to the shader, calculated once per camera.
+#[derive(Debug, Clone, Copy)]
+struct ActiveLight {
+    light_type: i32,
+    color: Color,
+    strength: f32,
+    position: Point,
+    position_view: Point,
+
+    /// The radius of a point light or the range of a spot light.
+    radius: f32,
+
+    direction: Vector3,
+    direction_view: Vector3,
+    cone_inner: f32,
+    cone_outer: f32,
+
+    /// The light's shadow map, if it's casting shadows for the current camera.
+    shadow_map: Option<ShadowMap>,
+}
+
+impl ActiveLight {
+    /// Estimates how strongly the light affects an object with the specified bounding sphere,
+    /// returning `None` if the light can't reach the object.
+    ///
+    /// The estimate uses the same distance attenuation as the built-in lit materials, evaluated at
+    /// the point on the bounding sphere closest to the light. Spot lights are treated like point
+    /// lights, ignoring their cone.
+    fn influence(&self, center: Point, radius: f32) -> Option<f32> {
+        match self.light_type {
+            // Directional lights affect everything equally.
+            2 => Some(self.strength),
+
+            _ => {
+                let distance = (center.distance(&self.position) - radius).max(0.0);
+                if distance >= self.radius {
+                    return None;
+                }
+
+                let falloff = 1.0 - distance / self.radius;
+                Some(self.strength * falloff * falloff)
+            },
+        }
+    }
+}
+
+/// The location of a light's shadow map within the shadow atlas.
+#[derive(Debug, Clone, Copy)]
+struct ShadowMap {
+    /// The transform from world space to the clip space of the light.
+    transform: Matrix4,
+
+    /// The offset and size of the light's tile within the atlas, in texture coordinates:
+    /// `[x, y, width, height]`.
+    tile: [f32; 4],
+}
+
+/// The framebuffer used by cameras rendering into a texture created with
+/// `create_render_texture()`. The color texture itself is stored with the other textures.
+#[derive(Debug)]
+struct RenderTarget {
+    // NOTE: The framebuffer must be declared before the depth buffer so that it's dropped first.
+    framebuffer: Framebuffer,
+
+    /// Only held so that it lives as long as the framebuffer.
+    _depth_buffer: Renderbuffer,
+}
+
+/// The materials for the built-in post effects.
+#[derive(Debug)]
+struct PostMaterials {
+    tone_mapping: Material,
+    gamma_correction: Material,
+    bloom: Material,
+    fxaa: Material,
+    color_grading: Material,
+}
+
+/// The offscreen buffers used to apply post effects to a camera's image.
+#[derive(Debug)]
+struct PostTargets {
+    /// The scene is rendered into the first buffer, then the post effects alternate between
+    /// reading from one buffer and drawing into the other.
+    buffers: [PostBuffer; 2],
+
+    /// Only held so that it lives as long as the framebuffers.
+    _depth_buffer: Renderbuffer,
+}
+
+impl PostTargets {
+    fn new(context: &Context, width: usize, height: usize) -> PostTargets {
+        // Both buffers share a depth buffer, which is only used while rendering the scene.
+        let depth_buffer = Renderbuffer::depth(context, width, height)
+            .expect("Unable to create post-processing depth buffer");
+        let buffers = [
+            PostBuffer::new(context, width, height, &depth_buffer),
+            PostBuffer::new(context, width, height, &depth_buffer),
+        ];
+
+        PostTargets {
+            buffers: buffers,
+            _depth_buffer: depth_buffer,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PostBuffer {
+    framebuffer: Framebuffer,
+    texture: GlTexture2d,
+}
+
+impl PostBuffer {
+    fn new(context: &Context, width: usize, height: usize, depth_buffer: &Renderbuffer) -> PostBuffer {
+        // Use a floating point format so that colors brighter than white survive until they're
+        // tone mapped.
+        let texture = GlTexture2d::render_target(context, TextureInternalFormat::Rgba16f, width, height)
+            .expect("Unable to create post-processing buffer");
+        let framebuffer = Framebuffer::with_color_texture(context, &texture, Some(depth_buffer))
+            .expect("Unable to create post-processing framebuffer");
+
+        PostBuffer {
+            framebuffer: framebuffer,
+            texture: texture,
+        }
+    }
+}
+
+/// The depth texture that all shadow maps are rendered into, see `render_shadow_maps()`.
+#[derive(Debug)]
+struct ShadowAtlas {
+    // NOTE: The framebuffer must be declared before the texture so that it's dropped first.
+    framebuffer: Framebuffer,
+    depth_texture: GlTexture2d,
+}
+
+impl ShadowAtlas {
+    /// Creates a shadow atlas with room for shadow maps of size `resolution`.
+    fn new(context: &Context, resolution: usize) -> ShadowAtlas {
+        let size = resolution * SHADOW_ATLAS_TILES;
+        let depth_texture = GlTexture2d::depth(context, size, size).expect("Unable to create shadow atlas texture");
+        let framebuffer = Framebuffer::with_depth_texture(context, &depth_texture)
+            .expect("Unable to create shadow atlas framebuffer");
+
+        ShadowAtlas {
+            framebuffer: framebuffer,
+            depth_texture: depth_texture,
+        }
+    }
+}
+
+/// Builds the program used to render shadow maps, which only writes depth.
+fn build_shadow_program(context: &Context) -> Program {
+    static SHADOW_VERT_SOURCE: &'static str = r#"
+        #version 330 core
+
+        uniform mat4 model_view_projection;
+
+        layout(location = 0) in vec4 vertex_position;
+
+        void main(void) {
+            gl_Position = model_view_projection * vertex_position;
+        }
+    "#;
+
+    static SHADOW_FRAG_SOURCE: &'static str = r#"
+        #version 330 core
+
+        void main(void) {}
+    "#;
+
+    let vert_shader = GlShader::new(context, SHADOW_VERT_SOURCE, ShaderType::Vertex).expect("Failed to compile shadow vertex shader");
+    let frag_shader = GlShader::new(context, SHADOW_FRAG_SOURCE, ShaderType::Fragment).expect("Failed to compile shadow fragment shader");
+    Program::new(context, &[vert_shader, frag_shader]).expect("Failed to link shadow program")
+}
+
+/// The light uniform arrays for a single draw call.
+#[derive(Debug)]
+struct LightUniforms {
+    light_type: [i32; MAX_LIGHTS_PER_OBJECT],
+    light_strength: [f32; MAX_LIGHTS_PER_OBJECT],
+    light_color: [Color; MAX_LIGHTS_PER_OBJECT],
+    light_position: [Point; MAX_LIGHTS_PER_OBJECT],
+    light_position_view: [Point; MAX_LIGHTS_PER_OBJECT],
+    light_radius: [f32; MAX_LIGHTS_PER_OBJECT],
+    light_direction: [Vector3; MAX_LIGHTS_PER_OBJECT],
+    light_direction_view: [Vector3; MAX_LIGHTS_PER_OBJECT],
+    light_cone_inner: [f32; MAX_LIGHTS_PER_OBJECT],
+    light_cone_outer: [f32; MAX_LIGHTS_PER_OBJECT],
+    light_shadow_transform: [[f32; 16]; MAX_LIGHTS_PER_OBJECT],
+    light_shadow_tile: [[f32; 4]; MAX_LIGHTS_PER_OBJECT],
+}
+
+impl LightUniforms {
+    /// Fills the uniform arrays from the lights in `lights` selected by `select_lights()`.
+    fn new(lights: &[ActiveLight], selected_lights: &[Option<usize>]) -> LightUniforms {
+        let mut uniforms = LightUniforms {
+            light_type: [0; MAX_LIGHTS_PER_OBJECT],
+            light_strength: [0.0; MAX_LIGHTS_PER_OBJECT],
+            light_color: [Color::rgb(0.0, 0.0, 0.0); MAX_LIGHTS_PER_OBJECT],
+            light_position: [Point::origin(); MAX_LIGHTS_PER_OBJECT],
+            light_position_view: [Point::origin(); MAX_LIGHTS_PER_OBJECT],
+            light_radius: [0.0; MAX_LIGHTS_PER_OBJECT],
+            light_direction: [Vector3::zero(); MAX_LIGHTS_PER_OBJECT],
+            light_direction_view: [Vector3::zero(); MAX_LIGHTS_PER_OBJECT],
+            light_cone_inner: [0.0; MAX_LIGHTS_PER_OBJECT],
+            light_cone_outer: [0.0; MAX_LIGHTS_PER_OBJECT],
+            light_shadow_transform: [[0.0; 16]; MAX_LIGHTS_PER_OBJECT],
+            light_shadow_tile: [[0.0; 4]; MAX_LIGHTS_PER_OBJECT],
+        };
+
+        let selected_lights = selected_lights.iter().filter_map(|&index| index.map(|index| &lights[index]));
+        for (index, light) in selected_lights.enumerate() {
+            uniforms.light_type[index] = light.light_type;
+            uniforms.light_color[index] = light.color;
+            uniforms.light_strength[index] = light.strength;
+            uniforms.light_position[index] = light.position;
+            uniforms.light_position_view[index] = light.position_view;
+            uniforms.light_radius[index] = light.radius;
+            uniforms.light_direction[index] = light.direction;
+            uniforms.light_direction_view[index] = light.direction_view;
+            uniforms.light_cone_inner[index] = light.cone_inner;
+            uniforms.light_cone_outer[index] = light.cone_outer;
+
+            // Lights without a shadow map keep an empty tile, which the shader checks for.
+            if let Some(shadow_map) = light.shadow_map {
+                uniforms.light_shadow_transform[index] = *shadow_map.transform.raw_data();
+                uniforms.light_shadow_tile[index] = shadow_map.tile;
+            }
+        }
+
+        uniforms
+    }
+
+    fn apply<'a>(&'a self, draw_builder: &mut DrawBuilder<'a>) {
+        draw_builder.uniform("light_type", &self.light_type[..]);
+        draw_builder.uniform("light_strength", &self.light_strength[..]);
+        draw_builder.uniform("light_color", Color::as_slice_of_arrays(&self.light_color));
+        draw_builder.uniform("light_position", Point::as_slice_of_arrays(&self.light_position));
+        draw_builder.uniform("light_position_view", Point::as_slice_of_arrays(&self.light_position_view));
+        draw_builder.uniform("light_radius", &self.light_radius[..]);
+        draw_builder.uniform("light_direction", Vector3::as_slice_of_arrays(&self.light_direction));
+        draw_builder.uniform("light_direction_view", Vector3::as_slice_of_arrays(&self.light_direction_view));
+        draw_builder.uniform("light_cone_inner", &self.light_cone_inner[..]);
+        draw_builder.uniform("light_cone_outer", &self.light_cone_outer[..]);
+        draw_builder.uniform(
+            "light_shadow_transform",
+            GlMatrix4Array {
+                data: &self.light_shadow_transform[..],
+                transpose: true,
+            },
+        );
+        draw_builder.uniform("light_shadow_tile", &self.light_shadow_tile[..]);
+    }
+}
+
+/// Selects the lights with the most influence on an object with the specified bounding sphere,
+/// returning their indices in `lights`.
+///
+/// The selected lights are sorted from most to least influential, unused slots are `None`.
+fn select_lights(
+    lights: &[ActiveLight],
+    center: Point,
+    radius: f32,
+) -> [Option<usize>; MAX_LIGHTS_PER_OBJECT] {
+    let mut selected: [Option<(f32, usize)>; MAX_LIGHTS_PER_OBJECT] = [None; MAX_LIGHTS_PER_OBJECT];
+
+    for (light_index, light) in lights.iter().enumerate() {
+        let influence = match light.influence(center, radius) {
+            Some(influence) => influence,
+            None => continue,
+        };
+
+        // Find where the light belongs in the sorted list, if it's influential enough to be
+        // included at all.
+        let position = selected.iter().position(|slot| match *slot {
+            Some((other_influence, _)) => influence > other_influence,
+            None => true,
+        });
+
+        if let Some(position) = position {
+            // Shift less influential lights down to make room, dropping the least influential.
+            for index in (position + 1..MAX_LIGHTS_PER_OBJECT).rev() {
+                selected[index] = selected[index - 1];
+            }
+            selected[position] = Some((influence, light_index));
+        }
+    }
+
+    let mut result = [None; MAX_LIGHTS_PER_OBJECT];
+    for (slot, selected) in result.iter_mut().zip(selected.iter()) {
+        *slot = selected.map(|(_, light_index)| light_index);
+    }
+    result
 }
 
 impl Into<AttribLayout> for VertexAttribute {
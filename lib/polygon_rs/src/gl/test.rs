@@ -0,0 +1,81 @@
+use math::*;
+use super::{ActiveLight, MAX_LIGHTS_PER_OBJECT, select_lights};
+
+fn light(light_type: i32, position: Point, radius: f32, strength: f32) -> ActiveLight {
+    ActiveLight {
+        light_type: light_type,
+        color: Color::rgb(1.0, 1.0, 1.0),
+        strength: strength,
+        position: position,
+        position_view: position,
+        radius: radius,
+        direction: Vector3::zero(),
+        direction_view: Vector3::zero(),
+        cone_inner: 0.0,
+        cone_outer: 0.0,
+        shadow_map: None,
+    }
+}
+
+fn point_light(position: Point, radius: f32, strength: f32) -> ActiveLight {
+    light(1, position, radius, strength)
+}
+
+fn directional_light(strength: f32) -> ActiveLight {
+    light(2, Point::origin(), 0.0, strength)
+}
+
+/// Gets the strength of each selected light, which the tests use to tell the lights apart.
+fn selected_strengths(lights: &[ActiveLight], center: Point, radius: f32) -> Vec<f32> {
+    select_lights(lights, center, radius)
+        .iter()
+        .filter_map(|index| index.map(|index| lights[index].strength))
+        .collect()
+}
+
+#[test]
+fn select_lights_by_influence() {
+    let lights = [
+        // Far away, so its falloff outweighs its strength.
+        point_light(Point::new(8.0, 0.0, 0.0), 10.0, 4.0),
+        point_light(Point::new(1.0, 0.0, 0.0), 10.0, 1.0),
+        point_light(Point::new(0.0, 2.0, 0.0), 10.0, 3.0),
+
+        // Out of range.
+        point_light(Point::new(0.0, 0.0, 20.0), 10.0, 100.0),
+    ];
+
+    assert_eq!(vec![3.0, 1.0, 4.0], selected_strengths(&lights, Point::origin(), 0.0));
+
+    // Lights reach anything that overlaps their radius, even if the center is out of range.
+    assert_eq!(vec![100.0], selected_strengths(&lights, Point::new(0.0, 0.0, 28.0), 1.0));
+    assert!(selected_strengths(&lights, Point::new(0.0, 0.0, 40.0), 1.0).is_empty());
+}
+
+#[test]
+fn select_lights_cap() {
+    // Twice as many lights as can be selected, getting stronger as they go.
+    let lights = (0..MAX_LIGHTS_PER_OBJECT * 2)
+        .map(|index| point_light(Point::origin(), 10.0, index as f32 + 1.0))
+        .collect::<Vec<_>>();
+
+    // Only the strongest lights are selected, strongest first.
+    let expected = (MAX_LIGHTS_PER_OBJECT + 1..MAX_LIGHTS_PER_OBJECT * 2 + 1)
+        .rev()
+        .map(|strength| strength as f32)
+        .collect::<Vec<_>>();
+    assert_eq!(expected, selected_strengths(&lights, Point::origin(), 1.0));
+}
+
+#[test]
+fn select_lights_directional() {
+    let lights = [
+        point_light(Point::origin(), 10.0, 2.0),
+        directional_light(1.0),
+        directional_light(3.0),
+    ];
+
+    // Directional lights always affect objects at full strength, no matter how far away they are.
+    assert_eq!(vec![3.0, 2.0, 1.0], selected_strengths(&lights, Point::origin(), 1.0));
+    assert_eq!(vec![3.0, 1.0], selected_strengths(&lights, Point::new(1000.0, 0.0, 0.0), 1.0));
+}
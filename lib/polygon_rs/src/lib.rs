@@ -17,7 +17,9 @@ pub mod gl;
 pub mod light;
 pub mod material;
 pub mod mesh_instance;
+pub mod post_process;
 pub mod shader;
+pub mod software;
 pub mod texture;
 
 use anchor::*;
@@ -28,10 +30,11 @@ use light::*;
 use material::*;
 use math::Color;
 use mesh_instance::*;
+use post_process::PostEffect;
 use texture::*;
 
 /// Identifies mesh data that has been sent to the GPU.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct GpuMesh(usize);
 derive_Counter!(GpuMesh);
 
@@ -52,12 +55,50 @@ pub trait Renderer: 'static + Send {
     /// Gets a registered material.
     fn get_material(&self, material_id: MaterialId) -> Option<&Material>;
 
+    /// Removes a shared material from the renderer.
+    ///
+    /// Mesh instances still using the material aren't drawn until they're given a new material
+    /// with `set_mesh_instance_material()`, and custom post effects using it are skipped. The
+    /// material's shader is destroyed once no registered material uses it, so copies of the
+    /// material can't be registered afterwards. Does nothing if the material has already been
+    /// removed.
+    fn unregister_shared_material(&mut self, material_id: MaterialId);
+
     /// Registers mesh data with the renderer, returning a unique id for the mesh.
     fn register_mesh(&mut self, mesh: &Mesh) -> GpuMesh;
 
+    /// Removes mesh data from the renderer, freeing its GPU memory.
+    ///
+    /// Mesh instances still using the mesh aren't drawn until they're given a new mesh. Does
+    /// nothing if the mesh has already been removed.
+    fn unregister_mesh(&mut self, mesh: GpuMesh);
+
     /// Registers texture data with the renderer, returning a unique id for the texture.
     fn register_texture(&mut self, texture: &Texture2d) -> GpuTexture;
 
+    /// Creates an empty texture that cameras can render into, see `Camera::set_target()`.
+    ///
+    /// The texture can be used by materials like any other texture.
+    fn create_render_texture(&mut self, width: usize, height: usize) -> GpuTexture;
+
+    /// Removes a texture from the renderer, freeing its GPU memory.
+    ///
+    /// Materials still using the texture behave as if it had never been set, and cameras that
+    /// render into it aren't drawn. Does nothing if the texture has already been removed.
+    fn unregister_texture(&mut self, texture: GpuTexture);
+
+    /// Makes the next call to `draw()` save the window's contents for `read_pixels()`.
+    ///
+    /// Reading the window is slow, so renderers only do it when asked to.
+    fn capture_next_frame(&mut self);
+
+    /// Reads the pixels of the most recently drawn frame.
+    ///
+    /// If `target` is `None` this reads the window's contents as of the last call to `draw()`
+    /// that followed a call to `capture_next_frame()`, otherwise it reads a texture created with
+    /// `create_render_texture()`. The result is always in RGBA format.
+    fn read_pixels(&self, target: Option<GpuTexture>) -> Texture2d;
+
     /// Registers a mesh instance with the renderer, returning a unique id for that mesh instance.
     fn register_mesh_instance(&mut self, mesh_instance: MeshInstance) -> MeshInstanceId;
 
@@ -67,6 +108,17 @@ pub trait Renderer: 'static + Send {
     /// Gets a mutable reference to a registered mesh instance.
     fn get_mesh_instance_mut(&mut self, id: MeshInstanceId) -> Option<&mut MeshInstance>;
 
+    /// Changes the material used by a registered mesh instance.
+    ///
+    /// Use this instead of `MeshInstance::set_material()` once the mesh instance has been
+    /// registered so that the renderer can keep track of which material it uses.
+    fn set_mesh_instance_material(&mut self, id: MeshInstanceId, material: MaterialType);
+
+    /// Removes a mesh instance from the renderer.
+    ///
+    /// Does nothing if the mesh instance has already been removed.
+    fn unregister_mesh_instance(&mut self, id: MeshInstanceId);
+
     /// Registers an anchor with the renderer, returning a unique id for the anchor.
     fn register_anchor(&mut self, anchor: Anchor) -> AnchorId;
 
@@ -76,6 +128,12 @@ pub trait Renderer: 'static + Send {
     /// Gets a mutable reference to a registered anchor.
     fn get_anchor_mut(&mut self, anchor_id: AnchorId) -> Option<&mut Anchor>;
 
+    /// Removes an anchor from the renderer.
+    ///
+    /// Mesh instances, cameras, and lights still attached to the anchor are ignored until
+    /// they're attached to another anchor. Does nothing if the anchor has already been removed.
+    fn unregister_anchor(&mut self, anchor_id: AnchorId);
+
     /// Registers a camera with the renderer, returning a unique id for the camera.
     fn register_camera(&mut self, camera: Camera) -> CameraId;
 
@@ -85,6 +143,11 @@ pub trait Renderer: 'static + Send {
     /// Gets a mutable reference to a registered camera.
     fn get_camera_mut(&mut self, camera_id: CameraId) -> Option<&mut Camera>;
 
+    /// Removes a camera from the renderer.
+    ///
+    /// Does nothing if the camera has already been removed.
+    fn unregister_camera(&mut self, camera_id: CameraId);
+
     /// Registers a light with the renderer, returning a unique id for the light.
     fn register_light(&mut self, light: Light) -> LightId;
 
@@ -94,27 +157,76 @@ pub trait Renderer: 'static + Send {
     /// Gets a mutable reference to a registered light.
     fn get_light_mut(&mut self, light_id: LightId) -> Option<&mut Light>;
 
+    /// Removes a light from the renderer.
+    ///
+    /// Does nothing if the light has already been removed.
+    fn unregister_light(&mut self, light_id: LightId);
+
     fn set_ambient_light(&mut self, color: Color);
+
+    /// Configures the shadows cast by lights with `casts_shadows` set.
+    fn set_shadow_settings(&mut self, settings: ShadowSettings);
 }
 
 /// A helper struct for selecting and initializing the most suitable renderer for the client's
 /// needs.
 pub struct RendererBuilder<'a> {
-    window: &'a Window,
+    /// The window to render to, or `None` to use the software renderer.
+    window: Option<&'a Window>,
+
+    /// The size of the software renderer's image, unused when rendering to a window.
+    size: (usize, usize),
+
+    post_effects: Vec<PostEffect>,
 }
 
 impl<'a> RendererBuilder<'a> {
     /// Creates a new builder object.
     pub fn new(window: &Window) -> RendererBuilder {
         RendererBuilder {
-            window: window,
+            window: Some(window),
+            size: (0, 0),
+            post_effects: Vec::new(),
         }
     }
 
+    /// Creates a builder for a software renderer, which draws into an in-memory image of the
+    /// specified size instead of a window.
+    ///
+    /// The software renderer doesn't need a GPU, so it can be used for testing, see the
+    /// `software` module for what it supports.
+    pub fn software(width: usize, height: usize) -> RendererBuilder<'static> {
+        RendererBuilder {
+            window: None,
+            size: (width, height),
+            post_effects: Vec::new(),
+        }
+    }
+
+    /// Sets the post effects applied to cameras that don't specify their own, see
+    /// `Camera::set_post_effects()`.
+    ///
+    /// By default no post effects are applied. The software renderer doesn't support post effects
+    /// and ignores them.
+    pub fn post_effects(&mut self, post_effects: Vec<PostEffect>) -> &mut RendererBuilder<'a> {
+        self.post_effects = post_effects;
+        self
+    }
+
     /// Constructs a new renderer using the options set in the builder.
     pub fn build(&mut self) -> Box<Renderer> {
-        let renderer = gl::GlRender::new(self.window).unwrap();
-        Box::new(renderer) as Box<Renderer>
+        match self.window {
+            Some(window) => {
+                let mut renderer = gl::GlRender::new(window).unwrap();
+                renderer.set_default_post_effects(self.post_effects.clone());
+                Box::new(renderer) as Box<Renderer>
+            },
+
+            None => {
+                let (width, height) = self.size;
+                Box::new(software::SoftwareRender::new(width, height)) as Box<Renderer>
+            },
+        }
     }
 }
 
@@ -6,6 +6,16 @@ pub struct Light {
     pub data: LightData,
     pub color: Color,
     pub strength: f32,
+
+    /// Disabled lights are ignored when rendering.
+    pub enabled: bool,
+
+    /// Whether or not mesh instances block the light, see `ShadowSettings`.
+    ///
+    /// Only directional and spot lights cast shadows, this is ignored for point lights. Lights
+    /// don't cast shadows by default.
+    pub casts_shadows: bool,
+
     anchor: Option<AnchorId>,
 }
 
@@ -15,6 +25,35 @@ impl Light {
             data: LightData::Point { radius: radius },
             color: color,
             strength: strength,
+            enabled: true,
+            casts_shadows: false,
+            anchor: None,
+        }
+    }
+
+    /// Creates a new spot light.
+    ///
+    /// The light shines along the forward axis of its anchor. `inner_angle` and `outer_angle`
+    /// are the angles in radians between the light's direction and the edges of the cone, the
+    /// light is at full strength inside the inner angle and fades out towards the outer angle.
+    pub fn spot(range: f32, inner_angle: f32, outer_angle: f32, strength: f32, color: Color) -> Light {
+        debug_assert!(
+            inner_angle <= outer_angle,
+            "Inner angle must not be greater than outer angle, inner: {}, outer: {}",
+            inner_angle,
+            outer_angle
+        );
+
+        Light {
+            data: LightData::Spot {
+                range: range,
+                inner_angle: inner_angle,
+                outer_angle: outer_angle,
+            },
+            color: color,
+            strength: strength,
+            enabled: true,
+            casts_shadows: false,
             anchor: None,
         }
     }
@@ -24,6 +63,8 @@ impl Light {
             data: LightData::Directional { direction: direction.normalized() },
             color: color,
             strength: strength,
+            enabled: true,
+            casts_shadows: false,
             anchor: None,
         }
     }
@@ -41,6 +82,48 @@ impl Light {
 pub enum LightData {
     Point { radius: f32 },
     Directional { direction: Vector3 },
+    Spot { range: f32, inner_angle: f32, outer_angle: f32 },
+}
+
+/// Configures the shadows cast by lights with `casts_shadows` set.
+///
+/// The settings are shared by all lights. Only a limited number of lights cast shadows at once,
+/// if there are more shadow casting lights than that the strongest ones are used.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowSettings {
+    /// The width and height in pixels of each light's shadow map.
+    ///
+    /// Larger shadow maps give sharper shadows but take more memory and time to render.
+    pub resolution: usize,
+
+    /// The depth offset used when checking if a point is in shadow.
+    ///
+    /// Too little bias makes surfaces incorrectly shadow themselves ("shadow acne"), too much
+    /// makes shadows detach from the objects casting them.
+    pub bias: f32,
+
+    /// The number of texels sampled on each side of a point when filtering shadows.
+    ///
+    /// 0 gives hard shadow edges, larger values give softer edges but sample the shadow map
+    /// `(2 * pcf_radius + 1)^2` times per light.
+    pub pcf_radius: u32,
+
+    /// How far from the camera directional lights cast shadows, in world units.
+    ///
+    /// Directional lights illuminate the whole scene, so their shadow map only covers the area
+    /// around the camera.
+    pub directional_distance: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> ShadowSettings {
+        ShadowSettings {
+            resolution: 1024,
+            bias: 0.005,
+            pcf_radius: 1,
+            directional_distance: 50.0,
+        }
+    }
 }
 
 /// Identifies a light that has been registered with the renderer.
@@ -28,6 +28,13 @@
 //!
 //! TODO: How do you specify vertex and frag shaders. What are their inputs and outputs?
 //!
+//! ## Blending
+//!
+//! By default materials are opaque. A material can declare that it blends with what's behind it
+//! with a `blend` item, e.g. `blend alpha;`, see `BlendMode` for the available modes. Mesh
+//! instances using transparent materials are drawn after all opaque ones, sorted from back to
+//! front, and don't write to the depth buffer.
+//!
 //! ## Vertex attributes
 //!
 //! TODO: What are the input and output vertex attributes?
@@ -48,6 +55,11 @@
 //! - `projection_transform: Matrix4` - The transform converting points in view space to projection space.
 //! - `model_view_project: Matrix4` - The transform converting points in model space to projection space.
 //!
+//! When many mesh instances share a mesh and material they may be drawn with a single instanced
+//! draw call. The model transforms are still available in the vertex program under the same
+//! names, but they're calculated per instance rather than set as uniforms, so they can't be used
+//! in the fragment program.
+//!
 //! Lighting:
 //!
 //! - `global_ambient: Color` - The ambient light given as a color.
@@ -58,14 +70,45 @@
 //! - `light_strength: f32` - The strength of the current light.
 //! - `light_color: Color` - The color of the current light.
 //! - `light_type: u32` - An integer constant specifying the type of the current light: 0 means no
-//!   light, 1 means point light, 2 means directional light. All light-related uniforms will be present
-//!   regardless of the light type, but uniforms not used for the current light type will not be
-//!   set, so reading them will yield some kind of garbage.
-//! - `light_radius: f32` - The radius of the current light (only for point lights).
+//!   light, 1 means point light, 2 means directional light, 3 means spot light. All light-related
+//!   uniforms will be present regardless of the light type, but uniforms not used for the current
+//!   light type will not be set, so reading them will yield some kind of garbage.
+//! - `light_radius: f32` - The radius of the current light (only for point lights), or the range
+//!   of the current light (only for spot lights).
 //! - `light_direction: Vector3` - The normalized direction in world space of the current light (only
-//!   for directional lights).
+//!   for directional and spot lights).
 //! - `light_direction_view` - The normalized direction in view space of the current light (only
-//!   for directional lights).
+//!   for directional and spot lights).
+//! - `light_cone_inner: f32` - The cosine of the angle inside which the current light is at full
+//!   strength (only for spot lights).
+//! - `light_cone_outer: f32` - The cosine of the angle outside which the current light has no
+//!   effect (only for spot lights).
+//!
+//! Shadows:
+//!
+//! - `shadow_atlas: Texture2d` - The depth texture containing the shadow maps of all shadow
+//!   casting lights.
+//! - `shadow_bias: f32` - The depth offset to use when comparing against the shadow maps.
+//! - `shadow_pcf_radius: i32` - The number of texels to sample on each side of a point when
+//!   filtering shadows.
+//! - `light_shadow_transform: Matrix4` - The transform converting points in world space to the
+//!   clip space of the current light's shadow map.
+//! - `light_shadow_tile: Vector4` - The offset (`xy`) and size (`zw`) of the current light's
+//!   shadow map within `shadow_atlas`, in texture coordinates. The size is zero if the current
+//!   light doesn't have a shadow map.
+//!
+//! Fragment programs can use `@shadow(light_index, world_position)` to get how much of a light
+//! reaches a point, from `0.0` (fully shadowed) to `1.0` (fully lit), taking care of the shadow
+//! map lookup and filtering. Lights without a shadow map always return `1.0`.
+//!
+//! Post effects:
+//!
+//! - `source_texture: Texture2d` - The camera's image before the current post effect is applied.
+//! - `source_texel_size: Vector2` - The size of one pixel of `source_texture` in texture
+//!   coordinates.
+//!
+//! These are only set when the material is drawn as a post effect, see the `post_process`
+//! module.
 
 use math::*;
 use shader::Shader;
@@ -73,7 +116,7 @@ use std::collections::HashMap;
 use std::collections::hash_map::Iter as HashMapIter;
 use texture::GpuTexture;
 
-pub use polygon_material::material_source::{Error as MaterialSourceError, MaterialSource};
+pub use polygon_material::material_source::{BlendMode, Error as MaterialSourceError, MaterialSource};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MaterialId(usize);
@@ -84,6 +127,7 @@ derive_Counter!(MaterialId);
 pub struct Material {
     shader: Shader,
     properties: HashMap<String, MaterialProperty>,
+    blend_mode: BlendMode,
 }
 
 impl Material {
@@ -92,6 +136,7 @@ impl Material {
         Material {
             shader: shader,
             properties: HashMap::new(),
+            blend_mode: BlendMode::Opaque,
         }
     }
 
@@ -100,6 +145,18 @@ impl Material {
         &self.shader
     }
 
+    /// Gets how the material blends with what's already been drawn.
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Sets how the material blends with what's already been drawn.
+    ///
+    /// Materials start out with the blend mode declared in their source.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
     /// Gets an iterator yielding the the current material properties.
     pub fn properties(&self) -> HashMapIter<String, MaterialProperty> {
         self.properties.iter()
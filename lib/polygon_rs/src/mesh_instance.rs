@@ -20,7 +20,10 @@ use material::*;
 pub struct MeshInstance {
     mesh: GpuMesh,
     material: MaterialType,
-    anchor: Option<AnchorId>
+    anchor: Option<AnchorId>,
+    layer: u32,
+    visible: bool,
+    casts_shadows: bool,
 }
 
 impl MeshInstance {
@@ -30,6 +33,9 @@ impl MeshInstance {
             mesh: mesh,
             material: MaterialType::Shared(material),
             anchor: None,
+            layer: 0,
+            visible: true,
+            casts_shadows: true,
         }
     }
 
@@ -39,6 +45,9 @@ impl MeshInstance {
             mesh: mesh,
             material: MaterialType::Owned(material),
             anchor: None,
+            layer: 0,
+            visible: true,
+            casts_shadows: true,
         }
     }
 
@@ -81,6 +90,14 @@ impl MeshInstance {
         }
     }
 
+    /// Sets the material used by the mesh instance.
+    ///
+    /// If the mesh instance has already been registered use
+    /// `Renderer::set_mesh_instance_material()` instead.
+    pub fn set_material(&mut self, material: MaterialType) {
+        self.material = material;
+    }
+
     /// Attaches the mesh instance to the specified anchor.
     pub fn set_anchor(&mut self, anchor_id: AnchorId) {
         self.anchor = Some(anchor_id);
@@ -90,6 +107,45 @@ impl MeshInstance {
     pub fn anchor(&self) -> Option<AnchorId> {
         self.anchor
     }
+
+    /// Sets the layer that the mesh instance is in.
+    ///
+    /// Cameras only render mesh instances in layers that are part of their layer mask. Mesh
+    /// instances start in layer 0.
+    pub fn set_layer(&mut self, layer: u32) {
+        debug_assert!(layer < 32, "Layer must be less than 32: {}", layer);
+        self.layer = layer;
+    }
+
+    /// Gets the layer that the mesh instance is in.
+    pub fn layer(&self) -> u32 {
+        self.layer
+    }
+
+    /// Sets whether or not the mesh instance is drawn.
+    ///
+    /// Hidden mesh instances stay registered with the renderer, so they can be shown again
+    /// later. Mesh instances start visible.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    /// Gets whether or not the mesh instance is drawn.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Sets whether or not the mesh instance blocks light from shadow casting lights.
+    ///
+    /// Mesh instances cast shadows by default. Hidden mesh instances never cast shadows.
+    pub fn set_casts_shadows(&mut self, casts_shadows: bool) {
+        self.casts_shadows = casts_shadows;
+    }
+
+    /// Gets whether or not the mesh instance blocks light from shadow casting lights.
+    pub fn casts_shadows(&self) -> bool {
+        self.casts_shadows
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -0,0 +1,88 @@
+//! Full-screen effects applied to a camera's image after the scene has been rendered.
+//!
+//! When a camera has post effects the scene is rendered into an offscreen HDR buffer instead of
+//! directly into the camera's target. Each effect then draws a full-screen quad that reads the
+//! previous effect's output, and the last effect draws into the camera's viewport.
+//!
+//! Effects are materials like any other, except their fragment program reads the image so far
+//! from the built-in `source_texture` uniform and writes the processed color to `@color`. The
+//! built-in effects are defined by the `.material` files in `resources/post_process`, and custom
+//! effects can be built from any material source that follows the same convention:
+//!
+//! ```glsl
+//! program frag {
+//!     vec4 color = texture(source_texture, @vertex.uv0);
+//!     @color = vec4(1.0 - color.rgb, color.a);
+//! }
+//! ```
+//!
+//! `source_texel_size` holds the size of one pixel of `source_texture` in texture coordinates,
+//! for effects that sample neighboring pixels.
+
+use math::Color;
+use material::MaterialId;
+
+/// A full-screen effect in a camera's post-processing chain.
+///
+/// Effects are applied in the order they're listed, so tone mapping should generally come first
+/// to bring HDR colors into displayable range before the other effects run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostEffect {
+    /// Maps HDR colors to the displayable range with a filmic curve.
+    ///
+    /// `exposure` scales the scene's colors before they're mapped, `1.0` leaves them unchanged.
+    ToneMapping { exposure: f32 },
+
+    /// Converts linear colors to gamma space by raising them to the power of `1.0 / gamma`.
+    ///
+    /// Only use this when the window's framebuffer doesn't already do the conversion, otherwise
+    /// the image is gamma corrected twice and looks washed out.
+    GammaCorrection { gamma: f32 },
+
+    /// Makes bright parts of the image glow into their surroundings.
+    ///
+    /// Colors brighter than `threshold` contribute to the glow, which is scaled by `intensity`
+    /// and added on top of the image.
+    Bloom { threshold: f32, intensity: f32 },
+
+    /// Smooths jagged edges with fast approximate anti-aliasing.
+    ///
+    /// FXAA works on the final colors, so it should come after tone mapping.
+    Fxaa,
+
+    /// Adjusts the overall look of the image.
+    ///
+    /// The image is first multiplied by `tint`, then `saturation` scales the colors away from
+    /// grey and `contrast` scales them away from mid-grey. `1.0` leaves the image unchanged for
+    /// both.
+    ColorGrading { contrast: f32, saturation: f32, tint: Color },
+
+    /// An effect using a shared material built from a custom post effect material source.
+    Custom(MaterialId),
+}
+
+impl PostEffect {
+    /// Creates a tone mapping effect with the default exposure.
+    pub fn tone_mapping() -> PostEffect {
+        PostEffect::ToneMapping { exposure: 1.0 }
+    }
+
+    /// Creates a gamma correction effect for the standard gamma of 2.2.
+    pub fn gamma_correction() -> PostEffect {
+        PostEffect::GammaCorrection { gamma: 2.2 }
+    }
+
+    /// Creates a bloom effect with moderate settings.
+    pub fn bloom() -> PostEffect {
+        PostEffect::Bloom { threshold: 1.0, intensity: 0.5 }
+    }
+
+    /// Creates a color grading effect that leaves the image unchanged.
+    pub fn color_grading() -> PostEffect {
+        PostEffect::ColorGrading {
+            contrast: 1.0,
+            saturation: 1.0,
+            tint: Color::rgb(1.0, 1.0, 1.0),
+        }
+    }
+}
@@ -0,0 +1,1272 @@
+//! A renderer that draws on the CPU into an in-memory image.
+//!
+//! The software renderer doesn't need a window or a GPU, which makes it possible to render scenes
+//! in tests and on build servers and inspect the result with `Renderer::read_pixels()`. It's far
+//! too slow for real-time rendering, so games should always use the OpenGL renderer.
+//!
+//! Material programs are written in GLSL, which the software renderer can't run. Instead every
+//! material is shaded with a built-in model based on the material's properties:
+//!
+//! - `surface_color` is the base color of the surface, which is multiplied by the
+//!   `surface_diffuse` texture if the material has one.
+//! - Materials whose fragment program reads the scene's lights are lit with Lambert diffuse
+//!   lighting plus Phong specular highlights using `surface_specular` and `surface_shininess`,
+//!   the same as the built-in lit materials.
+//! - All other materials are unlit and draw their base color directly, like
+//!   `diffuse_flat.material`.
+//!
+//! Blend modes and cameras rendering into textures work the same as in the OpenGL renderer.
+//! Shadows and post effects aren't supported and are ignored.
+
+use {BuildMaterialError, Counter, GpuMesh, Renderer};
+use anchor::*;
+use camera::*;
+use geometry::bounds::{BoundingSphere, Frustum};
+use geometry::mesh::{Mesh, VertexAttribute};
+use light::*;
+use material::*;
+use mesh_instance::*;
+use math::*;
+use shader::Shader;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::mem;
+use std::str;
+use stopwatch::{self, Stopwatch};
+use texture::*;
+
+static DEFAULT_SHADER_BYTES: &'static [u8] = include_bytes!("../resources/materials/diffuse_lit.material");
+
+#[derive(Debug)]
+pub struct SoftwareRender {
+    /// The image that cameras without a target render into, which stands in for the window.
+    frame: RenderTarget,
+
+    shared_materials: HashMap<MaterialId, Material>,
+    meshes: HashMap<GpuMesh, MeshData>,
+    textures: HashMap<GpuTexture, Image>,
+
+    /// The images for textures created with `create_render_texture()`.
+    render_targets: HashMap<GpuTexture, RenderTarget>,
+
+    mesh_instances: HashMap<MeshInstanceId, MeshInstance>,
+    anchors: HashMap<AnchorId, Anchor>,
+    cameras: HashMap<CameraId, Camera>,
+    lights: HashMap<LightId, Light>,
+    shading_models: HashMap<Shader, ShadingModel>,
+
+    mesh_instances_with_shared_materials: HashMap<MaterialId, Vec<MeshInstanceId>>,
+    mesh_instances_with_owned_material: Vec<MeshInstanceId>,
+
+    material_counter: MaterialId,
+    mesh_counter: GpuMesh,
+    texture_counter: GpuTexture,
+    mesh_instance_counter: MeshInstanceId,
+    anchor_counter: AnchorId,
+    camera_counter: CameraId,
+    light_counter: LightId,
+    shader_counter: Shader,
+
+    ambient_color: Color,
+
+    default_material: Material,
+}
+
+impl SoftwareRender {
+    /// Creates a new software renderer that draws into an image of the specified size.
+    pub fn new(width: usize, height: usize) -> SoftwareRender {
+        let _s = Stopwatch::new("Initializing software renderer");
+
+        let mut renderer = SoftwareRender {
+            frame: RenderTarget::new(width, height),
+
+            shared_materials: HashMap::new(),
+            meshes: HashMap::new(),
+            textures: HashMap::new(),
+            render_targets: HashMap::new(),
+            mesh_instances: HashMap::new(),
+            anchors: HashMap::new(),
+            cameras: HashMap::new(),
+            lights: HashMap::new(),
+            shading_models: HashMap::new(),
+
+            mesh_instances_with_shared_materials: HashMap::new(),
+            mesh_instances_with_owned_material: Vec::new(),
+
+            material_counter: MaterialId::initial(),
+            mesh_counter: GpuMesh::initial(),
+            texture_counter: GpuTexture::initial(),
+            mesh_instance_counter: MeshInstanceId::initial(),
+            anchor_counter: AnchorId::initial(),
+            camera_counter: CameraId::initial(),
+            light_counter: LightId::initial(),
+            shader_counter: Shader::initial(),
+
+            ambient_color: Color::rgb(0.01, 0.01, 0.01),
+
+            // Use a temporary value and replace it once the default material is built.
+            default_material: Material::new(Shader::initial()),
+        };
+
+        let default_material_source = str::from_utf8(DEFAULT_SHADER_BYTES).unwrap();
+        let material_source = MaterialSource::from_str(default_material_source).unwrap();
+        renderer.default_material = renderer.build_material(material_source).unwrap();
+
+        renderer
+    }
+
+    /// Gets the width and height in pixels of the image that stands in for the window.
+    pub fn size(&self) -> (usize, usize) {
+        (self.frame.image.width, self.frame.image.height)
+    }
+
+    /// Takes the image that a camera renders into out of the renderer so that it can be drawn
+    /// into while the rest of the renderer is borrowed.
+    ///
+    /// The image must be returned with `return_target()` once the camera is done.
+    fn take_target(&mut self, target: Option<GpuTexture>) -> RenderTarget {
+        match target {
+            Some(texture) => {
+                self.render_targets
+                    .remove(&texture)
+                    .expect("Camera target wasn't created with create_render_texture()")
+            },
+            None => mem::replace(&mut self.frame, RenderTarget::new(0, 0)),
+        }
+    }
+
+    fn return_target(&mut self, target: Option<GpuTexture>, render_target: RenderTarget) {
+        match target {
+            Some(texture) => { self.render_targets.insert(texture, render_target); },
+            None => self.frame = render_target,
+        }
+    }
+
+    fn render_camera(&self, camera: &Camera, target: &mut RenderTarget) {
+        let _stopwatch = Stopwatch::new("Rendering camera");
+
+        let camera_anchor = camera
+            .anchor()
+            .and_then(|anchor_id| self.anchors.get(&anchor_id))
+            .expect("Camera's anchor wasn't checked by can_render_camera()");
+
+        let viewport = camera.viewport().to_pixels(target.image.width as i32, target.image.height as i32);
+        let viewport = Rect::from_viewport(viewport, target.image.width, target.image.height);
+
+        match camera.clear() {
+            ClearFlags::Color(color) => target.clear(&viewport, Some(color)),
+            ClearFlags::Depth => target.clear(&viewport, None),
+            ClearFlags::Nothing => {},
+        }
+
+        let lights = self.active_lights();
+        let view_projection = camera.projection_matrix() * camera_anchor.view_matrix();
+        let frustum = Frustum::from_matrix(view_projection);
+        let camera_position = camera_anchor.position();
+
+        let mut drawn = 0;
+        let mut culled = 0;
+
+        // Pair every mesh instance with its material.
+        let mut mesh_instances = Vec::new();
+        for (material_id, mesh_instance_ids) in &self.mesh_instances_with_shared_materials {
+            let material = self.shared_materials.get(material_id).expect("No such material exists");
+            for mesh_instance_id in mesh_instance_ids {
+                let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
+                mesh_instances.push((mesh_instance, material));
+            }
+        }
+
+        for mesh_instance_id in &self.mesh_instances_with_owned_material {
+            let mesh_instance = self.mesh_instances.get(mesh_instance_id).expect("No such mesh instance");
+            let material = mesh_instance.material().expect("Mesh instance was in wrong bucket (was in the owned material bucket, had shared material)");
+            mesh_instances.push((mesh_instance, material));
+        }
+
+        // Gather the visible mesh instances. Transparent mesh instances have to be drawn after
+        // all opaque geometry, so they're collected along with their distance from the camera.
+        let mut opaque = Vec::new();
+        let mut transparent = Vec::new();
+        for (mesh_instance, material) in mesh_instances {
+            if !mesh_instance.is_visible()
+                || !camera.renders_layer(mesh_instance.layer())
+                || !self.has_resources(mesh_instance)
+            {
+                continue;
+            }
+
+            let bounds = match self.world_bounds(mesh_instance) {
+                Some(bounds) => bounds,
+                None => continue,
+            };
+
+            if !frustum.intersects_sphere(&bounds) {
+                culled += 1;
+                continue;
+            }
+            drawn += 1;
+
+            if material.blend_mode().is_transparent() {
+                let distance = bounds.center.distance_sqr(&camera_position);
+                transparent.push((distance, mesh_instance, material));
+            } else {
+                opaque.push((mesh_instance, material));
+            }
+        }
+
+        let pass = RenderPass {
+            view_projection: view_projection,
+            camera_position: camera_position,
+            lights: &*lights,
+            viewport: viewport,
+        };
+
+        for &(mesh_instance, material) in &opaque {
+            self.render_mesh_instance(mesh_instance, material, &pass, target);
+        }
+
+        // Render transparent mesh instances from back to front so that each one blends with
+        // everything behind it.
+        transparent.sort_by(|&(left, _, _), &(right, _, _)| {
+            right.partial_cmp(&left).unwrap_or(Ordering::Equal)
+        });
+        for &(_, mesh_instance, material) in &transparent {
+            self.render_mesh_instance(mesh_instance, material, &pass, target);
+        }
+
+        stopwatch::counter("Drawn mesh instances", drawn);
+        stopwatch::counter("Culled mesh instances", culled);
+        stopwatch::counter("Transparent mesh instances", transparent.len() as i64);
+    }
+
+    /// Checks that the camera has an anchor and that its anchor and target texture are still
+    /// registered.
+    ///
+    /// The camera's anchor determines where it's looking from, so cameras without one are skipped.
+    fn can_render_camera(&self, camera: &Camera) -> bool {
+        let has_anchor = camera.anchor().map_or(false, |anchor_id| self.anchors.contains_key(&anchor_id));
+        let has_target = camera.target().map_or(true, |texture| self.render_targets.contains_key(&texture));
+        has_anchor && has_target
+    }
+
+    /// Checks that the mesh and anchor used by the mesh instance are still registered.
+    fn has_resources(&self, mesh_instance: &MeshInstance) -> bool {
+        self.meshes.contains_key(mesh_instance.mesh())
+            && mesh_instance.anchor().map_or(true, |anchor_id| self.anchors.contains_key(&anchor_id))
+    }
+
+    /// Checks that the light's anchor is still registered, if it has one.
+    fn has_anchor(&self, light: &Light) -> bool {
+        light.anchor().map_or(true, |anchor_id| self.anchors.contains_key(anchor_id))
+    }
+
+    /// Checks if any registered material, or the default material, uses the shader.
+    fn is_shader_in_use(&self, shader: &Shader) -> bool {
+        let uses_shader = |material: &Material| material.shader() == shader;
+        uses_shader(&self.default_material)
+            || self.shared_materials.values().any(&uses_shader)
+            || self.mesh_instances.values().filter_map(MeshInstance::material).any(&uses_shader)
+    }
+
+    /// Gets the bounding sphere of the mesh instance in world space, or `None` if the mesh
+    /// instance isn't attached to an anchor.
+    fn world_bounds(&self, mesh_instance: &MeshInstance) -> Option<BoundingSphere> {
+        let anchor = match mesh_instance.anchor() {
+            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+            None => return None,
+        };
+
+        let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+        Some(mesh_data.bounding_sphere.transform(anchor.matrix()))
+    }
+
+    /// Gathers the data for the enabled lights in world space.
+    fn active_lights(&self) -> Vec<ActiveLight> {
+        self.lights
+            .values()
+            .filter(|light| light.enabled && self.has_anchor(light))
+            .map(|light| {
+                let light_anchor = || match light.anchor() {
+                    Some(anchor_id) => self.anchors.get(anchor_id).expect("No such anchor exists"),
+                    None => panic!("Cannot render point or spot light if it's not attached to an anchor"),
+                };
+
+                let kind = match light.data {
+                    LightData::Point { radius } => {
+                        LightKind::Point {
+                            position: light_anchor().position(),
+                            radius: radius,
+                        }
+                    },
+
+                    LightData::Directional { direction } => LightKind::Directional { direction: direction },
+
+                    LightData::Spot { range, inner_angle, outer_angle } => {
+                        // Spot lights shine along their anchor's forward axis.
+                        let anchor = light_anchor();
+                        LightKind::Spot {
+                            position: anchor.position(),
+                            range: range,
+                            direction: anchor.orientation() * Vector3::forward(),
+                            cone_inner: inner_angle.cos(),
+                            cone_outer: outer_angle.cos(),
+                        }
+                    },
+                };
+
+                ActiveLight {
+                    kind: kind,
+                    color: light.color,
+                    strength: light.strength,
+                }
+            })
+            .collect()
+    }
+
+    fn render_mesh_instance(
+        &self,
+        mesh_instance: &MeshInstance,
+        material: &Material,
+        pass: &RenderPass,
+        target: &mut RenderTarget,
+    ) {
+        let _s = Stopwatch::new("Rendering mesh instance");
+
+        let anchor = match mesh_instance.anchor() {
+            Some(anchor_id) => self.anchors.get(&anchor_id).expect("No such anchor exists"),
+            None => return,
+        };
+
+        let mesh_data = self.meshes.get(mesh_instance.mesh()).expect("Mesh data does not exist for mesh id");
+        let shading_model = *self
+            .shading_models
+            .get(material.shader())
+            .expect("Material is using a shader that does not exist");
+
+        // Transform every vertex once up front, since most vertices are shared by several
+        // triangles.
+        let model_transform = anchor.matrix();
+        let normal_transform = anchor.normal_matrix();
+        let model_view_projection = pass.view_projection * model_transform;
+
+        let vertices: Vec<ClipVertex> = (0..mesh_data.positions.len())
+            .map(|index| {
+                let position = mesh_data.positions[index];
+                ClipVertex {
+                    clip_position: position * model_view_projection,
+                    world_position: position * model_transform,
+                    world_normal: mesh_data.normals
+                        .get(index)
+                        .map(|&normal| (normal * normal_transform).normalized())
+                        .unwrap_or(Vector3::zero()),
+                    uv: mesh_data.texcoords.get(index).cloned().unwrap_or(Vector2::new(0.0, 0.0)),
+                }
+            })
+            .collect();
+
+        let shader = FragmentShader {
+            renderer: self,
+            material: material,
+            shading_model: shading_model,
+            pass: pass,
+            base_color: material.get_color("surface_color").cloned().unwrap_or(Color::default()),
+            specular: material.get_color("surface_specular").cloned().unwrap_or(Color::new(0.0, 0.0, 0.0, 0.0)),
+            shininess: material.get_f32("surface_shininess").cloned().unwrap_or(0.0),
+        };
+
+        for triangle in mesh_data.indices.chunks(3) {
+            if triangle.len() < 3 {
+                break;
+            }
+
+            let triangle = [
+                vertices[triangle[0] as usize],
+                vertices[triangle[1] as usize],
+                vertices[triangle[2] as usize],
+            ];
+
+            // Triangles crossing the near plane are clipped to it, which can turn them into a
+            // quad that's drawn as two triangles.
+            let polygon = clip_near(&triangle);
+            for index in 1..polygon.len().saturating_sub(1) {
+                rasterize(
+                    [&polygon[0], &polygon[index], &polygon[index + 1]],
+                    &shader,
+                    material.blend_mode(),
+                    &pass.viewport,
+                    target,
+                );
+            }
+        }
+    }
+
+    /// Adds a mesh instance to the bucket for its material.
+    ///
+    /// There's no bucket for shared materials that have been unregistered, so mesh instances using
+    /// them aren't drawn.
+    fn add_to_material_bucket(&mut self, id: MeshInstanceId, material: &MaterialType) {
+        match *material {
+            MaterialType::Shared(material_id) => {
+                if let Some(bucket) = self.mesh_instances_with_shared_materials.get_mut(&material_id) {
+                    bucket.push(id);
+                }
+            },
+            MaterialType::Owned(_) => self.mesh_instances_with_owned_material.push(id),
+        }
+    }
+
+    /// Removes a mesh instance from the bucket for its material.
+    ///
+    /// `shared_material` is the mesh instance's shared material, or `None` if it owns its material.
+    fn remove_from_material_bucket(&mut self, id: MeshInstanceId, shared_material: Option<MaterialId>) {
+        match shared_material {
+            Some(material_id) => {
+                if let Some(bucket) = self.mesh_instances_with_shared_materials.get_mut(&material_id) {
+                    bucket.retain(|&other| other != id);
+                }
+            },
+            None => self.mesh_instances_with_owned_material.retain(|&other| other != id),
+        }
+    }
+
+    /// Samples the texture at `uv`, or returns black if there's no such texture.
+    ///
+    /// Textures repeat outside of the `[0, 1]` range. Render textures are filtered linearly and
+    /// all other textures use the nearest texel, the same as in the OpenGL renderer.
+    fn sample(&self, texture: GpuTexture, uv: Vector2) -> Color {
+        if let Some(image) = self.textures.get(&texture) {
+            image.sample_nearest(uv)
+        } else if let Some(render_target) = self.render_targets.get(&texture) {
+            render_target.image.sample_linear(uv)
+        } else {
+            Color::rgb(0.0, 0.0, 0.0)
+        }
+    }
+}
+
+impl Renderer for SoftwareRender {
+    fn draw(&mut self) {
+        let _stopwatch = Stopwatch::new("SoftwareRender::draw()");
+
+        {
+            let _stopwatch = Stopwatch::new("Clearing buffer");
+            let (width, height) = self.size();
+            self.frame = RenderTarget::new(width, height);
+        }
+
+        // Render cameras in order of their depth, that way later cameras draw on top of earlier
+        // ones. Cameras rendering into textures go first so that the textures are up to date when
+        // the frame is drawn.
+        let mut cameras: Vec<(CameraId, Option<GpuTexture>, i32)> = self.cameras
+            .iter()
+            .filter(|&(_, camera)| self.can_render_camera(camera))
+            .map(|(&camera_id, camera)| (camera_id, camera.target(), camera.depth()))
+            .collect();
+        cameras.sort_by_key(|&(_, target, depth)| (target.is_none(), depth));
+
+        for (camera_id, target, _) in cameras {
+            let mut render_target = self.take_target(target);
+            self.render_camera(&self.cameras[&camera_id], &mut render_target);
+            self.return_target(target, render_target);
+        }
+    }
+
+    fn default_material(&self) -> Material {
+        self.default_material.clone()
+    }
+
+    fn build_material(&mut self, source: MaterialSource) -> Result<Material, BuildMaterialError> {
+        use polygon_material::material_source::PropertyType;
+
+        // Materials need a fragment program to be valid, even though it's never run.
+        let frag_source = source
+            .programs
+            .iter()
+            .find(|program_source| program_source.is_fragment())
+            .map(|program_source| program_source.source())
+            .ok_or(BuildMaterialError)?;
+
+        // Any fragment program that reads the lights is assumed to light the surface.
+        let shading_model = if frag_source.contains("light_type") {
+            ShadingModel::Lit
+        } else {
+            ShadingModel::Unlit
+        };
+
+        let shader = self.shader_counter.next();
+        self.shading_models.insert(shader, shading_model);
+
+        let mut material = Material::new(shader);
+        material.set_blend_mode(source.blend);
+
+        // Add the properties from the material declaration.
+        for property in source.properties {
+            match property.property_type {
+                PropertyType::Color => material.set_color(property.name, Color::default()),
+                PropertyType::Texture2d => material.set_texture(property.name, GpuTexture::default()),
+                PropertyType::f32 => material.set_f32(property.name, f32::default()),
+                PropertyType::Vector3 => material.set_vector3(property.name, Vector3::default()),
+            };
+        }
+
+        Ok(material)
+    }
+
+    fn register_shared_material(&mut self, material: Material) -> MaterialId {
+        let material_id = self.material_counter.next();
+
+        let old = self.shared_materials.insert(material_id, material);
+        assert!(old.is_none());
+
+        // Ensure there's a bucket for the shared material's mesh instances.
+        self.mesh_instances_with_shared_materials.entry(material_id).or_insert(Vec::new());
+
+        material_id
+    }
+
+    fn get_material(&self, material_id: MaterialId) -> Option<&Material> {
+        self.shared_materials.get(&material_id)
+    }
+
+    fn unregister_shared_material(&mut self, material_id: MaterialId) {
+        let material = match self.shared_materials.remove(&material_id) {
+            Some(material) => material,
+            None => return,
+        };
+
+        // The mesh instances in the material's bucket stay registered, but without a bucket
+        // they're no longer drawn.
+        self.mesh_instances_with_shared_materials.remove(&material_id);
+
+        if !self.is_shader_in_use(material.shader()) {
+            self.shading_models.remove(material.shader());
+        }
+    }
+
+    fn register_mesh(&mut self, mesh: &Mesh) -> GpuMesh {
+        let vertex_data = mesh.vertex_data();
+        let vertex_count = mesh.vertex_count();
+
+        let normals = match mesh.normal() {
+            Some(attribute) => {
+                read_attribute(vertex_data, attribute, vertex_count)
+                    .map(|data| Vector3::new(data[0], data[1], data[2]))
+                    .collect()
+            },
+            None => Vec::new(),
+        };
+
+        // TODO: Support multiple texcoords.
+        let texcoords = match mesh.texcoord().first() {
+            Some(&attribute) => {
+                read_attribute(vertex_data, attribute, vertex_count)
+                    .map(|data| Vector2::new(data[0], data[1]))
+                    .collect()
+            },
+            None => Vec::new(),
+        };
+
+        let mesh_id = self.mesh_counter.next();
+        self.meshes.insert(
+            mesh_id,
+            MeshData {
+                positions: mesh.positions().to_vec(),
+                normals: normals,
+                texcoords: texcoords,
+                indices: mesh.indices().to_vec(),
+                bounding_sphere: mesh.bounding_sphere(),
+            });
+
+        mesh_id
+    }
+
+    fn unregister_mesh(&mut self, mesh: GpuMesh) {
+        self.meshes.remove(&mesh);
+    }
+
+    fn register_texture(&mut self, texture: &Texture2d) -> GpuTexture {
+        let image = Image::from_texture(texture);
+
+        let texture_id = self.texture_counter.next();
+        let old = self.textures.insert(texture_id, image);
+        assert!(old.is_none());
+
+        texture_id
+    }
+
+    fn create_render_texture(&mut self, width: usize, height: usize) -> GpuTexture {
+        let texture_id = self.texture_counter.next();
+        self.render_targets.insert(texture_id, RenderTarget::new(width, height));
+
+        texture_id
+    }
+
+    fn unregister_texture(&mut self, texture: GpuTexture) {
+        self.render_targets.remove(&texture);
+        self.textures.remove(&texture);
+    }
+
+    fn capture_next_frame(&mut self) {
+        // The frame is kept in memory after it's drawn, so there's nothing to do.
+    }
+
+    fn read_pixels(&self, target: Option<GpuTexture>) -> Texture2d {
+        let image = match target {
+            Some(texture) => {
+                &self.render_targets
+                    .get(&texture)
+                    .expect("Texture wasn't created with create_render_texture()")
+                    .image
+            },
+            None => &self.frame.image,
+        };
+
+        image.to_texture()
+    }
+
+    fn register_mesh_instance(&mut self, mesh_instance: MeshInstance) -> MeshInstanceId {
+        let mesh_instance_id = self.mesh_instance_counter.next();
+
+        // Add the mesh instance to the right bucket based on its material type.
+        self.add_to_material_bucket(mesh_instance_id, mesh_instance.material_type());
+
+        let old = self.mesh_instances.insert(mesh_instance_id, mesh_instance);
+        assert!(old.is_none());
+
+        mesh_instance_id
+    }
+
+    fn get_mesh_instance(&self, id: MeshInstanceId) -> Option<&MeshInstance> {
+        self.mesh_instances.get(&id)
+    }
+
+    fn get_mesh_instance_mut(&mut self, id: MeshInstanceId) -> Option<&mut MeshInstance> {
+        self.mesh_instances.get_mut(&id)
+    }
+
+    fn set_mesh_instance_material(&mut self, id: MeshInstanceId, material: MaterialType) {
+        let old_material = self.mesh_instances.get(&id).expect("No such mesh instance").shared_material();
+        self.remove_from_material_bucket(id, old_material);
+
+        // Add the mesh instance to the bucket for its new material.
+        self.add_to_material_bucket(id, &material);
+
+        self.mesh_instances.get_mut(&id).unwrap().set_material(material);
+    }
+
+    fn unregister_mesh_instance(&mut self, id: MeshInstanceId) {
+        if let Some(mesh_instance) = self.mesh_instances.remove(&id) {
+            self.remove_from_material_bucket(id, mesh_instance.shared_material());
+        }
+    }
+
+    fn register_anchor(&mut self, anchor: Anchor) -> AnchorId {
+        let anchor_id = self.anchor_counter.next();
+
+        let old = self.anchors.insert(anchor_id, anchor);
+        assert!(old.is_none());
+
+        anchor_id
+    }
+
+    fn get_anchor(&self, anchor_id: AnchorId) -> Option<&Anchor> {
+        self.anchors.get(&anchor_id)
+    }
+
+    fn get_anchor_mut(&mut self, anchor_id: AnchorId) -> Option<&mut Anchor> {
+        self.anchors.get_mut(&anchor_id)
+    }
+
+    fn unregister_anchor(&mut self, anchor_id: AnchorId) {
+        self.anchors.remove(&anchor_id);
+    }
+
+    fn register_camera(&mut self, camera: Camera) -> CameraId {
+        let camera_id = self.camera_counter.next();
+
+        let old = self.cameras.insert(camera_id, camera);
+        assert!(old.is_none());
+
+        camera_id
+    }
+
+    fn get_camera(&self, camera_id: CameraId) -> Option<&Camera> {
+        self.cameras.get(&camera_id)
+    }
+
+    fn get_camera_mut(&mut self, camera_id: CameraId) -> Option<&mut Camera> {
+        self.cameras.get_mut(&camera_id)
+    }
+
+    fn unregister_camera(&mut self, camera_id: CameraId) {
+        self.cameras.remove(&camera_id);
+    }
+
+    fn register_light(&mut self, light: Light) -> LightId {
+        let light_id = self.light_counter.next();
+
+        let old = self.lights.insert(light_id, light);
+        assert!(old.is_none());
+
+        light_id
+    }
+
+    fn get_light(&self, light_id: LightId) -> Option<&Light> {
+        self.lights.get(&light_id)
+    }
+
+    fn get_light_mut(&mut self, light_id: LightId) -> Option<&mut Light> {
+        self.lights.get_mut(&light_id)
+    }
+
+    fn unregister_light(&mut self, light_id: LightId) {
+        self.lights.remove(&light_id);
+    }
+
+    fn set_ambient_light(&mut self, color: Color) {
+        self.ambient_color = color;
+    }
+
+    fn set_shadow_settings(&mut self, _settings: ShadowSettings) {
+        // Shadows aren't supported, so there's nothing to configure.
+    }
+}
+
+/// How a material is shaded, determined from its fragment program when it's built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShadingModel {
+    /// Draws the material's base color directly.
+    Unlit,
+
+    /// Lights the material's base color with Lambert diffuse and Phong specular lighting.
+    Lit,
+}
+
+#[derive(Debug)]
+struct MeshData {
+    positions: Vec<Point>,
+
+    /// The normal for each vertex, empty if the mesh doesn't have normals.
+    normals: Vec<Vector3>,
+
+    /// The first texcoord for each vertex, empty if the mesh doesn't have texcoords.
+    texcoords: Vec<Vector2>,
+
+    indices: Vec<u32>,
+    bounding_sphere: BoundingSphere,
+}
+
+/// Reads the value of a vertex attribute for each vertex.
+fn read_attribute<'a>(
+    vertex_data: &'a [f32],
+    attribute: VertexAttribute,
+    vertex_count: usize,
+) -> Box<Iterator<Item = &'a [f32]> + 'a> {
+    // A stride of 0 means the attribute's values are tightly packed.
+    let stride = if attribute.stride == 0 { attribute.elements } else { attribute.stride };
+    Box::new((0..vertex_count).map(move |index| {
+        let start = attribute.offset + index * stride;
+        &vertex_data[start..start + attribute.elements]
+    }))
+}
+
+/// An image with floating point colors, stored from the bottom row up.
+#[derive(Debug, Clone)]
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize, color: Color) -> Image {
+        Image {
+            width: width,
+            height: height,
+            pixels: vec![color; width * height],
+        }
+    }
+
+    fn from_texture(texture: &Texture2d) -> Image {
+        let pixels = (0..texture.width() * texture.height())
+            .map(|index| {
+                let (r, g, b, a) = texture.pixel(index);
+                Color::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, a as f32 / 255.0)
+            })
+            .collect();
+
+        Image {
+            width: texture.width(),
+            height: texture.height(),
+            pixels: pixels,
+        }
+    }
+
+    /// Converts the image to an RGBA texture, clamping the colors to the displayable range.
+    fn to_texture(&self) -> Texture2d {
+        fn to_u8(value: f32) -> u8 {
+            (value.max(0.0).min(1.0) * 255.0).round() as u8
+        }
+
+        let mut data = Vec::with_capacity(self.pixels.len() * 4);
+        for color in &self.pixels {
+            data.push(to_u8(color.r));
+            data.push(to_u8(color.g));
+            data.push(to_u8(color.b));
+            data.push(to_u8(color.a));
+        }
+
+        Texture2d::new(self.width, self.height, DataFormat::Rgba, TextureData::u8(data))
+    }
+
+    /// Gets the pixel at the specified coordinates, wrapping around at the edges of the image.
+    fn wrapped_pixel(&self, x: isize, y: isize) -> Color {
+        let x = modulo(x, self.width as isize) as usize;
+        let y = modulo(y, self.height as isize) as usize;
+        self.pixels[y * self.width + x]
+    }
+
+    fn sample_nearest(&self, uv: Vector2) -> Color {
+        if self.pixels.is_empty() {
+            return Color::rgb(0.0, 0.0, 0.0);
+        }
+
+        let x = (uv.x * self.width as f32).floor() as isize;
+        let y = (uv.y * self.height as f32).floor() as isize;
+        self.wrapped_pixel(x, y)
+    }
+
+    fn sample_linear(&self, uv: Vector2) -> Color {
+        if self.pixels.is_empty() {
+            return Color::rgb(0.0, 0.0, 0.0);
+        }
+
+        // Texel centers are at half-pixel offsets, so shift by half a texel to find the four
+        // texels surrounding the sample point.
+        let x = uv.x * self.width as f32 - 0.5;
+        let y = uv.y * self.height as f32 - 0.5;
+        let (left, bottom) = (x.floor(), y.floor());
+        let (tx, ty) = (x - left, y - bottom);
+        let (left, bottom) = (left as isize, bottom as isize);
+
+        let lower = lerp_color(self.wrapped_pixel(left, bottom), self.wrapped_pixel(left + 1, bottom), tx);
+        let upper = lerp_color(self.wrapped_pixel(left, bottom + 1), self.wrapped_pixel(left + 1, bottom + 1), tx);
+        lerp_color(lower, upper, ty)
+    }
+}
+
+/// A color image with a depth buffer that cameras can render into.
+#[derive(Debug, Clone)]
+struct RenderTarget {
+    image: Image,
+    depth: Vec<f32>,
+}
+
+impl RenderTarget {
+    /// Creates a render target cleared to black with the depth buffer at the far plane.
+    fn new(width: usize, height: usize) -> RenderTarget {
+        RenderTarget {
+            image: Image::new(width, height, Color::new(0.0, 0.0, 0.0, 0.0)),
+            depth: vec![1.0; width * height],
+        }
+    }
+
+    /// Clears the depth buffer within `rect`, and the color buffer as well if `color` is
+    /// specified.
+    fn clear(&mut self, rect: &Rect, color: Option<Color>) {
+        for y in rect.bottom..rect.top {
+            for x in rect.left..rect.right {
+                let index = y * self.image.width + x;
+                self.depth[index] = 1.0;
+                if let Some(color) = color {
+                    self.image.pixels[index] = color;
+                }
+            }
+        }
+    }
+}
+
+/// A rectangle of pixels, including `left` and `bottom` but not `right` and `top`.
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    left: usize,
+    bottom: usize,
+    right: usize,
+    top: usize,
+}
+
+impl Rect {
+    /// Converts a viewport in pixels to a rectangle, clamped to the size of the render target.
+    fn from_viewport(viewport: (i32, i32, i32, i32), width: usize, height: usize) -> Rect {
+        let (x, y, viewport_width, viewport_height) = viewport;
+        let clamp = |value: i32, max: usize| value.max(0).min(max as i32) as usize;
+
+        Rect {
+            left: clamp(x, width),
+            bottom: clamp(y, height),
+            right: clamp(x + viewport_width, width),
+            top: clamp(y + viewport_height, height),
+        }
+    }
+}
+
+/// The data shared by every mesh instance drawn by a camera.
+#[derive(Debug)]
+struct RenderPass<'a> {
+    view_projection: Matrix4,
+    camera_position: Point,
+    lights: &'a [ActiveLight],
+
+    /// The camera's viewport in pixels.
+    viewport: Rect,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveLight {
+    kind: LightKind,
+    color: Color,
+    strength: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LightKind {
+    Point { position: Point, radius: f32 },
+    Directional { direction: Vector3 },
+    Spot { position: Point, range: f32, direction: Vector3, cone_inner: f32, cone_outer: f32 },
+}
+
+/// A vertex that's been transformed into clip space, along with the values interpolated across
+/// the triangle for shading.
+#[derive(Debug, Clone, Copy)]
+struct ClipVertex {
+    clip_position: Point,
+    world_position: Point,
+    world_normal: Vector3,
+    uv: Vector2,
+}
+
+impl ClipVertex {
+    fn lerp(from: &ClipVertex, to: &ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            clip_position: lerp_point(from.clip_position, to.clip_position, t),
+            world_position: lerp_point(from.world_position, to.world_position, t),
+            world_normal: from.world_normal + (to.world_normal - from.world_normal) * t,
+            uv: from.uv + (to.uv - from.uv) * t,
+        }
+    }
+}
+
+/// A vertex mapped to pixel coordinates.
+#[derive(Debug, Clone, Copy)]
+struct ScreenVertex<'a> {
+    x: f32,
+    y: f32,
+
+    /// The depth in the range `[0, 1]`.
+    depth: f32,
+
+    /// `1 / w` for the vertex, used for perspective-correct interpolation.
+    inverse_w: f32,
+
+    vertex: &'a ClipVertex,
+}
+
+/// The values for a single pixel of a triangle, interpolated from its vertices.
+#[derive(Debug, Clone, Copy)]
+struct Fragment {
+    world_position: Point,
+    world_normal: Vector3,
+    uv: Vector2,
+}
+
+/// Calculates the color of each pixel of a mesh instance with the built-in shading model.
+struct FragmentShader<'a> {
+    renderer: &'a SoftwareRender,
+    material: &'a Material,
+    shading_model: ShadingModel,
+    pass: &'a RenderPass<'a>,
+
+    base_color: Color,
+    specular: Color,
+    shininess: f32,
+}
+
+impl<'a> FragmentShader<'a> {
+    fn shade(&self, fragment: &Fragment) -> Color {
+        let mut base_color = self.base_color;
+        if let Some(&MaterialProperty::Texture(texture)) = self.material.get_property("surface_diffuse") {
+            base_color = mul_color(base_color, self.renderer.sample(texture, fragment.uv));
+        }
+
+        if self.shading_model == ShadingModel::Unlit {
+            return base_color;
+        }
+
+        let mut color = mul_color(self.renderer.ambient_color, base_color);
+
+        let n = fragment.world_normal.normalized();
+        let v = (self.pass.camera_position - fragment.world_position).normalized();
+
+        for light in self.pass.lights {
+            // The direction from the surface to the light and the light's attenuation, which
+            // doesn't apply for directional lights.
+            let (l, attenuation) = match light.kind {
+                LightKind::Point { position, radius } => {
+                    let offset = position - fragment.world_position;
+                    (offset.normalized(), distance_attenuation(offset.magnitude(), radius))
+                },
+
+                LightKind::Directional { direction } => (-direction, 1.0),
+
+                LightKind::Spot { position, range, direction, cone_inner, cone_outer } => {
+                    let offset = position - fragment.world_position;
+                    let l = offset.normalized();
+
+                    // Fade out between the inner and outer cone angles.
+                    let cos_angle = (-l).dot(direction);
+                    let cone = smoothstep(cone_outer, cone_inner, cos_angle);
+                    (l, distance_attenuation(offset.magnitude(), range) * cone)
+                },
+            };
+
+            let intensity = attenuation * light.strength;
+
+            let l_dot_n = l.dot(n);
+            color = add_color(color, scale_color(mul_color(base_color, light.color), l_dot_n.max(0.0) * intensity));
+
+            if l_dot_n > 0.0 {
+                let r = (n * (2.0 * l_dot_n) - l).normalized();
+                let shine = r.dot(v).max(0.0).min(1.0).powf(self.shininess);
+                color = add_color(color, scale_color(mul_color(self.specular, light.color), shine * intensity));
+            }
+        }
+
+        // The lighting terms don't have a meaningful alpha, so use the surface's alpha.
+        color.a = base_color.a;
+        color
+    }
+}
+
+/// Clips a triangle to the near plane, returning the vertices of the remaining polygon.
+///
+/// Only the near plane needs to be clipped against since it's the only one where vertices can
+/// have `w <= 0`. Everything else outside of the view is discarded when rasterizing.
+fn clip_near(triangle: &[ClipVertex; 3]) -> Vec<ClipVertex> {
+    // A vertex is in front of the near plane when `z >= -w`.
+    let distance = |vertex: &ClipVertex| vertex.clip_position.z + vertex.clip_position.w;
+
+    let mut polygon = Vec::with_capacity(4);
+    for index in 0..3 {
+        let current = &triangle[index];
+        let next = &triangle[(index + 1) % 3];
+        let (current_distance, next_distance) = (distance(current), distance(next));
+
+        if current_distance >= 0.0 {
+            polygon.push(*current);
+        }
+
+        if (current_distance >= 0.0) != (next_distance >= 0.0) {
+            let t = current_distance / (current_distance - next_distance);
+            polygon.push(ClipVertex::lerp(current, next, t));
+        }
+    }
+
+    polygon
+}
+
+/// Draws a triangle into the render target.
+///
+/// Triangles are drawn with back face culling and depth testing against the depth buffer, the
+/// same as in the OpenGL renderer. Transparent triangles are blended with the render target and
+/// don't write to the depth buffer.
+fn rasterize(
+    triangle: [&ClipVertex; 3],
+    shader: &FragmentShader,
+    blend_mode: BlendMode,
+    viewport: &Rect,
+    target: &mut RenderTarget,
+) {
+    let screen = [
+        to_screen(triangle[0], viewport),
+        to_screen(triangle[1], viewport),
+        to_screen(triangle[2], viewport),
+    ];
+
+    // Front faces wind counter-clockwise, which gives them a positive area with y pointing up.
+    let area = edge(&screen[0], &screen[1], screen[2].x, screen[2].y);
+    if area.is_nan() || area <= 0.0 {
+        return;
+    }
+
+    // Only visit the pixels covered by the triangle's bounding box.
+    let min_x = screen.iter().map(|vertex| vertex.x).fold(::std::f32::INFINITY, f32::min);
+    let max_x = screen.iter().map(|vertex| vertex.x).fold(::std::f32::NEG_INFINITY, f32::max);
+    let min_y = screen.iter().map(|vertex| vertex.y).fold(::std::f32::INFINITY, f32::min);
+    let max_y = screen.iter().map(|vertex| vertex.y).fold(::std::f32::NEG_INFINITY, f32::max);
+
+    let clamp = |value: f32, min: usize, max: usize| value.max(min as f32).min(max as f32) as usize;
+    let left = clamp(min_x.floor(), viewport.left, viewport.right);
+    let right = clamp(max_x.ceil(), viewport.left, viewport.right);
+    let bottom = clamp(min_y.floor(), viewport.bottom, viewport.top);
+    let top = clamp(max_y.ceil(), viewport.bottom, viewport.top);
+
+    let transparent = blend_mode.is_transparent();
+    for y in bottom..top {
+        for x in left..right {
+            // Sample at the pixel's center.
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+            let weights = [
+                edge(&screen[1], &screen[2], px, py),
+                edge(&screen[2], &screen[0], px, py),
+                edge(&screen[0], &screen[1], px, py),
+            ];
+
+            // Pixels exactly on an edge shared by two triangles belong to only one of them, so
+            // that blended triangles don't cover them twice.
+            let inside = weights
+                .iter()
+                .zip(&[(1, 2), (2, 0), (0, 1)])
+                .all(|(&weight, &(from, to))| {
+                    weight > 0.0 || (weight == 0.0 && owns_edge(&screen[from], &screen[to]))
+                });
+            if !inside {
+                continue;
+            }
+
+            let barycentric = [weights[0] / area, weights[1] / area, weights[2] / area];
+
+            // Depth is linear in screen space, so it can be interpolated directly.
+            let depth =
+                barycentric[0] * screen[0].depth
+              + barycentric[1] * screen[1].depth
+              + barycentric[2] * screen[2].depth;
+
+            let index = y * target.image.width + x;
+            if depth < 0.0 || depth > 1.0 || depth >= target.depth[index] {
+                continue;
+            }
+
+            let fragment = interpolate(&screen, barycentric);
+            let color = shader.shade(&fragment);
+
+            let destination = target.image.pixels[index];
+            target.image.pixels[index] = blend(color, destination, blend_mode);
+            if !transparent {
+                target.depth[index] = depth;
+            }
+        }
+    }
+}
+
+/// Maps a vertex from clip space to pixel coordinates within the viewport.
+fn to_screen<'a>(vertex: &'a ClipVertex, viewport: &Rect) -> ScreenVertex<'a> {
+    let position = vertex.clip_position;
+    let inverse_w = 1.0 / position.w;
+    let width = (viewport.right - viewport.left) as f32;
+    let height = (viewport.top - viewport.bottom) as f32;
+
+    ScreenVertex {
+        x: viewport.left as f32 + (position.x * inverse_w * 0.5 + 0.5) * width,
+        y: viewport.bottom as f32 + (position.y * inverse_w * 0.5 + 0.5) * height,
+        depth: position.z * inverse_w * 0.5 + 0.5,
+        inverse_w: inverse_w,
+        vertex: vertex,
+    }
+}
+
+/// Calculates twice the signed area of the triangle formed by the edge from `from` to `to` and
+/// the point, which is positive if the point is to the left of the edge.
+fn edge(from: &ScreenVertex, to: &ScreenVertex, x: f32, y: f32) -> f32 {
+    (to.x - from.x) * (y - from.y) - (to.y - from.y) * (x - from.x)
+}
+
+/// Determines whether pixels lying exactly on the edge from `from` to `to` are drawn.
+///
+/// Two triangles sharing an edge traverse it in opposite directions, so exactly one of them owns
+/// it.
+fn owns_edge(from: &ScreenVertex, to: &ScreenVertex) -> bool {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+/// Interpolates the vertex values at a point in the triangle, correcting for perspective.
+fn interpolate(screen: &[ScreenVertex; 3], barycentric: [f32; 3]) -> Fragment {
+    let weights = [
+        barycentric[0] * screen[0].inverse_w,
+        barycentric[1] * screen[1].inverse_w,
+        barycentric[2] * screen[2].inverse_w,
+    ];
+    let total = weights[0] + weights[1] + weights[2];
+    let weights = [weights[0] / total, weights[1] / total, weights[2] / total];
+
+    let mut world_position = Point::origin();
+    let mut world_normal = Vector3::zero();
+    let mut uv = Vector2::new(0.0, 0.0);
+    for (vertex, &weight) in screen.iter().zip(weights.iter()) {
+        world_position += vertex.vertex.world_position.as_vector3() * weight;
+        world_normal += vertex.vertex.world_normal * weight;
+        uv = uv + vertex.vertex.uv * weight;
+    }
+
+    Fragment {
+        world_position: world_position,
+        world_normal: world_normal,
+        uv: uv,
+    }
+}
+
+/// Blends a new color with the existing color in the render target.
+///
+/// These are the same blend functions that the OpenGL renderer uses for each blend mode.
+fn blend(source: Color, destination: Color, blend_mode: BlendMode) -> Color {
+    let (source_factor, destination_factor) = match blend_mode {
+        BlendMode::Opaque => return source,
+        BlendMode::Alpha => (source.a, 1.0 - source.a),
+        BlendMode::Additive => (source.a, 1.0),
+        BlendMode::Premultiplied => (1.0, 1.0 - source.a),
+    };
+
+    add_color(scale_color(source, source_factor), scale_color(destination, destination_factor))
+}
+
+/// The distance attenuation used by the built-in lit materials.
+fn distance_attenuation(distance: f32, radius: f32) -> f32 {
+    (1.0 - distance / radius).max(0.0).min(1.0).powi(2)
+}
+
+/// Smoothly interpolates from 0 to 1 as `value` goes from `from` to `to`, like GLSL's
+/// `smoothstep()`.
+fn smoothstep(from: f32, to: f32, value: f32) -> f32 {
+    let t = ((value - from) / (to - from)).max(0.0).min(1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn modulo(value: isize, divisor: isize) -> isize {
+    ((value % divisor) + divisor) % divisor
+}
+
+fn lerp_point(from: Point, to: Point, t: f32) -> Point {
+    Point {
+        x: from.x + (to.x - from.x) * t,
+        y: from.y + (to.y - from.y) * t,
+        z: from.z + (to.z - from.z) * t,
+        w: from.w + (to.w - from.w) * t,
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    add_color(scale_color(from, 1.0 - t), scale_color(to, t))
+}
+
+fn add_color(left: Color, right: Color) -> Color {
+    Color::new(left.r + right.r, left.g + right.g, left.b + right.b, left.a + right.a)
+}
+
+fn mul_color(left: Color, right: Color) -> Color {
+    Color::new(left.r * right.r, left.g * right.g, left.b * right.b, left.a * right.a)
+}
+
+fn scale_color(color: Color, scale: f32) -> Color {
+    Color::new(color.r * scale, color.g * scale, color.b * scale, color.a * scale)
+}
@@ -2,6 +2,9 @@ use parse_bmp::{
     Bitmap,
     BitmapData,
 };
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 
 /// Represents texture data that has been sent to the GPU.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
@@ -14,7 +17,8 @@ derive_Counter!(GpuTexture);
 /// used by any of the rendering backends to send texture data to the GPU. It encapsulates all
 /// relevant information about the texture, including the raw bytes of the texture and information
 /// describing the in-memory layout of that data. It also provides functionality for safely
-/// loading textures from common formats (NOTE: Only bitmap is supported currently).
+/// loading textures from common formats (NOTE: Only bitmap is supported currently), and for saving
+/// textures as BMP or PNG images.
 #[derive(Debug)]
 pub struct Texture2d {
     width: usize,
@@ -24,6 +28,32 @@ pub struct Texture2d {
 }
 
 impl Texture2d {
+    /// Creates a texture from raw pixel data.
+    ///
+    /// Rows of pixels start at the bottom of the image, the same as OpenGL.
+    ///
+    /// # Panics
+    ///
+    /// - If `data` doesn't contain exactly `width * height` pixels of `format`.
+    pub fn new(width: usize, height: usize, format: DataFormat, data: TextureData) -> Texture2d {
+        let elements = width * height * format.channels() / data.channels_per_element();
+        assert!(
+            data.len() == elements,
+            "Wrong amount of texture data, width: {}, height: {}, format: {:?}, expected elements: {}, actual elements: {}",
+            width,
+            height,
+            format,
+            elements,
+            data.len());
+
+        Texture2d {
+            width: width,
+            height: height,
+            format: format,
+            data: data,
+        }
+    }
+
     /// Loads a new `Texture` from a bitmap file.
     pub fn from_bitmap(bitmap: Bitmap) -> Texture2d {
         let texture = match bitmap.data() {
@@ -67,6 +97,142 @@ impl Texture2d {
     pub fn data(&self) -> &TextureData {
         &self.data
     }
+
+    /// Gets the color of the pixel at `index`, counting from the bottom left of the image, as
+    /// RGBA bytes.
+    ///
+    /// Formats without alpha are fully opaque, and `f32` data is clamped to the range `[0, 1]`.
+    pub fn pixel(&self, index: usize) -> (u8, u8, u8, u8) {
+        fn from_f32(value: f32) -> u8 {
+            (value.max(0.0).min(1.0) * 255.0).round() as u8
+        }
+
+        let channels = self.format.channels();
+        let mut pixel = [0, 0, 0, 255];
+        for channel in 0..channels {
+            let element = index * channels + channel;
+            pixel[channel] = match self.data {
+                TextureData::f32(ref data) => from_f32(data[element]),
+                TextureData::u8(ref data) => data[element],
+                TextureData::u8x3(ref data) => {
+                    let (r, g, b) = data[index];
+                    [r, g, b][channel]
+                },
+                TextureData::u8x4(ref data) => {
+                    let (r, g, b, a) = data[index];
+                    [r, g, b, a][channel]
+                },
+            };
+        }
+
+        match self.format {
+            DataFormat::Rgb | DataFormat::Rgba => (pixel[0], pixel[1], pixel[2], pixel[3]),
+            DataFormat::Bgr | DataFormat::Bgra => (pixel[2], pixel[1], pixel[0], pixel[3]),
+        }
+    }
+
+    /// Saves the texture to an image file.
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ImageFormat) -> io::Result<()> {
+        let file = File::create(path)?;
+        self.write_image(&mut BufWriter::new(file), format)
+    }
+
+    /// Writes the texture as an image file in the specified format.
+    pub fn write_image<W: Write>(&self, writer: &mut W, format: ImageFormat) -> io::Result<()> {
+        match format {
+            ImageFormat::Bmp => self.write_bmp(writer),
+            ImageFormat::Png => self.write_png(writer),
+        }
+    }
+
+    /// Writes the texture as an uncompressed 24 bit bitmap, dropping the alpha channel.
+    fn write_bmp<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        const HEADER_SIZE: u32 = 14 + 40;
+
+        // Each row is padded to a multiple of 4 bytes.
+        let row_size = (self.width * 3 + 3) / 4 * 4;
+        let image_size = (row_size * self.height) as u32;
+
+        // File header.
+        writer.write_all(b"BM")?;
+        write_u32_le(writer, HEADER_SIZE + image_size)?;
+        write_u32_le(writer, 0)?;
+        write_u32_le(writer, HEADER_SIZE)?;
+
+        // Info header. A positive height means the rows are stored bottom-up, which is the same
+        // order as the texture data.
+        write_u32_le(writer, 40)?;
+        write_u32_le(writer, self.width as u32)?;
+        write_u32_le(writer, self.height as u32)?;
+        writer.write_all(&[1, 0, 24, 0])?;
+        write_u32_le(writer, 0)?;
+        write_u32_le(writer, image_size)?;
+        write_u32_le(writer, 2835)?;
+        write_u32_le(writer, 2835)?;
+        write_u32_le(writer, 0)?;
+        write_u32_le(writer, 0)?;
+
+        let mut row = Vec::with_capacity(row_size);
+        for y in 0..self.height {
+            row.clear();
+            for x in 0..self.width {
+                let (r, g, b, _) = self.pixel(y * self.width + x);
+                row.extend_from_slice(&[b, g, r]);
+            }
+            row.resize(row_size, 0);
+            writer.write_all(&*row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the texture as a 32 bit RGBA PNG.
+    ///
+    /// The image data is stored without compression to keep the encoder simple, so the files are
+    /// about as large as the equivalent bitmap.
+    fn write_png<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'])?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&u32_be(self.width as u32));
+        header.extend_from_slice(&u32_be(self.height as u32));
+        // Bit depth 8, color type 6 (RGBA), default compression, filtering and interlacing.
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+        write_png_chunk(writer, b"IHDR", &*header)?;
+
+        // PNG stores rows from the top down, and each row starts with its filter type (0 for none).
+        let row_size = 1 + self.width * 4;
+        let mut image_data = Vec::with_capacity(row_size * self.height);
+        for y in (0..self.height).rev() {
+            image_data.push(0);
+            for x in 0..self.width {
+                let (r, g, b, a) = self.pixel(y * self.width + x);
+                image_data.extend_from_slice(&[r, g, b, a]);
+            }
+        }
+
+        // Wrap the image data in a zlib stream made of uncompressed deflate blocks.
+        const MAX_BLOCK_SIZE: usize = 0xFFFF;
+        let mut zlib_data = Vec::with_capacity(image_data.len() + image_data.len() / MAX_BLOCK_SIZE * 5 + 11);
+        zlib_data.extend_from_slice(&[0x78, 0x01]);
+        let mut blocks = image_data.chunks(MAX_BLOCK_SIZE).peekable();
+        if blocks.peek().is_none() {
+            // Even an empty image needs a final block.
+            zlib_data.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+        }
+        while let Some(block) = blocks.next() {
+            let is_final = blocks.peek().is_none();
+            let length = block.len() as u16;
+            zlib_data.push(is_final as u8);
+            zlib_data.extend_from_slice(&[length as u8, (length >> 8) as u8]);
+            zlib_data.extend_from_slice(&[!length as u8, (!length >> 8) as u8]);
+            zlib_data.extend_from_slice(block);
+        }
+        zlib_data.extend_from_slice(&u32_be(adler32(&*image_data)));
+        write_png_chunk(writer, b"IDAT", &*zlib_data)?;
+
+        write_png_chunk(writer, b"IEND", &[])
+    }
 }
 
 /// An enum representing the supported data formats for a texture.
@@ -78,6 +244,48 @@ pub enum DataFormat {
     Bgra,
 }
 
+impl DataFormat {
+    /// Gets the number of color channels in each pixel.
+    pub fn channels(&self) -> usize {
+        match *self {
+            DataFormat::Rgb | DataFormat::Bgr => 3,
+            DataFormat::Rgba | DataFormat::Bgra => 4,
+        }
+    }
+}
+
+/// The image file formats that textures can be saved as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    Bmp,
+    Png,
+}
+
+impl ImageFormat {
+    /// Determines the image format from a path's extension, returning `None` if the extension
+    /// isn't recognized.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ImageFormat> {
+        let extension = match path.as_ref().extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => extension.to_lowercase(),
+            None => return None,
+        };
+
+        match &*extension {
+            "bmp" => Some(ImageFormat::Bmp),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+
+    /// Gets the file extension for the image format, without the leading dot.
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
 /// An enum representing the possible data types for a texture.
 ///
 /// `TextureData` also owns the texture raw data buffer in order to maintain type safety.
@@ -95,3 +303,69 @@ pub enum TextureData {
     u8x3(Vec<(u8, u8, u8)>),
     u8x4(Vec<(u8, u8, u8, u8)>),
 }
+
+impl TextureData {
+    /// Gets the number of elements in the data buffer.
+    pub fn len(&self) -> usize {
+        match *self {
+            TextureData::f32(ref data) => data.len(),
+            TextureData::u8(ref data) => data.len(),
+            TextureData::u8x3(ref data) => data.len(),
+            TextureData::u8x4(ref data) => data.len(),
+        }
+    }
+
+    /// Gets the number of color channels stored in each element of the data buffer.
+    fn channels_per_element(&self) -> usize {
+        match *self {
+            TextureData::f32(_) | TextureData::u8(_) => 1,
+            TextureData::u8x3(_) => 3,
+            TextureData::u8x4(_) => 4,
+        }
+    }
+}
+
+fn write_u32_le<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8])
+}
+
+fn u32_be(value: u32) -> [u8; 4] {
+    [(value >> 24) as u8, (value >> 16) as u8, (value >> 8) as u8, value as u8]
+}
+
+/// Writes a PNG chunk, which is the data's length, the chunk type, the data, and then a CRC of
+/// the chunk type and data.
+fn write_png_chunk<W: Write>(writer: &mut W, chunk_type: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&u32_be(data.len() as u32))?;
+    writer.write_all(chunk_type)?;
+    writer.write_all(data)?;
+
+    let crc = !crc32_update(crc32_update(!0, chunk_type), data);
+    writer.write_all(&u32_be(crc))
+}
+
+/// Updates a running CRC-32 (the variant used by PNG and zlib) with more data.
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xEDB88320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+
+    crc
+}
+
+/// Calculates the Adler-32 checksum used by zlib streams.
+fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+
+    let mut a = 1;
+    let mut b = 0;
+    for &byte in data {
+        a = (a + byte as u32) % MODULUS;
+        b = (b + a) % MODULUS;
+    }
+
+    (b << 16) | a
+}
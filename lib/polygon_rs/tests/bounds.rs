@@ -0,0 +1,104 @@
+extern crate polygon;
+
+use polygon::anchor::Anchor;
+use polygon::camera::Camera;
+use polygon::geometry::bounds::*;
+use polygon::math::*;
+use std::f32::consts::PI;
+
+fn sphere(x: f32, y: f32, z: f32, radius: f32) -> BoundingSphere {
+    BoundingSphere {
+        center: Point::new(x, y, z),
+        radius: radius,
+    }
+}
+
+/// Creates the frustum for `camera` at `position`, looking down the negative z axis.
+fn camera_frustum(camera: Camera, position: Point) -> Frustum {
+    let mut anchor = Anchor::new();
+    anchor.set_position(position);
+    Frustum::from_matrix(camera.projection_matrix() * anchor.view_matrix())
+}
+
+#[test]
+fn sphere_from_points() {
+    let empty = BoundingSphere::from_points(&[]);
+    assert_eq!(sphere(0.0, 0.0, 0.0, 0.0), empty);
+
+    // The sphere is centered on the points' bounding box rather than the origin.
+    let points = [
+        Point::new(10.0, 0.0, 0.0),
+        Point::new(12.0, 0.0, 0.0),
+        Point::new(11.0, 1.0, 0.0),
+        Point::new(11.0, -1.0, 0.0),
+    ];
+    let bounds = BoundingSphere::from_points(&points);
+    assert_eq!(Point::new(11.0, 0.0, 0.0), bounds.center);
+    assert!((bounds.radius - 1.0).is_zero());
+
+    for point in &points {
+        assert!(point.distance(&bounds.center) <= bounds.radius + 1e-6);
+    }
+}
+
+#[test]
+fn sphere_transform() {
+    let bounds = sphere(1.0, 0.0, 0.0, 2.0);
+
+    let translated = bounds.transform(Matrix4::translation(0.0, 5.0, 0.0));
+    assert_eq!(Point::new(1.0, 5.0, 0.0), translated.center);
+    assert!((translated.radius - 2.0).is_zero());
+
+    // Rotating a quarter turn around the y axis moves the center from +x to -z.
+    let rotated = bounds.transform(Matrix4::rotation(0.0, PI * 0.5, 0.0));
+    assert!((rotated.center - Point::new(0.0, 0.0, -1.0)).is_zero());
+    assert!((rotated.radius - 2.0).is_zero());
+
+    // Non-uniform scaling uses the largest scale, so the sphere still contains the object.
+    let scaled = bounds.transform(Matrix4::scale(1.0, 3.0, 0.5));
+    assert_eq!(Point::new(1.0, 0.0, 0.0), scaled.center);
+    assert!((scaled.radius - 6.0).is_zero());
+}
+
+#[test]
+fn frustum_perspective() {
+    let frustum = camera_frustum(Camera::new(PI * 0.5, 1.0, 1.0, 10.0), Point::origin());
+
+    // Inside the frustum.
+    assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -5.0, 0.5)));
+    assert!(frustum.intersects_sphere(&sphere(4.0, 0.0, -5.0, 0.5)));
+
+    // Behind the camera, in front of the near plane, and past the far plane.
+    assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, 5.0, 1.0)));
+    assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, -0.5, 0.25)));
+    assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, -12.0, 1.0)));
+
+    // Outside each side. With a 90 degree field of view the sides are at `|x| = -z`.
+    assert!(!frustum.intersects_sphere(&sphere(7.0, 0.0, -5.0, 1.0)));
+    assert!(!frustum.intersects_sphere(&sphere(-7.0, 0.0, -5.0, 1.0)));
+    assert!(!frustum.intersects_sphere(&sphere(0.0, 7.0, -5.0, 1.0)));
+    assert!(!frustum.intersects_sphere(&sphere(0.0, -7.0, -5.0, 1.0)));
+
+    // Spheres centered outside the frustum that overlap it are still visible.
+    assert!(frustum.intersects_sphere(&sphere(6.0, 0.0, -5.0, 1.0)));
+    assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -10.5, 1.0)));
+}
+
+#[test]
+fn frustum_follows_camera() {
+    let frustum = camera_frustum(Camera::new(PI * 0.5, 1.0, 1.0, 10.0), Point::new(100.0, 0.0, 0.0));
+
+    assert!(frustum.intersects_sphere(&sphere(100.0, 0.0, -5.0, 0.5)));
+    assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, -5.0, 0.5)));
+}
+
+#[test]
+fn frustum_orthographic() {
+    let frustum = camera_frustum(Camera::orthographic(2.0, 1.0, 1.0, 10.0), Point::origin());
+
+    // The sides of an orthographic frustum are parallel, at `|x| = 2` and `|y| = 2`.
+    assert!(frustum.intersects_sphere(&sphere(1.5, 1.5, -9.0, 0.1)));
+    assert!(!frustum.intersects_sphere(&sphere(2.5, 0.0, -2.0, 0.25)));
+    assert!(!frustum.intersects_sphere(&sphere(0.0, -2.5, -9.0, 0.25)));
+    assert!(frustum.intersects_sphere(&sphere(2.5, 0.0, -2.0, 1.0)));
+}
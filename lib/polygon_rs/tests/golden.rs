@@ -0,0 +1,402 @@
+//! Golden-image tests for the scenes in `examples`.
+//!
+//! Each test renders one of the examples at a fixed point in its animation with the software
+//! renderer and compares the result to a reference image in `tests/golden`. When a test fails
+//! the rendered image and an image highlighting the differing pixels are written to
+//! `target/golden`.
+//!
+//! After an intentional change to how scenes are rendered, run the tests with `POLYGON_BLESS=1`
+//! set to replace the reference images with the new results.
+
+extern crate polygon;
+
+use polygon::*;
+use polygon::anchor::*;
+use polygon::camera::*;
+use polygon::geometry::mesh::*;
+use polygon::geometry::primitive::*;
+use polygon::light::*;
+use polygon::material::*;
+use polygon::math::*;
+use polygon::mesh_instance::*;
+use polygon::texture::*;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[path = "../examples/utils.rs"]
+mod utils;
+
+/// The width and height of the rendered images. The examples use the default camera aspect
+/// ratio of 1, so the images are square.
+const IMAGE_SIZE: usize = 128;
+
+/// The largest difference in any color channel for which two pixels are considered the same
+/// without checking them perceptually.
+const CHANNEL_TOLERANCE: u8 = 2;
+
+/// The largest perceptual difference between two pixels (the CIE76 color difference) that isn't
+/// counted as a mismatch. 2.3 is roughly the smallest difference people can notice.
+const PERCEPTUAL_TOLERANCE: f32 = 2.3;
+
+/// The fraction of pixels that may mismatch before the images are considered different, which
+/// allows for small differences in rasterization along edges.
+const MAX_MISMATCHED_PIXELS: f32 = 0.001;
+
+#[test]
+fn hello_triangle() {
+    let mut renderer = RendererBuilder::software(IMAGE_SIZE, IMAGE_SIZE).build();
+
+    let mesh = MeshBuilder::new()
+        .set_position_data(&[
+            Point::new(-1.0, -1.0, 0.0),
+            Point::new(1.0, -1.0, 0.0),
+            Point::new(0.0, 1.0, 0.0),
+        ])
+        .set_indices(&[0, 1, 2])
+        .build()
+        .unwrap();
+    let gpu_mesh = renderer.register_mesh(&mesh);
+
+    let anchor_id = renderer.register_anchor(Anchor::new());
+
+    let mut material = renderer.default_material();
+    material.set_color("surface_color", Color::rgb(1.0, 0.0, 0.0));
+
+    let mut mesh_instance = MeshInstance::with_owned_material(gpu_mesh, material);
+    mesh_instance.set_anchor(anchor_id);
+    renderer.register_mesh_instance(mesh_instance);
+
+    add_camera(&mut *renderer, Point::new(0.0, 0.0, 10.0));
+    renderer.set_ambient_light(Color::rgb(1.0, 1.0, 1.0));
+
+    assert_matches_golden("hello_triangle", &mut *renderer);
+}
+
+#[test]
+fn point_light() {
+    let mut renderer = RendererBuilder::software(IMAGE_SIZE, IMAGE_SIZE).build();
+
+    let mut material = build_material(&mut *renderer, "diffuse_lit");
+    material.set_color("surface_color", Color::rgb(1.0, 0.0, 1.0));
+    material.set_color("surface_specular", Color::rgb(1.0, 1.0, 1.0));
+    material.set_f32("surface_shininess", 4.0);
+    add_head(&mut *renderer, Point::new(0.0, 0.0, 0.0), material);
+
+    add_camera(&mut *renderer, Point::new(0.0, 0.0, 2.0));
+    add_point_light(&mut *renderer, Point::new(0.0, 0.5, 0.75), 1.0, Color::rgb(1.0, 1.0, 1.0));
+
+    assert_matches_golden("point_light", &mut *renderer);
+}
+
+#[test]
+fn multiple_lights() {
+    let mut renderer = RendererBuilder::software(IMAGE_SIZE, IMAGE_SIZE).build();
+
+    let mut material = build_material(&mut *renderer, "diffuse_lit");
+    material.set_color("surface_color", Color::rgb(1.0, 1.0, 1.0));
+    material.set_color("surface_specular", Color::rgb(1.0, 1.0, 1.0));
+    material.set_f32("surface_shininess", 4.0);
+    add_head(&mut *renderer, Point::new(0.0, 0.0, 0.0), material);
+
+    add_camera(&mut *renderer, Point::new(0.0, 0.0, 2.0));
+
+    // The lights are placed where they are at the start of the example's orbits.
+    let lights = [
+        (2.3, Vector3::new(1.0, 1.0, 1.0), Color::rgb(1.0, 0.0, 0.0)),
+        (0.0, Vector3::new(2.0, 0.6, 1.0), Color::rgb(0.0, 1.0, 0.0)),
+        (1.7, Vector3::new(0.7, 1.7, 0.1), Color::rgb(0.0, 0.0, 1.0)),
+    ];
+    for &(time_offset, scale, color) in &lights {
+        let position = Point::new(
+            (time_offset * scale.x).sin(),
+            (time_offset * scale.y).cos(),
+            (time_offset * scale.z).sin(),
+        );
+        add_point_light(&mut *renderer, position, 2.0, color);
+    }
+
+    assert_matches_golden("multiple_lights", &mut *renderer);
+}
+
+#[test]
+fn textures() {
+    let mut renderer = RendererBuilder::software(IMAGE_SIZE, IMAGE_SIZE).build();
+
+    let texture = utils::load_texture("resources/textures/structured.bmp");
+    let gpu_texture = renderer.register_texture(&texture);
+
+    let mut material = build_material(&mut *renderer, "texture_diffuse_lit");
+    material.set_color("surface_color", Color::rgb(1.0, 1.0, 1.0));
+    material.set_f32("surface_shininess", 4.0);
+    material.set_texture("surface_diffuse", gpu_texture);
+    add_head(&mut *renderer, Point::new(0.0, 0.0, 0.0), material);
+
+    add_camera(&mut *renderer, Point::new(0.0, 0.0, 2.0));
+    add_point_light(&mut *renderer, Point::new(1.0, 0.0, 0.75), 2.0, Color::rgb(1.0, 1.0, 1.0));
+
+    assert_matches_golden("textures", &mut *renderer);
+}
+
+#[test]
+fn materials() {
+    let mut renderer = RendererBuilder::software(IMAGE_SIZE, IMAGE_SIZE).build();
+
+    let texture = utils::load_texture("resources/textures/structured.bmp");
+    let gpu_texture = renderer.register_texture(&texture);
+
+    let mut left_material = build_material(&mut *renderer, "diffuse_flat");
+    left_material.set_color("surface_color", Color::rgb(1.0, 0.5, 1.0));
+    add_head(&mut *renderer, Point::new(-1.5, 0.0, 0.0), left_material);
+
+    let mut middle_material = build_material(&mut *renderer, "diffuse_lit");
+    middle_material.set_color("surface_color", Color::rgb(0.0, 1.0, 1.0));
+    middle_material.set_color("specular_color", Color::rgb(1.0, 1.0, 1.0));
+    middle_material.set_f32("surface_shininess", 4.0);
+    add_head(&mut *renderer, Point::new(0.0, 0.0, 0.0), middle_material);
+
+    let mut right_material = build_material(&mut *renderer, "texture_diffuse_lit");
+    right_material.set_texture("surface_diffuse", gpu_texture);
+    right_material.set_color("surface_color", Color::rgb(1.0, 1.0, 1.0));
+    right_material.set_color("specular_color", Color::rgb(0.2, 0.2, 0.2));
+    right_material.set_f32("surface_shininess", 3.0);
+    add_head(&mut *renderer, Point::new(1.5, 0.0, 0.0), right_material);
+
+    add_camera(&mut *renderer, Point::new(0.0, 0.0, 4.0));
+    add_point_light(&mut *renderer, Point::new(2.75, 0.0, 2.0), 5.0, Color::rgb(1.0, 1.0, 1.0));
+
+    assert_matches_golden("materials", &mut *renderer);
+}
+
+#[test]
+fn primitives() {
+    let mut renderer = RendererBuilder::software(IMAGE_SIZE, IMAGE_SIZE).build();
+
+    let primitives = [
+        Primitive::Cube { size: 1.2 },
+        Primitive::UvSphere { radius: 0.8, segments: 24, rings: 12 },
+        Primitive::Icosphere { radius: 0.8, subdivisions: 2 },
+        Primitive::Plane { width: 1.5, depth: 1.5, subdivisions: 4 },
+        Primitive::Cylinder { radius: 0.6, height: 1.4, segments: 24 },
+        Primitive::Cone { radius: 0.7, height: 1.4, segments: 24 },
+        Primitive::Capsule { radius: 0.5, height: 1.6, segments: 24, rings: 6 },
+        Primitive::Torus { radius: 0.6, tube_radius: 0.25, segments: 32, tube_segments: 16 },
+    ];
+
+    let texture = utils::load_texture("resources/textures/structured.bmp");
+    let gpu_texture = renderer.register_texture(&texture);
+
+    let mut material = build_material(&mut *renderer, "texture_diffuse_lit");
+    material.set_color("surface_color", Color::rgb(1.0, 1.0, 1.0));
+    material.set_f32("surface_shininess", 4.0);
+    material.set_texture("surface_diffuse", gpu_texture);
+
+    for (index, primitive) in primitives.iter().enumerate() {
+        let gpu_mesh = renderer.register_mesh(&primitive.build());
+
+        let column = (index % 4) as f32;
+        let row = (index / 4) as f32;
+        let mut anchor = Anchor::new();
+        anchor.set_position(Point::new(column * 2.0 - 3.0, 1.0 - row * 2.0, 0.0));
+        anchor.set_orientation(Orientation::from_eulers(0.5, 0.0, 0.0));
+        let anchor_id = renderer.register_anchor(anchor);
+
+        let mut mesh_instance = MeshInstance::with_owned_material(gpu_mesh, material.clone());
+        mesh_instance.set_anchor(anchor_id);
+        renderer.register_mesh_instance(mesh_instance);
+    }
+
+    add_camera(&mut *renderer, Point::new(0.0, 0.0, 8.0));
+
+    let light = Light::directional(Vector3::new(1.0, -1.0, -1.0), 1.0, Color::rgb(1.0, 1.0, 1.0));
+    renderer.register_light(light);
+    renderer.set_ambient_light(Color::rgb(0.2, 0.2, 0.2));
+
+    assert_matches_golden("primitives", &mut *renderer);
+}
+
+fn build_material(renderer: &mut Renderer, name: &str) -> Material {
+    let path = format!("resources/materials/{}.material", name);
+    let source = MaterialSource::from_file(path).unwrap();
+    renderer.build_material(source).unwrap()
+}
+
+/// Adds an instance of the head mesh used by most of the examples.
+fn add_head(renderer: &mut Renderer, position: Point, material: Material) {
+    let mesh = utils::load_mesh("resources/meshes/epps_head.obj").unwrap();
+    let gpu_mesh = renderer.register_mesh(&mesh);
+
+    let mut anchor = Anchor::new();
+    anchor.set_position(position);
+    let anchor_id = renderer.register_anchor(anchor);
+
+    let mut mesh_instance = MeshInstance::with_owned_material(gpu_mesh, material);
+    mesh_instance.set_anchor(anchor_id);
+    renderer.register_mesh_instance(mesh_instance);
+}
+
+fn add_camera(renderer: &mut Renderer, position: Point) {
+    let mut camera_anchor = Anchor::new();
+    camera_anchor.set_position(position);
+    let camera_anchor_id = renderer.register_anchor(camera_anchor);
+
+    let mut camera = Camera::default();
+    camera.set_anchor(camera_anchor_id);
+    renderer.register_camera(camera);
+}
+
+fn add_point_light(renderer: &mut Renderer, position: Point, radius: f32, color: Color) {
+    let mut light_anchor = Anchor::new();
+    light_anchor.set_position(position);
+    let light_anchor_id = renderer.register_anchor(light_anchor);
+
+    let mut light = Light::point(radius, 1.0, color);
+    light.set_anchor(light_anchor_id);
+    renderer.register_light(light);
+}
+
+/// Draws a frame and compares it to the reference image for the test.
+fn assert_matches_golden(name: &str, renderer: &mut Renderer) {
+    renderer.capture_next_frame();
+    renderer.draw();
+    let image = renderer.read_pixels(None);
+
+    let reference_directory = Path::new("tests/golden");
+    let reference_path = reference_directory.join(format!("{}.bmp", name));
+    if env::var_os("POLYGON_BLESS").is_some() {
+        fs::create_dir_all(reference_directory).unwrap();
+        image.save(&reference_path, ImageFormat::Bmp).unwrap();
+        return;
+    }
+
+    if !reference_path.exists() {
+        panic!(
+            "No reference image for {} at {:?}, run the tests with POLYGON_BLESS=1 to create it",
+            name,
+            reference_path);
+    }
+    let reference = utils::load_texture(&reference_path);
+
+    let comparison = compare(&reference, &image);
+    if comparison.mismatched == 0 {
+        return;
+    }
+
+    let failure_directory = PathBuf::from("target/golden");
+    fs::create_dir_all(&failure_directory).unwrap();
+    let actual_path = failure_directory.join(format!("{}.actual.bmp", name));
+    let diff_path = failure_directory.join(format!("{}.diff.bmp", name));
+    image.save(&actual_path, ImageFormat::Bmp).unwrap();
+    comparison.diff.save(&diff_path, ImageFormat::Bmp).unwrap();
+
+    panic!(
+        "{} doesn't match its reference image, {} of {} pixels differ (largest difference {:.2}), \
+         see {:?} and {:?}",
+        name,
+        comparison.mismatched,
+        image.width() * image.height(),
+        comparison.max_difference,
+        actual_path,
+        diff_path);
+}
+
+/// The result of comparing a rendered image to its reference image.
+struct Comparison {
+    /// The number of mismatched pixels, which is 0 if the images are close enough to match
+    /// even if some pixels were different.
+    mismatched: usize,
+
+    /// The largest perceptual difference between any two pixels.
+    max_difference: f32,
+
+    /// The reference image faded to grey, with the mismatched pixels in red.
+    diff: Texture2d,
+}
+
+fn compare(reference: &Texture2d, actual: &Texture2d) -> Comparison {
+    assert!(
+        reference.width() == actual.width() && reference.height() == actual.height(),
+        "Image is {}x{} but the reference image is {}x{}",
+        actual.width(),
+        actual.height(),
+        reference.width(),
+        reference.height());
+
+    let pixel_count = actual.width() * actual.height();
+    let mut mismatched = 0;
+    let mut max_difference: f32 = 0.0;
+    let mut diff = Vec::with_capacity(pixel_count * 3);
+
+    for index in 0..pixel_count {
+        let (r, g, b, _) = reference.pixel(index);
+        let (actual_r, actual_g, actual_b, _) = actual.pixel(index);
+
+        let channel_difference = [(r, actual_r), (g, actual_g), (b, actual_b)]
+            .iter()
+            .map(|&(expected, actual)| (expected as i16 - actual as i16).abs() as u8)
+            .max()
+            .unwrap();
+
+        let difference = if channel_difference <= CHANNEL_TOLERANCE {
+            0.0
+        } else {
+            perceptual_difference((r, g, b), (actual_r, actual_g, actual_b))
+        };
+        max_difference = max_difference.max(difference);
+
+        if difference > PERCEPTUAL_TOLERANCE {
+            mismatched += 1;
+            diff.extend(&[255, 0, 0]);
+        } else {
+            let luminance = (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) as u8;
+            let faded = 128 + luminance / 4;
+            diff.extend(&[faded, faded, faded]);
+        }
+    }
+
+    if (mismatched as f32) <= pixel_count as f32 * MAX_MISMATCHED_PIXELS {
+        mismatched = 0;
+    }
+
+    Comparison {
+        mismatched: mismatched,
+        max_difference: max_difference,
+        diff: Texture2d::new(actual.width(), actual.height(), DataFormat::Rgb, TextureData::u8(diff)),
+    }
+}
+
+/// Calculates how different two sRGB colors look, as their distance in the CIELAB color space.
+fn perceptual_difference(first: (u8, u8, u8), second: (u8, u8, u8)) -> f32 {
+    let (first_l, first_a, first_b) = to_lab(first);
+    let (second_l, second_a, second_b) = to_lab(second);
+
+    ((first_l - second_l).powi(2) + (first_a - second_a).powi(2) + (first_b - second_b).powi(2)).sqrt()
+}
+
+/// Converts an sRGB color to CIELAB, using the D65 white point.
+fn to_lab(color: (u8, u8, u8)) -> (f32, f32, f32) {
+    fn to_linear(channel: u8) -> f32 {
+        let channel = channel as f32 / 255.0;
+        if channel <= 0.04045 {
+            channel / 12.92
+        } else {
+            ((channel + 0.055) / 1.055).powf(2.4)
+        }
+    }
+
+    fn f(t: f32) -> f32 {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    }
+
+    let (r, g, b) = (to_linear(color.0), to_linear(color.1), to_linear(color.2));
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
@@ -0,0 +1,300 @@
+extern crate polygon;
+
+use polygon::geometry::mesh::*;
+use polygon::geometry::primitive::*;
+use polygon::math::*;
+
+fn normals(mesh: &Mesh) -> Vec<Vector3> {
+    let attribute = mesh.normal().expect("Mesh has no normals");
+    let data = &mesh.vertex_data()[attribute.offset..];
+    data.chunks(3)
+        .take(mesh.vertex_count())
+        .map(|normal| Vector3::new(normal[0], normal[1], normal[2]))
+        .collect()
+}
+
+fn texcoords(mesh: &Mesh) -> Vec<Vector2> {
+    let attribute = mesh.texcoord()[0];
+    let data = &mesh.vertex_data()[attribute.offset..];
+    data.chunks(2)
+        .take(mesh.vertex_count())
+        .map(|texcoord| Vector2::new(texcoord[0], texcoord[1]))
+        .collect()
+}
+
+/// Gets the tangent of every vertex in `mesh` along with its handedness.
+fn tangents(mesh: &Mesh) -> Vec<(Vector3, f32)> {
+    let attribute = mesh.tangent().expect("Mesh has no tangents");
+    assert_eq!(4, attribute.elements);
+
+    let data = &mesh.vertex_data()[attribute.offset..];
+    data.chunks(4)
+        .take(mesh.vertex_count())
+        .map(|tangent| (Vector3::new(tangent[0], tangent[1], tangent[2]), tangent[3]))
+        .collect()
+}
+
+fn face_normal(positions: &[Point], triangle: &[MeshIndex]) -> Vector3 {
+    let (a, b, c) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+    Vector3::cross(positions[b] - positions[a], positions[c] - positions[a]).normalized()
+}
+
+/// Creates a cube with sides of length 2 where each corner is a single vertex shared by all of
+/// the faces around it, without normals.
+fn shared_cube() -> MeshBuilder {
+    let positions = (0..8)
+        .map(|corner| {
+            let coordinate = |bit: usize| if corner & bit == 0 { -1.0 } else { 1.0 };
+            Point::new(coordinate(1), coordinate(2), coordinate(4))
+        })
+        .collect::<Vec<_>>();
+
+    // The corners of each face, counter-clockwise when seen from outside the cube.
+    let faces = [
+        [1, 3, 7, 5],
+        [0, 4, 6, 2],
+        [2, 6, 7, 3],
+        [0, 1, 5, 4],
+        [4, 5, 7, 6],
+        [0, 2, 3, 1],
+    ];
+
+    let mut indices = Vec::new();
+    for face in &faces {
+        indices.extend(&[face[0], face[1], face[2], face[0], face[2], face[3]]);
+    }
+
+    MeshBuilder::new()
+        .set_position_data(&*positions)
+        .set_indices(&*indices)
+}
+
+/// Checks that the normals are unit length and on the same side as the faces they belong to.
+fn check_normals(mesh: &Mesh) {
+    let positions = mesh.positions();
+    let normals = normals(mesh);
+    for triangle in mesh.indices().chunks(3) {
+        let face_normal = face_normal(positions, triangle);
+        for &index in triangle {
+            let normal = normals[index as usize];
+            assert!((normal.magnitude() - 1.0).abs() < 1e-4, "normal {} isn't unit length", index);
+            assert!(face_normal.dot(normal) > 0.0, "normal {} faces away from its triangle", index);
+        }
+    }
+}
+
+/// Checks that the tangents are unit length and orthogonal to the normals, and that the tangent
+/// and bitangent point along the directions of increasing u and v on each triangle.
+fn check_tangents(mesh: &Mesh) {
+    let positions = mesh.positions();
+    let normals = normals(mesh);
+    let texcoords = texcoords(mesh);
+    let tangents = tangents(mesh);
+
+    for (index, &(tangent, handedness)) in tangents.iter().enumerate() {
+        assert!(handedness == 1.0 || handedness == -1.0, "tangent {} has handedness {}", index, handedness);
+        assert!((tangent.magnitude() - 1.0).abs() < 1e-4, "tangent {} isn't unit length", index);
+        assert!(tangent.dot(normals[index]).abs() < 1e-4, "tangent {} isn't orthogonal to its normal", index);
+    }
+
+    let mut checked = 0;
+    for triangle in mesh.indices().chunks(3) {
+        let (a, b, c) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+        let (edge_1, edge_2) = (positions[b] - positions[a], positions[c] - positions[a]);
+        let (delta_1, delta_2) = (texcoords[b] - texcoords[a], texcoords[c] - texcoords[a]);
+
+        // Triangles where the texture is squashed into a line have no direction for u or v.
+        let determinant = delta_1.x * delta_2.y - delta_2.x * delta_1.y;
+        if determinant.abs() < 1e-6 {
+            continue;
+        }
+
+        let u_direction = (edge_1 * delta_2.y - edge_2 * delta_1.y) / determinant;
+        let v_direction = (edge_2 * delta_1.x - edge_1 * delta_2.x) / determinant;
+        for &index in &[a, b, c] {
+            let (tangent, handedness) = tangents[index];
+            let bitangent = Vector3::cross(normals[index], tangent) * handedness;
+            assert!(tangent.dot(u_direction) > 0.0, "tangent {} points against u", index);
+            assert!(bitangent.dot(v_direction) > 0.0, "bitangent {} points against v", index);
+            checked += 1;
+        }
+    }
+
+    assert!(checked > 0, "No triangles had texcoords to check the tangents against");
+}
+
+#[test]
+fn flat_normals() {
+    let mesh = shared_cube().generate_flat_normals().build().unwrap();
+    check_normals(&mesh);
+
+    // Each triangle gets its own vertices, all with the normal of the face.
+    assert_eq!(36, mesh.vertex_count());
+    let normals = normals(&mesh);
+    for triangle in mesh.indices().chunks(3) {
+        let face_normal = face_normal(mesh.positions(), triangle);
+        for &index in triangle {
+            assert!((normals[index as usize] - face_normal).is_zero());
+        }
+    }
+}
+
+#[test]
+fn smooth_normals_hard_edges() {
+    // The cube's edges are 90 degrees, which is sharper than the threshold, so the corners are
+    // split up into one vertex for each face.
+    let mesh = shared_cube().generate_smooth_normals(1.0).build().unwrap();
+    check_normals(&mesh);
+    assert_eq!(24, mesh.vertex_count());
+
+    for normal in normals(&mesh) {
+        let largest = normal.x.abs().max(normal.y.abs()).max(normal.z.abs());
+        assert!((largest - 1.0).abs() < 1e-5, "{:?} isn't along an axis", normal);
+    }
+}
+
+#[test]
+fn smooth_normals_soft_edges() {
+    // With a threshold wider than any edge, the faces around each corner are averaged together.
+    let mesh = shared_cube().generate_smooth_normals(3.2).build().unwrap();
+    check_normals(&mesh);
+    assert_eq!(8, mesh.vertex_count());
+
+    for (&position, &normal) in mesh.positions().iter().zip(normals(&mesh).iter()) {
+        assert!((normal - Vector3::from(position).normalized()).is_zero(), "{:?} at {:?}", normal, position);
+    }
+}
+
+#[test]
+fn smooth_normals_sphere() {
+    // The sphere's seam and poles have separate vertices in the same place, which are smoothed
+    // together so the generated normals come close to the sphere's real normals everywhere.
+    let sphere = uv_sphere(1.0, 24, 12);
+
+    // The positions on either side of the seam are calculated from different angles, so they're
+    // rounded to make them exactly the same.
+    let round = |value: f32| (value * 1e5).round() / 1e5;
+    let positions = sphere.positions()
+        .iter()
+        .map(|position| Point::new(round(position.x), round(position.y), round(position.z)))
+        .collect::<Vec<_>>();
+
+    let mesh = MeshBuilder::new()
+        .set_position_data(&*positions)
+        .set_texcoord_data(&*texcoords(&sphere))
+        .set_indices(sphere.indices())
+        .generate_smooth_normals(1.0)
+        .generate_tangents()
+        .build()
+        .unwrap();
+
+    for (&position, &normal) in mesh.positions().iter().zip(normals(&mesh).iter()) {
+        let error = (normal - Vector3::from(position).normalized()).magnitude();
+        assert!(error < 0.02, "{:?} at {:?}", normal, position);
+    }
+
+    check_tangents(&mesh);
+}
+
+#[test]
+fn primitive_tangents() {
+    let primitives = [
+        Primitive::Cube { size: 1.0 },
+        Primitive::Plane { width: 2.0, depth: 1.0, subdivisions: 3 },
+        Primitive::Cylinder { radius: 1.0, height: 2.0, segments: 12 },
+        Primitive::Torus { radius: 1.0, tube_radius: 0.3, segments: 16, tube_segments: 8 },
+    ];
+
+    for primitive in &primitives {
+        let source = primitive.build();
+        let mesh = MeshBuilder::new()
+            .set_position_data(source.positions())
+            .set_normal_data(&*normals(&source))
+            .set_texcoord_data(&*texcoords(&source))
+            .set_indices(source.indices())
+            .generate_tangents()
+            .build()
+            .unwrap();
+
+        // None of the primitives mirror their texture, so no vertices need to be split.
+        assert_eq!(source.vertex_count(), mesh.vertex_count(), "{:?}", primitive);
+        check_tangents(&mesh);
+    }
+}
+
+#[test]
+fn mirrored_texcoords() {
+    // Two triangles sharing the edge along the y axis, with the texture mirrored across it.
+    let positions = [
+        Point::new(0.0, 0.0, 0.0),
+        Point::new(0.0, 1.0, 0.0),
+        Point::new(1.0, 0.0, 0.0),
+        Point::new(-1.0, 0.0, 0.0),
+    ];
+    let texcoords = [
+        Vector2::new(0.0, 0.0),
+        Vector2::new(0.0, 1.0),
+        Vector2::new(1.0, 0.0),
+        Vector2::new(1.0, 0.0),
+    ];
+    let mesh = MeshBuilder::new()
+        .set_position_data(&positions)
+        .set_normal_data(&[Vector3::back(); 4])
+        .set_texcoord_data(&texcoords)
+        .set_indices(&[0, 2, 1, 0, 1, 3])
+        .generate_tangents()
+        .build()
+        .unwrap();
+
+    // The shared vertices need a different handedness on each side, so they're split up.
+    assert_eq!(6, mesh.vertex_count());
+    check_tangents(&mesh);
+}
+
+#[test]
+fn degenerate_texcoords() {
+    // The texture has no direction on the triangle, but the vertices still get valid tangents.
+    let mesh = MeshBuilder::new()
+        .set_position_data(&[Point::new(0.0, 0.0, 0.0), Point::new(1.0, 0.0, 0.0), Point::new(0.0, 1.0, 0.0)])
+        .set_texcoord_data(&[Vector2::new(0.0, 0.0); 3])
+        .set_indices(&[0, 1, 2])
+        .generate_flat_normals()
+        .generate_tangents()
+        .build()
+        .unwrap();
+    assert_eq!(3, mesh.vertex_count());
+
+    for (&(tangent, handedness), &normal) in tangents(&mesh).iter().zip(normals(&mesh).iter()) {
+        assert!((tangent.magnitude() - 1.0).abs() < 1e-5);
+        assert!(tangent.dot(normal).abs() < 1e-5);
+        assert_eq!(1.0, handedness);
+    }
+}
+
+#[test]
+fn missing_attributes() {
+    match shared_cube().generate_tangents().build() {
+        Err(BuildMeshError::MissingAttribute { attribute: VertexAttributeType::Texcoord }) => {},
+        result => panic!("Expected missing texcoords, got {:?}", result),
+    }
+
+    let texcoords = [Vector2::new(0.0, 0.0); 8];
+    match shared_cube().set_texcoord_data(&texcoords).generate_tangents().build() {
+        Err(BuildMeshError::MissingAttribute { attribute: VertexAttributeType::Normal }) => {},
+        result => panic!("Expected missing normals, got {:?}", result),
+    }
+
+    // Generated normals can be used in place of normal data.
+    let result = shared_cube()
+        .set_texcoord_data(&texcoords)
+        .generate_smooth_normals(1.0)
+        .generate_tangents()
+        .build();
+    assert!(result.is_ok());
+
+    // Bad indices are still reported when generating normals, rather than panicking.
+    match shared_cube().add_index(100).generate_flat_normals().build() {
+        Err(BuildMeshError::IndexOutOfBounds { .. }) => {},
+        result => panic!("Expected an out of bounds index, got {:?}", result),
+    }
+}
@@ -0,0 +1,163 @@
+extern crate polygon;
+
+use polygon::geometry::mesh::Mesh;
+use polygon::geometry::primitive::*;
+use polygon::math::*;
+
+/// Gets the normal of every vertex in `mesh`.
+fn normals(mesh: &Mesh) -> Vec<Vector3> {
+    let attribute = mesh.normal().expect("Primitive has no normals");
+    let data = &mesh.vertex_data()[attribute.offset..];
+    data.chunks(3)
+        .take(mesh.vertex_count())
+        .map(|normal| Vector3::new(normal[0], normal[1], normal[2]))
+        .collect()
+}
+
+/// Checks that the indices of `mesh` are in range and that its normals are unit length and point
+/// outward.
+///
+/// `center` gives the point on the inside of the shape closest to a point on its surface, so that
+/// the direction from it to the surface point is outward.
+fn check_mesh<F>(mesh: &Mesh, center: F)
+    where F: Fn(Point) -> Point
+{
+    let positions = mesh.positions();
+    let normals = normals(mesh);
+    assert_eq!(mesh.vertex_count(), positions.len());
+    assert_eq!(mesh.vertex_count(), normals.len());
+
+    for (index, (&position, &normal)) in positions.iter().zip(normals.iter()).enumerate() {
+        assert!((normal.magnitude() - 1.0).abs() < 1e-4, "normal {} isn't unit length: {:?}", index, normal);
+
+        let outward = position - center(position);
+        assert!(normal.dot(outward) > 0.0, "normal {} points inward: {:?} at {:?}", index, normal, position);
+    }
+
+    assert_eq!(0, mesh.indices().len() % 3);
+    for triangle in mesh.indices().chunks(3) {
+        for &index in triangle {
+            assert!((index as usize) < mesh.vertex_count(), "index {} is out of range", index);
+        }
+
+        // The winding of each triangle has to agree with its normals, otherwise the outside of the
+        // shape would be culled.
+        let (a, b, c) = (triangle[0] as usize, triangle[1] as usize, triangle[2] as usize);
+        let face_normal = Vector3::cross(positions[b] - positions[a], positions[c] - positions[a]);
+        let vertex_normal = normals[a] + normals[b] + normals[c];
+        assert!(face_normal.dot(vertex_normal) > 0.0, "triangle {:?} is wound backwards", triangle);
+    }
+}
+
+fn triangle_count(mesh: &Mesh) -> usize {
+    mesh.indices().len() / 3
+}
+
+fn origin(_: Point) -> Point {
+    Point::origin()
+}
+
+#[test]
+fn cube_mesh() {
+    let mesh = cube(2.0);
+    check_mesh(&mesh, origin);
+
+    // Every face has its own 4 corners.
+    assert_eq!(24, mesh.vertex_count());
+    assert_eq!(12, triangle_count(&mesh));
+}
+
+#[test]
+fn uv_sphere_mesh() {
+    let mesh = uv_sphere(1.5, 16, 8);
+    check_mesh(&mesh, origin);
+
+    // The first and last columns of vertices overlap at the seam. The quads touching the poles
+    // collapse into a single triangle.
+    assert_eq!(17 * 9, mesh.vertex_count());
+    assert_eq!(16 * 8 * 2 - 16 * 2, triangle_count(&mesh));
+}
+
+#[test]
+fn icosphere_mesh() {
+    for subdivisions in 0..4 {
+        let mesh = icosphere(1.0, subdivisions);
+        check_mesh(&mesh, origin);
+
+        for position in mesh.positions() {
+            assert!((position.distance(&Point::origin()) - 1.0).abs() < 1e-4);
+        }
+
+        // Vertices are duplicated along the texture seam and at the poles, so there are more than
+        // the `10 * 4^n + 2` vertices of the subdivided icosahedron itself.
+        let triangles = 20 * 4usize.pow(subdivisions as u32);
+        assert_eq!(triangles, triangle_count(&mesh));
+        assert!(mesh.vertex_count() >= triangles / 2 + 2);
+    }
+}
+
+#[test]
+fn plane_mesh() {
+    let mesh = plane(2.0, 3.0, 4);
+    check_mesh(&mesh, |position| position - Vector3::up());
+
+    assert_eq!(5 * 5, mesh.vertex_count());
+    assert_eq!(4 * 4 * 2, triangle_count(&mesh));
+}
+
+#[test]
+fn cylinder_mesh() {
+    let mesh = cylinder(1.0, 2.0, 12);
+    check_mesh(&mesh, origin);
+
+    // Two rings of vertices for the side, and a ring plus a center vertex for each cap.
+    assert_eq!(13 * 2 + 13 * 2, mesh.vertex_count());
+    assert_eq!(12 * 2 + 12 * 2, triangle_count(&mesh));
+}
+
+#[test]
+fn cone_mesh() {
+    let mesh = cone(1.0, 2.0, 12);
+    check_mesh(&mesh, origin);
+
+    // The top row of the side collapses into the tip, so each quad of the side is one triangle.
+    assert_eq!(13 * 2 + 13, mesh.vertex_count());
+    assert_eq!(12 + 12, triangle_count(&mesh));
+}
+
+#[test]
+fn capsule_mesh() {
+    let (radius, height) = (0.5, 2.0);
+    let mesh = capsule(radius, height, 12, 4);
+
+    // The normals point away from the line segment running through the middle of the capsule.
+    let half_cylinder = (height - radius * 2.0) * 0.5;
+    check_mesh(&mesh, |position| Point::new(0.0, position.y.max(-half_cylinder).min(half_cylinder), 0.0));
+
+    // 4 rows of quads for each end and one for the middle, where the quads touching the poles
+    // collapse into a single triangle.
+    assert_eq!(13 * 10, mesh.vertex_count());
+    assert_eq!(12 * 9 * 2 - 12 * 2, triangle_count(&mesh));
+}
+
+#[test]
+fn torus_mesh() {
+    let radius = 1.0;
+    let mesh = torus(radius, 0.25, 24, 12);
+
+    // The normals point away from the circle running through the middle of the tube.
+    check_mesh(&mesh, |position| {
+        Point::from(Vector3::new(position.x, 0.0, position.z).normalized() * radius)
+    });
+
+    assert_eq!(25 * 13, mesh.vertex_count());
+    assert_eq!(24 * 12 * 2, triangle_count(&mesh));
+}
+
+#[test]
+fn primitive_build() {
+    let primitive = Primitive::Cylinder { radius: 1.0, height: 2.0, segments: 12 };
+    let mesh = primitive.build();
+    check_mesh(&mesh, origin);
+    assert_eq!(cylinder(1.0, 2.0, 12).positions(), mesh.positions());
+}
@@ -0,0 +1,140 @@
+extern crate parse_bmp;
+extern crate polygon;
+
+use parse_bmp::Bitmap;
+use polygon::texture::*;
+
+/// Creates a texture where every pixel has a different color, with an alpha channel that
+/// differs from 255.
+fn test_texture(width: usize, height: usize) -> Texture2d {
+    let mut data = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            data.extend_from_slice(&[x as u8, y as u8, (x * 7 + y * 13) as u8, 128]);
+        }
+    }
+
+    Texture2d::new(width, height, DataFormat::Rgba, TextureData::u8(data))
+}
+
+fn encode(texture: &Texture2d, format: ImageFormat) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    texture.write_image(&mut bytes, format).unwrap();
+    bytes
+}
+
+fn read_u32_be(bytes: &[u8]) -> u32 {
+    (bytes[0] as u32) << 24 | (bytes[1] as u32) << 16 | (bytes[2] as u32) << 8 | bytes[3] as u32
+}
+
+/// Splits a PNG file into its chunks, returning the type, data, and CRC of each chunk.
+fn png_chunks(bytes: &[u8]) -> Vec<([u8; 4], Vec<u8>, u32)> {
+    assert_eq!(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'], &bytes[..8]);
+
+    let mut chunks = Vec::new();
+    let mut rest = &bytes[8..];
+    while !rest.is_empty() {
+        let length = read_u32_be(rest) as usize;
+        let mut chunk_type = [0; 4];
+        chunk_type.copy_from_slice(&rest[4..8]);
+        let data = rest[8..8 + length].to_vec();
+        let crc = read_u32_be(&rest[8 + length..]);
+
+        chunks.push((chunk_type, data, crc));
+        rest = &rest[12 + length..];
+    }
+
+    chunks
+}
+
+/// Decodes a zlib stream made of uncompressed deflate blocks, returning the data and the stream's
+/// Adler-32 checksum.
+fn inflate_stored(zlib_data: &[u8]) -> (Vec<u8>, u32) {
+    assert_eq!(&[0x78, 0x01], &zlib_data[..2]);
+
+    let mut data = Vec::new();
+    let mut rest = &zlib_data[2..];
+    loop {
+        let is_final = rest[0] == 1;
+        let length = rest[1] as usize | (rest[2] as usize) << 8;
+        let inverse_length = rest[3] as usize | (rest[4] as usize) << 8;
+        assert_eq!(0xFFFF, length ^ inverse_length);
+
+        data.extend_from_slice(&rest[5..5 + length]);
+        rest = &rest[5 + length..];
+
+        if is_final {
+            break;
+        }
+    }
+
+    assert_eq!(4, rest.len());
+    (data, read_u32_be(rest))
+}
+
+#[test]
+fn bmp_round_trip() {
+    // `parse_bmp` doesn't support padded rows, so the width is a multiple of 4.
+    let texture = test_texture(4, 3);
+    let bitmap = Bitmap::from_bytes(&*encode(&texture, ImageFormat::Bmp)).unwrap();
+    let decoded = Texture2d::from_bitmap(bitmap);
+
+    assert_eq!(texture.width(), decoded.width());
+    assert_eq!(texture.height(), decoded.height());
+
+    // Bitmaps don't store alpha, so the decoded pixels are opaque.
+    for index in 0..texture.width() * texture.height() {
+        let (r, g, b, _) = texture.pixel(index);
+        assert_eq!((r, g, b, 255), decoded.pixel(index), "pixel {}", index);
+    }
+}
+
+#[test]
+fn png_checksums() {
+    // A 1x1 RGBA image has the same header as every other 1x1 RGBA PNG, so its CRC is a known
+    // value, as is the CRC of the empty IEND chunk.
+    let texture = Texture2d::new(1, 1, DataFormat::Rgba, TextureData::u8(vec![1, 2, 3, 4]));
+    let chunks = png_chunks(&*encode(&texture, ImageFormat::Png));
+    assert_eq!(3, chunks.len());
+
+    let (ref header_type, ref header, header_crc) = chunks[0];
+    assert_eq!(b"IHDR", header_type);
+    assert_eq!(&[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0], &**header);
+    assert_eq!(0x1F15C489, header_crc);
+
+    let (ref end_type, ref end, end_crc) = chunks[2];
+    assert_eq!(b"IEND", end_type);
+    assert!(end.is_empty());
+    assert_eq!(0xAE426082, end_crc);
+
+    // The image data is the row's filter type followed by the pixel, `[0, 1, 2, 3, 4]`. Its
+    // Adler-32 checksum has the sum of the bytes plus one, 11, in the low half and the sum of the
+    // running totals, 1 + 2 + 4 + 7 + 11 = 25, in the high half.
+    let (ref data_type, ref data, _) = chunks[1];
+    assert_eq!(b"IDAT", data_type);
+    let (image_data, adler) = inflate_stored(&**data);
+    assert_eq!(vec![0, 1, 2, 3, 4], image_data);
+    assert_eq!(25 << 16 | 11, adler);
+}
+
+#[test]
+fn png_round_trip() {
+    // Large enough that the image data is split across several deflate blocks.
+    let texture = test_texture(200, 100);
+    let chunks = png_chunks(&*encode(&texture, ImageFormat::Png));
+
+    let (image_data, _) = inflate_stored(&*chunks[1].1);
+    let row_size = 1 + texture.width() * 4;
+    assert_eq!(row_size * texture.height(), image_data.len());
+
+    // PNG rows go from the top down, while texture rows go from the bottom up.
+    for (row_index, row) in image_data.chunks(row_size).enumerate() {
+        assert_eq!(0, row[0]);
+
+        let y = texture.height() - 1 - row_index;
+        for (x, pixel) in row[1..].chunks(4).enumerate() {
+            let (r, g, b, a) = texture.pixel(y * texture.width() + x);
+            assert_eq!(&[r, g, b, a], pixel);
+        }
+    }
+}
@@ -47,6 +47,7 @@ pub fn switch_context(old: FiberId, new: FiberId) {
                 ts: timestamp,
                 tid: platform::thread_id(),
                 pid: 0,
+                args: HashMap::new(),
             });
         }
     });
@@ -74,6 +75,7 @@ pub fn switch_context(old: FiberId, new: FiberId) {
                 ts: timestamp,
                 tid: platform::thread_id(),
                 pid: 0,
+                args: HashMap::new(),
             });
         }
     });
@@ -85,6 +87,25 @@ pub fn write_events_to_string() -> String {
     serde_json::to_string(&*events).unwrap()
 }
 
+/// Records the current value of a counter.
+///
+/// Counters show up as a graph alongside the timeline events, which is useful for tracking
+/// how much work was done each frame (e.g. how many objects were updated).
+pub fn counter(name: &'static str, value: i64) {
+    let mut args = HashMap::with_capacity(1);
+    args.insert(name, value);
+
+    push_event(Event {
+        name: name,
+        cat: String::new(),
+        ph: "C",
+        ts: platform::timestamp(),
+        tid: platform::thread_id(),
+        pid: 0,
+        args: args,
+    });
+}
+
 pub struct Stopwatch {
     name: &'static str,
 }
@@ -98,6 +119,7 @@ impl Stopwatch {
             ts: platform::timestamp(),
             tid: platform::thread_id(),
             pid: 0, // TODO: Do we care about tracking process ID?
+            args: HashMap::new(),
         });
 
         with_context(|stack| {
@@ -129,6 +151,7 @@ impl Drop for Stopwatch {
             ts: platform::timestamp(),
             tid: platform::thread_id(),
             pid: 0, // TODO: Do we care about tracking process ID?
+            args: HashMap::new(),
         });
     }
 }
@@ -152,6 +175,10 @@ struct Event {
 
     /// Thread ID for the event.
     tid: usize,
+
+    /// Extra data associated with the event, currently only used for counter values.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    args: HashMap<&'static str, i64>,
 }
 
 fn push_event(event: Event) {
@@ -0,0 +1,467 @@
+//! Keyframe animation and tweening of transforms.
+//!
+//! An `Animation` is made up of up to three tracks, one each for the position, orientation, and
+//! scale of a transform. Each track is a list of keyframes along with the interpolation mode used
+//! to blend between them. Once an animation has been built it can be played on a `Transform` with
+//! `play()`, which advances the animation once per frame until it finishes.
+//!
+//! For the common case of moving a transform from its current state to some target state there
+//! are the `tween_*()` functions, which build and play a two-keyframe animation.
+//!
+//! # Completion
+//!
+//! `play()` returns an `Async<()>` that completes when the animation finishes, so game code can
+//! `await()` the animation to sequence it with other work. As with any other `Async`, dropping it
+//! suspends the current fiber until the animation finishes, so use `forget()` to let the
+//! animation play in the background. Animations with a looping mode other than `Loop::Once`
+//! never finish, so their `Async` must always be forgotten and they keep playing for as long as
+//! the engine runs.
+//!
+//! Note that animations only advance between frames, so awaiting an animation from within a
+//! `run_each_frame()` behavior will stall the engine.
+
+use engine;
+use math::*;
+use math::quaternion::Quaternion;
+use scheduler::{self, Async};
+use time;
+use transform::{Transform, TransformData};
+
+/// The method used to interpolate between the keyframes in a track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Holds the value of each keyframe until the next keyframe is reached.
+    Step,
+
+    /// Interpolates linearly between keyframes. Orientations are spherically interpolated.
+    Linear,
+
+    /// Smoothly interpolates through the keyframes using a Catmull-Rom spline.
+    ///
+    /// Orientations use spherical quadrangle interpolation (squad), the rotational equivalent of
+    /// a cubic spline.
+    Cubic,
+}
+
+/// Determines what happens once an animation reaches its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Loop {
+    /// The animation plays once and stops on its last frame.
+    Once,
+
+    /// The animation restarts from the beginning once it reaches the end.
+    Repeat,
+
+    /// The animation alternates between playing forwards and backwards.
+    PingPong,
+}
+
+/// An easing function used to remap the timing of an animation.
+///
+/// Easing is applied to the playback of the animation as a whole (i.e. it's applied over the
+/// duration of the animation, not between each pair of keyframes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+}
+
+impl Easing {
+    /// Remaps `t`, which should be in the range [0, 1], according to the easing function.
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => t * (2.0 - t),
+            Easing::QuadInOut => if t < 0.5 {
+                2.0 * t * t
+            } else {
+                -1.0 + (4.0 - 2.0 * t) * t
+            },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => {
+                let t = t - 1.0;
+                t * t * t + 1.0
+            },
+            Easing::CubicInOut => if t < 0.5 {
+                4.0 * t * t * t
+            } else {
+                let t = 2.0 * t - 2.0;
+                0.5 * t * t * t + 1.0
+            },
+            Easing::SineIn => 1.0 - (t * PI * 0.5).cos(),
+            Easing::SineOut => (t * PI * 0.5).sin(),
+            Easing::SineInOut => 0.5 * (1.0 - (t * PI).cos()),
+        }
+    }
+}
+
+impl Default for Easing {
+    fn default() -> Easing {
+        Easing::Linear
+    }
+}
+
+/// A value that can be animated by a `Track`.
+pub trait Interpolate: Lerp + Copy {
+    /// Interpolates between `p1` and `p2` on a cubic curve, with `p0` and `p3` being the values
+    /// before and after the segment.
+    fn cubic(t: f32, p0: Self, p1: Self, p2: Self, p3: Self) -> Self;
+}
+
+impl Interpolate for Vector3 {
+    fn cubic(t: f32, p0: Vector3, p1: Vector3, p2: Vector3, p3: Vector3) -> Vector3 {
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        0.5 * (
+            2.0 * p1
+          + (p2 - p0) * t
+          + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+          + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3
+        )
+    }
+}
+
+impl Interpolate for Point {
+    fn cubic(t: f32, p0: Point, p1: Point, p2: Point, p3: Point) -> Point {
+        let offset = Vector3::cubic(
+            t,
+            p0.as_vector3(),
+            p1.as_vector3(),
+            p2.as_vector3(),
+            p3.as_vector3(),
+        );
+        Point::origin() + offset
+    }
+}
+
+impl Interpolate for Orientation {
+    fn cubic(t: f32, p0: Orientation, p1: Orientation, p2: Orientation, p3: Orientation) -> Orientation {
+        // `q` and `-q` are the same rotation, so each keyframe is flipped to be on the same side as
+        // its neighbour to make the curve take the shortest path between them.
+        let q1 = p1.0;
+        let q0 = closest(p0.0, q1);
+        let q2 = closest(p2.0, q1);
+        let q3 = closest(p3.0, q2);
+
+        let s1 = squad_control(q0, q1, q2);
+        let s2 = squad_control(q1, q2, q3);
+
+        let curve = Quaternion::slerp(q1, q2, t);
+        let control = Quaternion::slerp(s1, s2, t);
+        Orientation(Quaternion::slerp(curve, control, 2.0 * t * (1.0 - t)))
+    }
+}
+
+/// Gets whichever of `q` and `-q` is closer to `other`.
+fn closest(q: Quaternion, other: Quaternion) -> Quaternion {
+    if Quaternion::dot(q, other) < 0.0 { q * -1.0 } else { q }
+}
+
+/// Calculates the squad control point for `current`, which makes the curve's angular velocity
+/// continuous as it passes through `current` from `previous` towards `next`.
+fn squad_control(previous: Quaternion, current: Quaternion, next: Quaternion) -> Quaternion {
+    let inverse = current.conjugate();
+    let to_next = log(inverse * next);
+    let to_previous = log(inverse * previous);
+    current * exp((to_next + to_previous) * -0.25)
+}
+
+/// Calculates the logarithm of a unit quaternion, which is the rotation's axis scaled by half
+/// its angle.
+fn log(q: Quaternion) -> Vector3 {
+    let half_angle = q.w.max(-1.0).min(1.0).acos();
+    let sin = half_angle.sin();
+    if sin.is_zero() {
+        Vector3::zero()
+    } else {
+        q.v * (half_angle / sin)
+    }
+}
+
+/// Calculates the exponential of a quaternion with no real part, the inverse of `log()`.
+fn exp(v: Vector3) -> Quaternion {
+    let half_angle = v.magnitude();
+    if half_angle.is_zero() {
+        Quaternion::identity()
+    } else {
+        Quaternion::new(v * (half_angle.sin() / half_angle), half_angle.cos())
+    }
+}
+
+/// A single value in a track at a given point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe<T> {
+    /// The time of the keyframe in seconds from the start of the animation.
+    pub time: f32,
+    pub value: T,
+}
+
+/// A sequence of keyframes for a single property.
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+    interpolation: Interpolation,
+}
+
+impl<T: Interpolate> Track<T> {
+    /// Creates a new, empty track using the specified interpolation mode.
+    pub fn new(interpolation: Interpolation) -> Track<T> {
+        Track {
+            keyframes: Vec::new(),
+            interpolation: interpolation,
+        }
+    }
+
+    /// Adds a keyframe to the track at `time` seconds.
+    ///
+    /// Keyframes may be added in any order.
+    pub fn keyframe(mut self, time: f32, value: T) -> Track<T> {
+        assert!(time >= 0.0, "Keyframe time must not be negative, but was {}", time);
+
+        let index = self.keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > time)
+            .unwrap_or(self.keyframes.len());
+        self.keyframes.insert(index, Keyframe { time: time, value: value });
+        self
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &*self.keyframes
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Gets the time of the last keyframe in the track.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map(|keyframe| keyframe.time).unwrap_or(0.0)
+    }
+
+    /// Calculates the value of the track at `time` seconds.
+    ///
+    /// Times before the first keyframe or after the last keyframe are clamped to the first and
+    /// last keyframes, respectively. Returns `None` if the track has no keyframes.
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let keyframes = &*self.keyframes;
+        let (first, last) = match (keyframes.first(), keyframes.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return None,
+        };
+
+        if time <= first.time {
+            return Some(first.value);
+        }
+
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        // Find the pair of keyframes surrounding `time`. We've already handled the cases where
+        // `time` is outside the range of the keyframes, so there must be a next keyframe.
+        let next = keyframes
+            .iter()
+            .position(|keyframe| keyframe.time > time)
+            .expect("No keyframe after sample time");
+        let prev = next - 1;
+
+        let from = keyframes[prev];
+        let to = keyframes[next];
+        let t = (time - from.time) / (to.time - from.time);
+
+        let value = match self.interpolation {
+            Interpolation::Step => from.value,
+            Interpolation::Linear => T::lerp(t, from.value, to.value),
+            Interpolation::Cubic => {
+                // Duplicate the end keyframes when there's no keyframe before or after the
+                // current segment.
+                let before = if prev > 0 { keyframes[prev - 1].value } else { from.value };
+                let after = keyframes.get(next + 1).map(|keyframe| keyframe.value).unwrap_or(to.value);
+                T::cubic(t, before, from.value, to.value, after)
+            },
+        };
+
+        Some(value)
+    }
+}
+
+/// A keyframe animation that can be played on a `Transform`.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    position: Option<Track<Point>>,
+    orientation: Option<Track<Orientation>>,
+    scale: Option<Track<Vector3>>,
+    easing: Easing,
+    looping: Loop,
+}
+
+impl Animation {
+    /// Creates a new animation with no tracks that plays once with linear easing.
+    pub fn new() -> Animation {
+        Animation {
+            position: None,
+            orientation: None,
+            scale: None,
+            easing: Easing::Linear,
+            looping: Loop::Once,
+        }
+    }
+
+    pub fn position(mut self, track: Track<Point>) -> Animation {
+        self.position = Some(track);
+        self
+    }
+
+    pub fn orientation(mut self, track: Track<Orientation>) -> Animation {
+        self.orientation = Some(track);
+        self
+    }
+
+    pub fn scale(mut self, track: Track<Vector3>) -> Animation {
+        self.scale = Some(track);
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Animation {
+        self.easing = easing;
+        self
+    }
+
+    pub fn looping(mut self, looping: Loop) -> Animation {
+        self.looping = looping;
+        self
+    }
+
+    /// Gets the length of the animation in seconds, which is the duration of its longest track.
+    pub fn duration(&self) -> f32 {
+        let position = self.position.as_ref().map(Track::duration).unwrap_or(0.0);
+        let orientation = self.orientation.as_ref().map(Track::duration).unwrap_or(0.0);
+        let scale = self.scale.as_ref().map(Track::duration).unwrap_or(0.0);
+
+        position.max(orientation).max(scale)
+    }
+
+    /// Determines if the animation has finished playing at `time` seconds.
+    ///
+    /// Looping animations never finish.
+    pub fn is_finished(&self, time: f32) -> bool {
+        self.looping == Loop::Once && time >= self.duration()
+    }
+
+    /// Applies the state of the animation at `time` seconds to `transform`.
+    ///
+    /// `time` is the total time the animation has been playing, taking into account the
+    /// animation's looping mode and easing.
+    pub fn apply(&self, time: f32, transform: &mut Transform) {
+        self.apply_to_data(time, &mut *transform.inner().data_mut());
+    }
+
+    fn apply_to_data(&self, time: f32, data: &mut TransformData) {
+        let time = self.local_time(time);
+
+        if let Some(position) = self.position.as_ref().and_then(|track| track.sample(time)) {
+            data.position = position;
+        }
+
+        if let Some(orientation) = self.orientation.as_ref().and_then(|track| track.sample(time)) {
+            data.orientation = orientation;
+        }
+
+        if let Some(scale) = self.scale.as_ref().and_then(|track| track.sample(time)) {
+            data.scale = scale;
+        }
+    }
+
+    /// Converts total playback time into the time to sample from the tracks, taking into account
+    /// the animation's looping mode and easing.
+    pub fn local_time(&self, time: f32) -> f32 {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return 0.0;
+        }
+
+        let progress = match self.looping {
+            Loop::Once => (time / duration).clamp(0.0, 1.0),
+            Loop::Repeat => (time / duration).modulo(1.0),
+            Loop::PingPong => {
+                let progress = (time / duration).modulo(2.0);
+                if progress > 1.0 { 2.0 - progress } else { progress }
+            },
+        };
+
+        self.easing.apply(progress) * duration
+    }
+}
+
+/// Plays `animation` on `transform`, advancing it once per frame.
+///
+/// Returns an `Async` that completes once the animation finishes. See the module documentation
+/// for more information on awaiting animations.
+pub fn play(transform: &Transform, animation: Animation) -> Async<'static, ()> {
+    let (promise, async) = scheduler::promise();
+
+    // Apply the first frame immediately so that the transform doesn't sit in its old state for
+    // a frame before the animation starts.
+    let inner = transform.inner();
+    animation.apply_to_data(0.0, &mut *inner.data_mut());
+
+    if animation.is_finished(0.0) {
+        promise.complete(());
+        return async;
+    }
+
+    // The behavior is removed once the animation finishes.
+    let mut time = 0.0;
+    let mut promise = Some(promise);
+    engine::run_each_frame_while(move || {
+        time += time::delta_f32();
+        animation.apply_to_data(time, &mut *inner.data_mut());
+
+        if animation.is_finished(time) {
+            if let Some(promise) = promise.take() {
+                promise.complete(());
+            }
+
+            false
+        } else {
+            true
+        }
+    });
+
+    async
+}
+
+/// Moves `transform` from its current position to `position` over `duration` seconds.
+pub fn tween_position(transform: &Transform, position: Point, duration: f32, easing: Easing) -> Async<'static, ()> {
+    let track = Track::new(Interpolation::Linear)
+        .keyframe(0.0, transform.position())
+        .keyframe(duration, position);
+    play(transform, Animation::new().position(track).easing(easing))
+}
+
+/// Rotates `transform` from its current orientation to `orientation` over `duration` seconds.
+pub fn tween_orientation(transform: &Transform, orientation: Orientation, duration: f32, easing: Easing) -> Async<'static, ()> {
+    let track = Track::new(Interpolation::Linear)
+        .keyframe(0.0, transform.orientation())
+        .keyframe(duration, orientation);
+    play(transform, Animation::new().orientation(track).easing(easing))
+}
+
+/// Scales `transform` from its current scale to `scale` over `duration` seconds.
+pub fn tween_scale(transform: &Transform, scale: Vector3, duration: f32, easing: Easing) -> Async<'static, ()> {
+    let track = Track::new(Interpolation::Linear)
+        .keyframe(0.0, transform.scale())
+        .keyframe(duration, scale);
+    play(transform, Animation::new().scale(track).easing(easing))
+}
@@ -1,14 +1,31 @@
+use cell_extras::atomic_init_cell::AtomicInitCell;
 use engine::{self, EngineMessage};
-use transform::Transform;
+use math::*;
+use polygon::anchor::Anchor;
+use polygon::camera::{Camera as RenderCamera, CameraId};
+use resource::{Texture, TextureId};
+pub use polygon::camera::{ClearFlags, Projection, Viewport};
+pub use polygon::post_process::PostEffect;
+use transform::{Transform, TransformInnerHandle};
 use std::f32::consts::PI;
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::{Deref, DerefMut};
 use std::ptr::Unique;
+use std::sync::Arc;
 
+// TODO: This shouldn't be fully public, only public within the crate.
+pub type CameraIdHandle = Arc<AtomicInitCell<CameraId>>;
+
+/// A camera in the scene.
+///
+/// Any number of cameras can be active at once, each rendering to its own viewport in order of
+/// its depth. The camera is removed from the scene when it's dropped.
 pub struct Camera {
     data: Unique<CameraData>,
+    id: CameraIdHandle,
+    transform: TransformInnerHandle,
 
     // Pretend `Camera` owns a raw pointer to default implementation for `Sync`.
     // TODO: Remove this once negative trait bounds are stabilized.
@@ -17,21 +34,110 @@ pub struct Camera {
 
 impl Camera {
     pub fn new(transform: &Transform) -> Camera {
-        let mut camera_data = Box::new(CameraData::default());
+        Camera::with_data(transform, CameraData::default())
+    }
+
+    /// Creates a new camera using the specified camera settings.
+    pub fn with_data(transform: &Transform, data: CameraData) -> Camera {
+        let mut camera_data = Box::new(data);
 
         let ptr = &mut *camera_data as *mut _;
+        let id = Arc::new(AtomicInitCell::new());
 
-        engine::send_message(EngineMessage::Camera(camera_data, transform.inner()));
+        engine::send_message(EngineMessage::Camera(camera_data, id.clone(), transform.inner()));
 
         Camera {
             data: unsafe { Unique::new(ptr) },
+            id: id,
+            transform: transform.inner(),
             _phantom: PhantomData,
         }
     }
 
+    /// Converts a point in window pixel coordinates to a world space ray through the camera.
+    ///
+    /// Pixel coordinates have their origin at the top left corner of the window, the same as
+    /// `input::mouse_pos()`. The ray starts on the camera's near plane.
+    pub fn screen_point_to_ray(&self, screen_point: (i32, i32)) -> Ray {
+        let (width, height) = window_size();
+        let viewport = self.viewport();
+        let viewport_point = Vector2::new(
+            (screen_point.0 as f32 / width - viewport.x) / viewport.width,
+            (1.0 - screen_point.1 as f32 / height - viewport.y) / viewport.height,
+        );
+
+        let near = self.near();
+        let origin = self.viewport_to_world(viewport_point, near);
+        let direction = (self.viewport_to_world(viewport_point, near + 1.0) - origin).normalized();
+
+        Ray {
+            origin: origin,
+            direction: direction,
+        }
+    }
+
+    /// Projects a point in world space to window pixel coordinates.
+    ///
+    /// Returns `None` if the point is behind the camera. Points outside of the camera's view
+    /// still return a result, but the coordinates will fall outside of the window.
+    pub fn world_to_screen(&self, point: Point) -> Option<Vector2> {
+        let view_projection = render_camera(self).projection_matrix() * self.anchor().view_matrix();
+        let clip = point * view_projection;
+        if clip.w <= 0.0 {
+            return None;
+        }
+
+        let (width, height) = window_size();
+        let viewport = self.viewport();
+        let viewport_x = (clip.x / clip.w + 1.0) * 0.5;
+        let viewport_y = (clip.y / clip.w + 1.0) * 0.5;
+
+        Some(Vector2::new(
+            (viewport.x + viewport_x * viewport.width) * width,
+            (1.0 - (viewport.y + viewport_y * viewport.height)) * height,
+        ))
+    }
+
+    /// Converts a point in viewport coordinates to world space.
+    ///
+    /// Viewport coordinates are normalized to the camera's view, with `(0, 0)` being the bottom
+    /// left and `(1, 1)` being the top right. `depth` is the distance from the camera along its
+    /// forward axis.
+    pub fn viewport_to_world(&self, viewport_point: Vector2, depth: f32) -> Point {
+        let ndc_x = viewport_point.x * 2.0 - 1.0;
+        let ndc_y = viewport_point.y * 2.0 - 1.0;
+
+        // Determine the half-height of the view volume at `depth`.
+        let half_height = match self.projection() {
+            Projection::Perspective => depth * (self.fov() * 0.5).tan(),
+            Projection::Orthographic => self.size(),
+        };
+        let half_width = half_height * self.aspect();
+
+        let view_point = Point::new(ndc_x * half_width, ndc_y * half_height, -depth);
+        view_point * self.anchor().inverse_view_matrix()
+    }
+
     pub fn forget(self) {
         mem::forget(self);
     }
+
+    /// Creates an anchor matching the camera's transform, that way we calculate the view matrix
+    /// the same way the renderer does.
+    fn anchor(&self) -> Anchor {
+        let data = self.transform.data();
+
+        let mut anchor = Anchor::new();
+        anchor.set_position(data.position);
+        anchor.set_orientation(data.orientation);
+        anchor
+    }
+}
+
+impl Drop for Camera {
+    fn drop(&mut self) {
+        engine::send_message(EngineMessage::RemoveCamera(self.id.clone()));
+    }
 }
 
 unsafe impl Send for Camera {}
@@ -45,6 +151,14 @@ impl Debug for Camera {
             .field("aspect", &data.aspect)
             .field("near", &data.near)
             .field("far", &data.far)
+            .field("projection", &data.projection)
+            .field("size", &data.size)
+            .field("viewport", &data.viewport)
+            .field("depth", &data.depth)
+            .field("clear", &data.clear)
+            .field("layer_mask", &data.layer_mask)
+            .field("target", &data.target)
+            .field("post_effects", &data.post_effects)
             .finish()
     }
 }
@@ -59,15 +173,67 @@ impl DerefMut for Camera {
     fn deref_mut(&mut self) -> &mut CameraData { unsafe { self.data.get_mut() } }
 }
 
+/// A ray in world space, such as one cast from the camera through the mouse cursor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: Point,
+
+    /// The direction of the ray. This is always normalized.
+    pub direction: Vector3,
+}
+
+impl Ray {
+    /// Gets the point `distance` units along the ray.
+    pub fn point_at(&self, distance: f32) -> Point {
+        self.origin + self.direction * distance
+    }
+}
+
 #[derive(Debug)]
 pub struct CameraData {
     fov: f32,
     aspect: f32,
     near: f32,
     far: f32,
+    projection: Projection,
+    size: f32,
+    viewport: Viewport,
+    depth: i32,
+    clear: ClearFlags,
+    layer_mask: u32,
+    target: Option<TextureId>,
+    post_effects: Option<Vec<PostEffect>>,
 }
 
 impl CameraData {
+    pub fn new(fov: f32, aspect: f32, near: f32, far: f32) -> CameraData {
+        CameraData {
+            fov: fov,
+            aspect: aspect,
+            near: near,
+            far: far,
+            projection: Projection::Perspective,
+            size: 1.0,
+            viewport: Viewport::default(),
+            depth: 0,
+            clear: ClearFlags::default(),
+            layer_mask: !0,
+            target: None,
+            post_effects: None,
+        }
+    }
+
+    /// Creates camera settings for an orthographic camera.
+    ///
+    /// `size` is half the height of the view volume in world units.
+    pub fn orthographic(size: f32, aspect: f32, near: f32, far: f32) -> CameraData {
+        CameraData {
+            projection: Projection::Orthographic,
+            size: size,
+            .. CameraData::new(PI / 3.0, aspect, near, far)
+        }
+    }
+
     pub fn fov(&self) -> f32 { self.fov }
 
     pub fn aspect(&self) -> f32 { self.aspect }
@@ -75,6 +241,102 @@ impl CameraData {
     pub fn near(&self) -> f32 { self.near }
 
     pub fn far(&self) -> f32 { self.far }
+
+    pub fn projection(&self) -> Projection { self.projection }
+
+    pub fn size(&self) -> f32 { self.size }
+
+    pub fn viewport(&self) -> Viewport { self.viewport }
+
+    pub fn depth(&self) -> i32 { self.depth }
+
+    pub fn clear(&self) -> ClearFlags { self.clear }
+
+    pub fn layer_mask(&self) -> u32 { self.layer_mask }
+
+    /// Gets the texture the camera renders into, or `None` if it renders to the window.
+    pub fn target(&self) -> Option<TextureId> { self.target }
+
+    /// Gets the post effects applied to the camera's image, or `None` if the camera uses the
+    /// renderer's default post effects.
+    pub fn post_effects(&self) -> Option<&[PostEffect]> {
+        self.post_effects.as_ref().map(|effects| &**effects)
+    }
+
+    pub fn set_fov(&mut self, fov: f32) {
+        debug_assert!(fov > 0.0, "Field of view must be positive: {}", fov);
+        debug_assert!(fov < PI, "Field of view must be less than 180 degrees: {}", fov);
+        self.fov = fov;
+    }
+
+    pub fn set_aspect(&mut self, aspect: f32) {
+        debug_assert!(aspect > 0.0, "Aspect ratio must be positive: {}", aspect);
+        self.aspect = aspect;
+    }
+
+    pub fn set_near(&mut self, near: f32) {
+        debug_assert!(near > 0.0, "Near plane distance must be positive: {}", near);
+        debug_assert!(near < self.far, "Near plane distance must be less than far plane distance, near: {}, far: {}", near, self.far);
+        self.near = near;
+    }
+
+    pub fn set_far(&mut self, far: f32) {
+        debug_assert!(far > 0.0, "Far plane distance must be positive: {}", far);
+        debug_assert!(far > self.near, "Far plane distance must be greater than near plane distance, near: {}, far: {}", self.near, far);
+        self.far = far;
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    /// Sets the half-height of the view volume used by orthographic projection.
+    pub fn set_size(&mut self, size: f32) {
+        debug_assert!(size > 0.0, "Orthographic size must be positive: {}", size);
+        self.size = size;
+    }
+
+    /// Sets the region of the window the camera renders to.
+    ///
+    /// The viewport is normalized to the window size, see `Viewport` for details. Note that the
+    /// aspect ratio isn't updated automatically, so set it to match the viewport's proportions.
+    pub fn set_viewport(&mut self, viewport: Viewport) {
+        self.viewport = viewport;
+    }
+
+    /// Sets the render order of the camera. Cameras with a lower depth are rendered first, so
+    /// cameras with a higher depth draw on top of them.
+    pub fn set_depth(&mut self, depth: i32) {
+        self.depth = depth;
+    }
+
+    pub fn set_clear(&mut self, clear: ClearFlags) {
+        self.clear = clear;
+    }
+
+    /// Sets which layers the camera renders, where bit `n` represents layer `n`.
+    pub fn set_layer_mask(&mut self, layer_mask: u32) {
+        self.layer_mask = layer_mask;
+    }
+
+    /// Sets the texture that the camera renders into, or `None` to render to the window.
+    ///
+    /// The texture must have been created with `resource::create_render_texture()`. The viewport
+    /// is relative to the texture instead of the window, and the screen space helpers such as
+    /// `Camera::screen_point_to_ray()` still assume the camera renders to the window.
+    pub fn set_target(&mut self, target: Option<&Texture>) {
+        self.target = target.map(Texture::id);
+    }
+
+    /// Sets the post effects applied to the camera's image, in the order they're applied.
+    ///
+    /// `None` uses the renderer's default post effects, and an empty list disables
+    /// post-processing for the camera. Custom effects are created from a loaded material with
+    /// `PostEffect::from(&material)`. With post effects the camera always covers its viewport,
+    /// even if it doesn't clear the color buffer.
+    pub fn set_post_effects(&mut self, post_effects: Option<Vec<PostEffect>>) {
+        self.post_effects = post_effects;
+    }
 }
 
 impl Default for CameraData {
@@ -84,6 +346,29 @@ impl Default for CameraData {
             aspect: 1.0,
             near: 0.001,
             far: 1_000.0,
+            projection: Projection::Perspective,
+            size: 1.0,
+            viewport: Viewport::default(),
+            depth: 0,
+            clear: ClearFlags::default(),
+            layer_mask: !0,
+            target: None,
+            post_effects: None,
         }
     }
 }
+
+/// Creates a renderer camera with the same settings as `data`.
+fn render_camera(data: &CameraData) -> RenderCamera {
+    let mut camera = RenderCamera::new(data.fov, data.aspect, data.near, data.far);
+    camera.set_projection(data.projection);
+    camera.set_size(data.size);
+    camera.set_viewport(data.viewport);
+    camera
+}
+
+/// Gets the size in pixels of the window's client area, which is what the renderer draws to.
+fn window_size() -> (f32, f32) {
+    let (width, height) = engine::window(|window| window.client_size());
+    (width as f32, height as f32)
+}
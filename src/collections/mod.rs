@@ -1,6 +1,8 @@
 extern crate alloc;
 
 pub use self::array::Array;
+pub use self::segmented_array::SegmentedArray;
 
 pub mod array;
 pub mod atomic_array;
+pub mod segmented_array;
@@ -0,0 +1,200 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Index;
+use std::ptr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// The number of elements in the first segment. Each subsequent segment is twice as large as the
+/// one before it.
+const FIRST_SEGMENT_CAPACITY: usize = 1024;
+
+/// The maximum number of segments, giving a total capacity of just over one billion elements.
+const MAX_SEGMENTS: usize = 20;
+
+/// A growable, thread-safe array container whose elements never move.
+///
+/// `SegmentedArray` is the growable counterpart to [`AtomicArray`][atomic_array]. Rather than
+/// reallocating a single buffer when it runs out of space, it allocates a new segment (each
+/// segment being twice the size of the previous one) and leaves existing segments untouched.
+/// This gives it the following properties:
+///
+/// - References to elements remain valid for as long as the array lives, so it's safe to hold a
+///   raw pointer to an element while other threads push new elements.
+/// - Pushing is done through a shared reference and is safe to do concurrently from multiple
+///   threads. Pushes are serialized by an internal lock, which is only held while writing the new
+///   element and, occasionally, while allocating a new segment.
+/// - Reading elements never takes the lock, so existing elements can be accessed while other
+///   threads are pushing.
+///
+/// Only pushing is supported, elements cannot be removed once added.
+///
+/// [atomic_array]: ../atomic_array/struct.AtomicArray.html
+// TODO: impl Debug for SegmentedArray.
+pub struct SegmentedArray<T> {
+    segments: [AtomicPtr<T>; MAX_SEGMENTS],
+    len: AtomicUsize,
+    write_lock: Mutex<()>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> SegmentedArray<T> {
+    pub fn new() -> SegmentedArray<T> {
+        SegmentedArray {
+            segments: Default::default(),
+            len: AtomicUsize::new(0),
+            write_lock: Mutex::new(()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Adds an element to the end of the array, returning a reference to the element in its
+    /// final location.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the array is already at its maximum capacity.
+    pub fn push(&self, element: T) -> &T {
+        let _guard = self.write_lock.lock().expect("Segmented array write lock was poisoned");
+
+        let index = self.len.load(Ordering::SeqCst);
+        let (segment, offset) = location(index);
+        assert!(segment < MAX_SEGMENTS, "Segmented array exceeded its maximum capacity");
+
+        // Allocate the segment if this is the first element to go into it.
+        let mut segment_ptr = self.segments[segment].load(Ordering::SeqCst);
+        if segment_ptr.is_null() {
+            let mut buffer = Vec::<T>::with_capacity(segment_capacity(segment));
+            segment_ptr = buffer.as_mut_ptr();
+            mem::forget(buffer);
+
+            self.segments[segment].store(segment_ptr, Ordering::SeqCst);
+        }
+
+        // Write the element into its slot without dropping the uninitialized memory that was
+        // there. Only once the write completes do we increment `len`, that way no other thread
+        // can observe the element in an uninitialized state.
+        unsafe {
+            let dest = segment_ptr.offset(offset as isize);
+            ptr::write(dest, element);
+            self.len.store(index + 1, Ordering::SeqCst);
+
+            &*dest
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len() {
+            let (segment, offset) = location(index);
+            unsafe { Some(&*self.segments[segment].load(Ordering::SeqCst).offset(offset as isize)) }
+        } else {
+            None
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the elements in the array.
+    ///
+    /// The iterator only visits the elements that were present when it was created, elements
+    /// pushed while iterating will not be visited.
+    pub fn iter(&self) -> Iter<T> {
+        Iter {
+            array: self,
+            index: 0,
+            len: self.len(),
+        }
+    }
+}
+
+impl<T> Drop for SegmentedArray<T> {
+    fn drop(&mut self) {
+        let mut remaining = self.len();
+        for (segment, segment_ptr) in self.segments.iter().enumerate() {
+            let segment_ptr = segment_ptr.load(Ordering::SeqCst);
+            if segment_ptr.is_null() {
+                break;
+            }
+
+            // Reconstruct the `Vec` that originally allocated the segment so that it will drop
+            // the initialized elements and deallocate the buffer.
+            let capacity = segment_capacity(segment);
+            let len = if remaining < capacity { remaining } else { capacity };
+            remaining -= len;
+
+            unsafe { Vec::from_raw_parts(segment_ptr, len, capacity); }
+        }
+    }
+}
+
+impl<T> Index<usize> for SegmentedArray<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        let len = self.len();
+        self.get(index)
+            .unwrap_or_else(|| panic!("Index out of bounds, length is {} but index was {}", len, index))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SegmentedArray<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+unsafe impl<T> Send for SegmentedArray<T> where T: Send {}
+unsafe impl<T> Sync for SegmentedArray<T> where T: Send + Sync {}
+
+/// An iterator over the elements of a `SegmentedArray`.
+pub struct Iter<'a, T: 'a> {
+    array: &'a SegmentedArray<T>,
+    index: usize,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.index < self.len {
+            let element = self.array.get(self.index);
+            self.index += 1;
+            element
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+/// Returns the number of elements that fit in the specified segment.
+fn segment_capacity(segment: usize) -> usize {
+    FIRST_SEGMENT_CAPACITY << segment
+}
+
+/// Maps an index into the array to a segment and an offset within that segment.
+fn location(index: usize) -> (usize, usize) {
+    // Segment `n` starts at index `FIRST_SEGMENT_CAPACITY * (2^n - 1)`, so the segment is the
+    // base 2 log of `index / FIRST_SEGMENT_CAPACITY + 1`.
+    let bucket = index / FIRST_SEGMENT_CAPACITY + 1;
+    let segment = mem::size_of::<usize>() * 8 - 1 - bucket.leading_zeros() as usize;
+    let offset = index - FIRST_SEGMENT_CAPACITY * ((1 << segment) - 1);
+
+    (segment, offset)
+}
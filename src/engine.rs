@@ -1,21 +1,24 @@
-use camera::CameraData;
-use mesh_renderer::MeshRendererData;
-use resource::{MaterialId, MeshId};
-use scheduler::{self, WorkId};
+use camera::{CameraData, CameraIdHandle};
+use mesh_renderer::{MaterialProperty, MeshInstanceIdHandle, MeshRendererData};
+use resource::{MaterialId, MeshId, TextureId};
+use scheduler::{self, Promise, WorkId};
 use transform::{TransformInnerHandle, TransformGraph};
 use bootstrap::window::{Message, Window};
 use cell_extras::{AtomicInitCell, InitCell};
 use input::{self, Input, ScanCode};
 use light::LightInner;
-use polygon::{GpuMesh, Renderer, RendererBuilder};
+use polygon::{BuildMaterialError, GpuMesh, Renderer, RendererBuilder};
 use polygon::anchor::Anchor;
 use polygon::camera::{Camera as RenderCamera, CameraId};
-use polygon::material::MaterialId as PolygonMaterialId;
-use polygon::mesh_instance::MeshInstance;
+use polygon::material::{MaterialId as PolygonMaterialId, MaterialType};
+use polygon::mesh_instance::{MeshInstance, MeshInstanceId};
+use polygon::texture::{GpuTexture, Texture2d};
+pub use polygon::texture::ImageFormat;
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Write;
 use std::mem;
+use std::path::PathBuf;
 use std::ptr::{self, Unique};
 use std::sync::{Arc, Barrier};
 use std::sync::mpsc::{self, Receiver, Sender};
@@ -124,15 +127,22 @@ impl EngineBuilder {
             channel: receiever,
 
             mesh_map: HashMap::new(),
+            material_map: HashMap::new(),
+            texture_map: HashMap::new(),
+
+            missing_mesh_instances: Vec::new(),
 
             scene_graph: TransformGraph::new(),
             lights: Vec::new(),
-            camera: None,
+            cameras: Vec::new(),
             behaviors: Vec::new(),
             input: Input::new(),
 
             default_material_id: default_material_id,
 
+            screenshots: Vec::new(),
+            recording: None,
+
             debug_pause: false,
         });
 
@@ -169,15 +179,25 @@ pub struct Engine {
     channel: Receiver<EngineMessage>,
 
     mesh_map: HashMap<MeshId, GpuMesh>,
+    material_map: HashMap<MaterialId, PolygonMaterialId>,
+    texture_map: HashMap<TextureId, GpuTexture>,
+
+    /// Mesh renderers whose mesh or material was removed before their mesh instance could be
+    /// created. Their ids are never initialized, so any messages for them are ignored.
+    missing_mesh_instances: Vec<MeshInstanceIdHandle>,
 
     scene_graph: TransformGraph,
     lights: Vec<LightInner>,
-    camera: Option<(Box<CameraData>, CameraId)>,
-    behaviors: Vec<Box<FnMut() + Send>>,
+    cameras: Vec<(Box<CameraData>, CameraId)>,
+    behaviors: Vec<Box<FnMut() -> bool + Send>>,
     input: Input,
 
     default_material_id: PolygonMaterialId,
 
+    /// Paths to save the next frame to, see `screenshot()`.
+    screenshots: Vec<PathBuf>,
+    recording: Option<Recording>,
+
     debug_pause: bool,
 }
 
@@ -223,12 +243,57 @@ pub fn window<F, T>(func: F) -> T
 
 pub enum EngineMessage {
     Anchor(TransformInnerHandle),
-    Camera(Box<CameraData>, TransformInnerHandle),
-    Light(LightInner),
-    Material(MaterialId, ::polygon::material::MaterialSource),
+    Camera(Box<CameraData>, CameraIdHandle, TransformInnerHandle),
+    RemoveCamera(CameraIdHandle),
+    Light(LightInner, Option<TransformInnerHandle>),
+    RemoveLight(LightInner),
+    Material(MaterialId, ::polygon::material::MaterialSource, Promise<Result<PolygonMaterialId, BuildMaterialError>>),
+    RemoveMaterial(MaterialId),
+    MaterialProperty(MeshInstanceIdHandle, String, MaterialProperty),
     Mesh(MeshId, ::polygon::geometry::mesh::Mesh),
-    MeshInstance(Box<MeshRendererData>, TransformInnerHandle),
-    Behavior(Box<FnMut() + Send>),
+    RemoveMesh(MeshId),
+    MeshInstance(Box<MeshRendererData>, MeshInstanceIdHandle, TransformInnerHandle),
+    MeshInstanceMesh(MeshInstanceIdHandle, MeshId),
+    MeshInstanceVisible(MeshInstanceIdHandle, bool),
+    MeshInstanceCastsShadows(MeshInstanceIdHandle, bool),
+    RemoveMeshInstance(MeshInstanceIdHandle),
+    Texture(TextureId, Texture2d),
+    RenderTexture(TextureId, usize, usize),
+    RemoveTexture(TextureId),
+    Behavior(Box<FnMut() -> bool + Send>),
+    Screenshot(PathBuf),
+    StartRecording(Recording),
+    StopRecording,
+}
+
+/// The settings and progress of a recording started with `start_recording()`.
+#[derive(Debug)]
+pub struct Recording {
+    directory: PathBuf,
+    format: ImageFormat,
+    interval: usize,
+
+    /// The number of frames drawn since recording started.
+    frames: usize,
+
+    /// The number of frames saved so far.
+    saved: usize,
+}
+
+impl Recording {
+    /// Advances the recording by one frame, returning the path to save the frame to if it should
+    /// be recorded.
+    fn next_frame(&mut self) -> Option<PathBuf> {
+        let frame = self.frames;
+        self.frames += 1;
+        if frame % self.interval != 0 {
+            return None;
+        }
+
+        let file_name = format!("frame_{:06}.{}", self.saved, self.format.extension());
+        self.saved += 1;
+        Some(self.directory.join(file_name))
+    }
 }
 
 pub fn send_message(message: EngineMessage) {
@@ -244,15 +309,95 @@ pub fn run_each_frame<F>(func: F)
     F: 'static,
     F: FnMut(),
     F: Send,
+{
+    let mut func = func;
+    run_each_frame_while(move || {
+        func();
+        true
+    });
+}
+
+/// Runs `func` once per frame until it returns `false`, at which point it's removed.
+pub fn run_each_frame_while<F>(func: F)
+    where
+    F: 'static,
+    F: FnMut() -> bool,
+    F: Send,
 {
     send_message(EngineMessage::Behavior(Box::new(func)));
 }
 
+/// Saves the next frame drawn to the window as an image.
+///
+/// The image format is determined by the file extension, `.bmp` saves a bitmap and anything else
+/// saves a PNG.
+pub fn screenshot<P: Into<PathBuf>>(path: P) {
+    send_message(EngineMessage::Screenshot(path.into()));
+}
+
+/// Starts saving every `interval`th frame drawn to the window as a numbered image in `directory`.
+///
+/// The frames are named `frame_000000.png`, `frame_000001.png`, etc., numbered by the order they
+/// were saved in. Saving frames is slow, so the game will likely run below its usual frame rate
+/// while recording. Any previous recording is stopped.
+pub fn start_recording<P: Into<PathBuf>>(directory: P, format: ImageFormat, interval: usize) {
+    assert!(interval > 0, "Recording interval must be at least 1");
+    send_message(EngineMessage::StartRecording(Recording {
+        directory: directory.into(),
+        format: format,
+        interval: interval,
+        frames: 0,
+        saved: 0,
+    }));
+}
+
+/// Stops the recording started with `start_recording()`.
+///
+/// Does nothing if no recording is in progress.
+pub fn stop_recording() {
+    send_message(EngineMessage::StopRecording);
+}
+
 /// Suspends the calling worker until the engine main loop has finished.
 pub fn wait_for_quit() {
     MAIN_LOOP.borrow().await();
 }
 
+/// Saves the frame that was just drawn for any pending screenshots and the current recording.
+///
+/// `recording_path` is where the current recording saves the frame, if it saves this one. The
+/// renderer must have been told to capture the frame with `capture_next_frame()` before it was
+/// drawn.
+fn capture_frame(engine: &mut Engine, recording_path: Option<PathBuf>) {
+    let _s = Stopwatch::new("Capture frame");
+
+    let frame = engine.renderer.read_pixels(None);
+    for path in engine.screenshots.drain(..) {
+        let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
+        if let Err(error) = frame.save(&path, format) {
+            println!("WARNING: Failed to save screenshot {:?}: {:?}", path, error);
+        }
+    }
+
+    if let Some(path) = recording_path {
+        let format = engine.recording.as_ref().unwrap().format;
+        if let Err(error) = frame.save(&path, format) {
+            println!("WARNING: Failed to save recorded frame {:?}, stopping recording: {:?}", path, error);
+            engine.recording = None;
+        }
+    }
+}
+
+/// Gets the renderer's id for a mesh renderer's mesh instance, or `None` if the mesh instance was
+/// never created.
+fn mesh_instance_id(engine: &Engine, id: &MeshInstanceIdHandle) -> Option<MeshInstanceId> {
+    if engine.missing_mesh_instances.iter().any(|missing| Arc::ptr_eq(missing, id)) {
+        None
+    } else {
+        Some(*id.borrow())
+    }
+}
+
 fn main_loop(mut engine: Box<Engine>) {
     // TODO: This should be a constant, but we can't create constant `Duration` objects right now.
     let target_frame_time = Duration::new(0, 1_000_000_000 / 60);
@@ -291,18 +436,22 @@ fn main_loop(mut engine: Box<Engine>) {
             // Kick off all game behaviors and wait for them to complete.
             if engine.behaviors.len() > 0 && (!engine.debug_pause || debug_step) {
                 let _stopwatch = Stopwatch::new("game behaviors");
-                let mut pending = Vec::with_capacity(engine.behaviors.len());
+                let keep_alive = {
+                    let mut pending = Vec::with_capacity(engine.behaviors.len());
 
-                // Start all behaviors...
-                for behavior in engine.behaviors.iter_mut() {
-                    let async = scheduler::start(&mut **behavior);
-                    pending.push(async);
-                }
+                    // Start all behaviors...
+                    for behavior in engine.behaviors.iter_mut() {
+                        let async = scheduler::start(&mut **behavior);
+                        pending.push(async);
+                    }
 
-                // ... then wait for each of them to finish.
-                for async in pending {
-                    async.await();
-                }
+                    // ... then wait for each of them to finish.
+                    pending.into_iter().map(|async| async.await()).collect::<Vec<_>>()
+                };
+
+                // Remove any behaviors that are done running.
+                let mut keep_alive = keep_alive.into_iter();
+                engine.behaviors.retain(|_| keep_alive.next().unwrap_or(true));
             } else {
                 let _s = Stopwatch::new("no game behaviors");
                 // There are no per-frame behaviors. We suspend the main loop fiber anyway to give
@@ -315,6 +464,10 @@ fn main_loop(mut engine: Box<Engine>) {
             // loaded but need to be registered with the renderer before the next draw.
             {
                 let _s = Stopwatch::new("Process resource messages");
+
+                // Messages that can't be handled until another message has been processed, which
+                // are sent again once all pending messages have been handled.
+                let mut deferred_messages = Vec::new();
                 while let Ok(message) = engine.channel.try_recv() {
                     match message {
                         EngineMessage::Anchor(transform_inner) => {
@@ -322,11 +475,14 @@ fn main_loop(mut engine: Box<Engine>) {
                             let anchor = Anchor::new();
                             let anchor_id = engine.renderer.register_anchor(anchor);
 
+                            // NOTE: Anchors are never unregistered because transforms are never
+                            // removed from the scene graph, see `Drop for Transform`. Once they
+                            // are, the transform's anchor should be removed with
+                            // `Renderer::unregister_anchor()`.
                             transform_inner.set_anchor(anchor_id);
                         },
-                        EngineMessage::Camera(camera_data, transform_inner) => {
+                        EngineMessage::Camera(camera_data, id, transform_inner) => {
                             let _s = Stopwatch::new("Camera message");
-                            assert!(engine.camera.is_none(), "Can't add camera, one is already registered");
 
                             let anchor_id = match transform_inner.anchor() {
                                 Some(anchor) => anchor,
@@ -336,14 +492,37 @@ fn main_loop(mut engine: Box<Engine>) {
                             let mut camera = RenderCamera::default();
                             camera.set_anchor(anchor_id);
                             let camera_id = engine.renderer.register_camera(camera);
+                            id.init(camera_id);
+
+                            engine.cameras.push((camera_data, camera_id));
+                        },
+                        EngineMessage::RemoveCamera(id) => {
+                            let _s = Stopwatch::new("Remove camera message");
+                            let camera_id = *id.borrow();
 
-                            engine.camera = Some((camera_data, camera_id));
+                            engine.renderer.unregister_camera(camera_id);
+                            engine.cameras.retain(|&(_, id)| id != camera_id);
                         },
-                        EngineMessage::Light(light_inner) => {
+                        EngineMessage::Light(light_inner, transform_inner) => {
                             let _s = Stopwatch::new("Light message");
+
+                            // The transform's anchor is created by an `Anchor` message, so if
+                            // that hasn't been handled yet the light is registered next frame.
+                            let anchor_id = transform_inner
+                                .as_ref()
+                                .and_then(|transform_inner| transform_inner.anchor());
+                            if transform_inner.is_some() && anchor_id.is_none() {
+                                deferred_messages.push(EngineMessage::Light(light_inner, transform_inner));
+                                continue;
+                            }
+
                             {
                                 let &(ref id, ref light) = &*light_inner;
-                                let light = light.borrow().clone();
+                                let mut light = light.borrow().clone();
+
+                                if let Some(anchor_id) = anchor_id {
+                                    light.set_anchor(anchor_id);
+                                }
 
                                 let light_id = engine.renderer.register_light(light);
                                 id.init(light_id);
@@ -351,13 +530,89 @@ fn main_loop(mut engine: Box<Engine>) {
 
                             engine.lights.push(light_inner);
                         }
-                        EngineMessage::Material(_material_id, _material_source) => {
-                            // let _s = Stopwatch::new("Material message");
-                            // let material = engine.renderer.build_material(material_source).expect("TODO: Handle material compilation failure");
-                            // let _gpu_material = engine.renderer.register_material(material);
-                            //
-                            // // TODO: Create an association between `material_id` and `material_source`.
-                            unimplemented!();
+                        EngineMessage::RemoveLight(light_inner) => {
+                            let _s = Stopwatch::new("Remove light message");
+
+                            // A light dropped while it's waiting for its anchor hasn't been
+                            // registered yet, so it has to be removed after it's registered.
+                            if !engine.lights.iter().any(|light| Arc::ptr_eq(light, &light_inner)) {
+                                deferred_messages.push(EngineMessage::RemoveLight(light_inner));
+                                continue;
+                            }
+
+                            let light_id = *light_inner.0.borrow();
+
+                            engine.renderer.unregister_light(light_id);
+                            engine.lights.retain(|light| *light.0.borrow() != light_id);
+                        }
+                        EngineMessage::Material(material_id, material_source, promise) => {
+                            let _s = Stopwatch::new("Material message");
+
+                            // Build failures are reported back to `load_material()`, in which
+                            // case no `Material` is ever created for `material_id`.
+                            match engine.renderer.build_material(material_source) {
+                                Ok(material) => {
+                                    let gpu_material = engine.renderer.register_shared_material(material);
+                                    let last = engine.material_map.insert(material_id, gpu_material);
+                                    assert!(last.is_none(), "Duplicate material_id found: {:?}", material_id);
+
+                                    promise.complete(Ok(gpu_material));
+                                },
+                                Err(error) => promise.complete(Err(error)),
+                            }
+                        },
+                        EngineMessage::RemoveMaterial(material_id) => {
+                            let _s = Stopwatch::new("Remove material message");
+                            if let Some(gpu_material) = engine.material_map.remove(&material_id) {
+                                engine.renderer.unregister_shared_material(gpu_material);
+                            }
+                        },
+                        EngineMessage::MaterialProperty(id, name, property) => {
+                            let _s = Stopwatch::new("Material property message");
+                            let mesh_instance_id = match mesh_instance_id(engine, &id) {
+                                Some(mesh_instance_id) => mesh_instance_id,
+                                None => continue,
+                            };
+
+                            // Textures have to be looked up before borrowing the mesh instance's
+                            // material. Textures that have already been dropped are skipped.
+                            let gpu_texture = match property {
+                                MaterialProperty::Texture(texture_id) => match engine.texture_map.get(&texture_id) {
+                                    Some(&gpu_texture) => Some(gpu_texture),
+                                    None => continue,
+                                },
+                                _ => None,
+                            };
+
+                            // The first time one of its properties is overridden the mesh instance
+                            // gets its own copy of its shared material so that the override doesn't
+                            // affect any other mesh instances.
+                            let shared_material = engine.renderer
+                                .get_mesh_instance(mesh_instance_id)
+                                .expect("No such mesh instance")
+                                .shared_material();
+                            if let Some(material_id) = shared_material {
+                                // The mesh instance isn't drawn once its material has been dropped,
+                                // so there's nothing to override.
+                                let material = match engine.renderer.get_material(material_id) {
+                                    Some(material) => material.clone(),
+                                    None => continue,
+                                };
+                                engine.renderer.set_mesh_instance_material(
+                                    mesh_instance_id,
+                                    MaterialType::Owned(material),
+                                );
+                            }
+
+                            let material = engine.renderer
+                                .get_mesh_instance_mut(mesh_instance_id)
+                                .and_then(|mesh_instance| mesh_instance.material_mut())
+                                .expect("Mesh instance didn't own its material");
+                            match property {
+                                MaterialProperty::Color(color) => material.set_color(name, color),
+                                MaterialProperty::f32(value) => material.set_f32(name, value),
+                                MaterialProperty::Texture(_) => material.set_texture(name, gpu_texture.unwrap()),
+                            }
                         },
                         EngineMessage::Mesh(mesh_id, mesh_data) => {
                             let _s = Stopwatch::new("Mesh message");
@@ -365,44 +620,147 @@ fn main_loop(mut engine: Box<Engine>) {
                             let last = engine.mesh_map.insert(mesh_id, gpu_mesh);
                             assert!(last.is_none(), "Duplicate mesh_id found: {:?}", mesh_id);
                         },
-                        EngineMessage::MeshInstance(mesh_renderer_data, transform_inner) => {
+                        EngineMessage::RemoveMesh(mesh_id) => {
+                            let _s = Stopwatch::new("Remove mesh message");
+                            if let Some(gpu_mesh) = engine.mesh_map.remove(&mesh_id) {
+                                engine.renderer.unregister_mesh(gpu_mesh);
+                            }
+                        },
+                        EngineMessage::MeshInstance(mesh_renderer_data, id, transform_inner) => {
                             let _s = Stopwatch::new("Mesh instance message");
                             let anchor_id = match transform_inner.anchor() {
                                 Some(anchor) => anchor,
                                 None => unimplemented!(), // TODO: Create the anchor.
                             };
 
-                            let gpu_mesh = *engine
-                            .mesh_map
-                            .get(&mesh_renderer_data.mesh_id())
-                            .expect("No gpu mesh found for mesh id");
+                            // The mesh renderer's mesh or material may have been dropped before
+                            // its mesh instance was created, in which case there's nothing to draw.
+                            let gpu_mesh = engine.mesh_map.get(&mesh_renderer_data.mesh_id()).cloned();
+                            let material_id = match mesh_renderer_data.material_id() {
+                                Some(material_id) => engine.material_map.get(&material_id).cloned(),
+                                None => Some(engine.default_material_id),
+                            };
+                            let (gpu_mesh, material_id) = match (gpu_mesh, material_id) {
+                                (Some(gpu_mesh), Some(material_id)) => (gpu_mesh, material_id),
+                                _ => {
+                                    engine.missing_mesh_instances.push(id);
+                                    continue;
+                                },
+                            };
 
                             let mut mesh_instance = MeshInstance::with_shared_material(
                                 gpu_mesh,
-                                engine.default_material_id,
+                                material_id,
                             );
 
                             mesh_instance.set_anchor(anchor_id);
+                            mesh_instance.set_layer(mesh_renderer_data.layer());
+
+                            let mesh_instance_id = engine.renderer.register_mesh_instance(mesh_instance);
+                            id.init(mesh_instance_id);
+                        }
+                        EngineMessage::MeshInstanceMesh(id, mesh_id) => {
+                            let _s = Stopwatch::new("Mesh instance mesh message");
+                            let mesh_instance_id = match mesh_instance_id(engine, &id) {
+                                Some(mesh_instance_id) => mesh_instance_id,
+                                None => continue,
+                            };
+
+                            // A mesh that's already been dropped can't be drawn, so the mesh
+                            // instance keeps its current mesh.
+                            let gpu_mesh = match engine.mesh_map.get(&mesh_id) {
+                                Some(&gpu_mesh) => gpu_mesh,
+                                None => {
+                                    println!("WARNING: Mesh renderer was given a mesh that has been dropped: {:?}", mesh_id);
+                                    continue;
+                                },
+                            };
 
-                            let _ = engine.renderer.register_mesh_instance(mesh_instance);
+                            engine.renderer
+                                .get_mesh_instance_mut(mesh_instance_id)
+                                .expect("No such mesh instance")
+                                .set_mesh(gpu_mesh);
+                        }
+                        EngineMessage::MeshInstanceVisible(id, visible) => {
+                            let _s = Stopwatch::new("Mesh instance visible message");
+                            if let Some(mesh_instance_id) = mesh_instance_id(engine, &id) {
+                                engine.renderer
+                                    .get_mesh_instance_mut(mesh_instance_id)
+                                    .expect("No such mesh instance")
+                                    .set_visible(visible);
+                            }
+                        }
+                        EngineMessage::MeshInstanceCastsShadows(id, casts_shadows) => {
+                            let _s = Stopwatch::new("Mesh instance casts shadows message");
+                            if let Some(mesh_instance_id) = mesh_instance_id(engine, &id) {
+                                engine.renderer
+                                    .get_mesh_instance_mut(mesh_instance_id)
+                                    .expect("No such mesh instance")
+                                    .set_casts_shadows(casts_shadows);
+                            }
+                        }
+                        EngineMessage::RemoveMeshInstance(id) => {
+                            let _s = Stopwatch::new("Remove mesh instance message");
+                            match mesh_instance_id(engine, &id) {
+                                Some(mesh_instance_id) => engine.renderer.unregister_mesh_instance(mesh_instance_id),
+                                None => engine.missing_mesh_instances.retain(|missing| !Arc::ptr_eq(missing, &id)),
+                            }
+                        }
+                        EngineMessage::RenderTexture(texture_id, width, height) => {
+                            let _s = Stopwatch::new("Render texture message");
+                            let gpu_texture = engine.renderer.create_render_texture(width, height);
+                            let last = engine.texture_map.insert(texture_id, gpu_texture);
+                            assert!(last.is_none(), "Duplicate texture_id found: {:?}", texture_id);
+                        }
+                        EngineMessage::Texture(texture_id, texture) => {
+                            let _s = Stopwatch::new("Texture message");
+                            let gpu_texture = engine.renderer.register_texture(&texture);
+                            let last = engine.texture_map.insert(texture_id, gpu_texture);
+                            assert!(last.is_none(), "Duplicate texture_id found: {:?}", texture_id);
+                        }
+                        EngineMessage::RemoveTexture(texture_id) => {
+                            let _s = Stopwatch::new("Remove texture message");
+                            if let Some(gpu_texture) = engine.texture_map.remove(&texture_id) {
+                                engine.renderer.unregister_texture(gpu_texture);
+                            }
                         }
                         EngineMessage::Behavior(func) => {
                             let _s = Stopwatch::new("Behavior message");
                             engine.behaviors.push(func);
                         }
+                        EngineMessage::Screenshot(path) => {
+                            engine.screenshots.push(path);
+                        }
+                        EngineMessage::StartRecording(recording) => {
+                            match fs::create_dir_all(&recording.directory) {
+                                Ok(()) => engine.recording = Some(recording),
+                                Err(error) => println!(
+                                    "WARNING: Failed to create recording directory {:?}: {:?}",
+                                    recording.directory,
+                                    error),
+                            }
+                        }
+                        EngineMessage::StopRecording => {
+                            engine.recording = None;
+                        }
                     }
                 }
+
+                for message in deferred_messages {
+                    send_message(message);
+                }
             }
 
             // Update renderer's anchors with flattened scene graph.
             {
                 let _s = Stopwatch::new("Update renderer anchors");
 
-                for node in engine.scene_graph.roots() {
+                // Only nodes that were modified since the last frame need to be sent to the
+                // renderer, all other anchors are already up to date.
+                let mut updated_anchors = 0;
+                for node in engine.scene_graph.take_dirty() {
                     let node = node.borrow();
 
-                    // TODO: Do something like pre-sorting so we only try to update out of
-                    // date nodes.
                     if let Some(anchor_id) = node.anchor() {
                         // Send position/rotation/scale to renderer anchor.
                         let anchor = engine.renderer
@@ -411,22 +769,47 @@ fn main_loop(mut engine: Box<Engine>) {
                         anchor.set_position(node.position);
                         anchor.set_orientation(node.orientation);
                         anchor.set_scale(node.scale);
+
+                        updated_anchors += 1;
+                    } else {
+                        // The node's anchor hasn't been created yet, so keep it dirty until its
+                        // anchor is ready.
+                        node.inner.mark_dirty();
                     }
                 }
+
+                stopwatch::counter("Updated anchors", updated_anchors);
             }
 
-            // Update the camera.
-            if let Some((ref camera_data, ref camera_id)) = engine.camera {
+            // Update the cameras.
+            for &(ref camera_data, ref camera_id) in &engine.cameras {
                 let _s = Stopwatch::new("Update renderer camera");
 
+                // The renderer doesn't draw cameras whose target texture has been removed, so
+                // there's no need to update them.
+                let target = match camera_data.target() {
+                    Some(texture_id) => match engine.texture_map.get(&texture_id) {
+                        Some(&gpu_texture) => Some(gpu_texture),
+                        None => continue,
+                    },
+                    None => None,
+                };
+
                 let render_camera = engine.renderer
                     .get_camera_mut(*camera_id)
                     .expect("Camera didn't exist for camera id");
 
                 render_camera.set_fov(camera_data.fov());
                 render_camera.set_aspect(camera_data.aspect());
-                render_camera.set_near(camera_data.near());
-                render_camera.set_far(camera_data.far());
+                render_camera.set_clip_planes(camera_data.near(), camera_data.far());
+                render_camera.set_projection(camera_data.projection());
+                render_camera.set_size(camera_data.size());
+                render_camera.set_viewport(camera_data.viewport());
+                render_camera.set_depth(camera_data.depth());
+                render_camera.set_clear(camera_data.clear());
+                render_camera.set_layer_mask(camera_data.layer_mask());
+                render_camera.set_target(target);
+                render_camera.set_post_effects(camera_data.post_effects().map(|effects| effects.to_vec()));
             }
 
             // Update lights.
@@ -436,12 +819,29 @@ fn main_loop(mut engine: Box<Engine>) {
                 for light in &engine.lights {
                     let &(ref id, ref data) = &**light;
                     let light = engine.renderer.get_light_mut(*id.borrow()).expect("Renderer has no such light");
+
+                    // The anchor is only set on the renderer's copy of the light, so preserve it
+                    // when copying over the rest of the light's data.
+                    let anchor = light.anchor().cloned();
                     *light = data.borrow().clone();
+                    if let Some(anchor_id) = anchor {
+                        light.set_anchor(anchor_id);
+                    }
                 }
             }
 
-            // Draw.
+            // Draw, capturing the frame if it needs to be saved.
+            let recording_path = engine.recording.as_mut().and_then(Recording::next_frame);
+            let capture = !engine.screenshots.is_empty() || recording_path.is_some();
+            if capture {
+                engine.renderer.capture_next_frame();
+            }
+
             engine.renderer.draw();
+
+            if capture {
+                capture_frame(engine, recording_path);
+            }
         }
 
         frame_times.push(frame_start.elapsed());
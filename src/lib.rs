@@ -21,6 +21,7 @@ extern crate fiber;
 extern crate hash;
 #[macro_use]
 extern crate lazy_static;
+extern crate parse_bmp;
 extern crate parse_obj as obj;
 extern crate polygon;
 
@@ -30,6 +31,7 @@ pub extern crate stopwatch;
 #[macro_use]
 pub mod macros;
 
+pub mod animation;
 pub mod camera;
 pub mod collections;
 pub mod engine;
@@ -38,6 +40,7 @@ pub mod light;
 pub mod mesh_renderer;
 pub mod prelude;
 pub mod resource;
+pub mod scene;
 pub mod scheduler;
 pub mod time;
 pub mod transform;
@@ -5,10 +5,14 @@ use math::*;
 use polygon::light::*;
 use std::mem;
 use std::sync::Arc;
+use transform::Transform;
 
 // TODO: This shouldn't be fully public, only public within the crate.
 pub type LightInner = Arc<(AtomicInitCell<LightId>, AtomicRefCell<Light>)>;
 
+/// A light that illuminates the whole scene from a single direction, like the sun.
+///
+/// The light is removed from the scene when it's dropped.
 #[derive(Debug)]
 pub struct DirectionalLight {
     data: LightInner,
@@ -18,33 +22,263 @@ impl DirectionalLight {
     pub fn new(direction: Vector3, color: Color, strength: f32) -> DirectionalLight {
         let light = Light::directional(direction, strength, color);
         let data = Arc::new((AtomicInitCell::new(), AtomicRefCell::new(light)));
-        engine::send_message(EngineMessage::Light(data.clone()));
+        engine::send_message(EngineMessage::Light(data.clone(), None));
         DirectionalLight {
             data: data,
         }
     }
 
+    pub fn direction(&self) -> Vector3 {
+        match self.data.1.borrow().data {
+            LightData::Directional { direction } => direction,
+            _ => panic!("Directional light had non-directional light data"),
+        }
+    }
+
+    /// Sets the direction the light shines in. `direction` doesn't need to be normalized.
+    pub fn set_direction(&mut self, direction: Vector3) {
+        self.data.1.borrow_mut().data = LightData::Directional { direction: direction.normalized() };
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.1.borrow().color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.data.1.borrow_mut().color = color;
+    }
+
+    pub fn strength(&self) -> f32 {
+        self.data.1.borrow().strength
+    }
+
+    pub fn set_strength(&mut self, strength: f32) {
+        self.data.1.borrow_mut().strength = strength;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.data.1.borrow().enabled
+    }
+
+    /// Enables the light, making it illuminate the scene again after `disable()` was called.
+    pub fn enable(&mut self) {
+        self.data.1.borrow_mut().enabled = true;
+    }
+
+    /// Disables the light without removing it from the scene.
+    pub fn disable(&mut self) {
+        self.data.1.borrow_mut().enabled = false;
+    }
+
+    pub fn casts_shadows(&self) -> bool {
+        self.data.1.borrow().casts_shadows
+    }
+
+    /// Sets whether or not the light is blocked by mesh renderers. Lights don't cast shadows by
+    /// default.
+    pub fn set_casts_shadows(&mut self, casts_shadows: bool) {
+        self.data.1.borrow_mut().casts_shadows = casts_shadows;
+    }
+
     pub fn forget(self) {
         mem::forget(self);
     }
 }
 
+impl Drop for DirectionalLight {
+    fn drop(&mut self) {
+        engine::send_message(EngineMessage::RemoveLight(self.data.clone()));
+    }
+}
+
+/// A light that shines in all directions from a point, positioned by a transform.
+///
+/// The light is removed from the scene when it's dropped.
 #[derive(Debug)]
 pub struct PointLight {
     data: LightInner,
 }
 
 impl PointLight {
-    pub fn new(radius: f32, color: Color, strength: f32) -> PointLight {
+    pub fn new(transform: &Transform, radius: f32, color: Color, strength: f32) -> PointLight {
         let light = Light::point(radius, strength, color);
         let data = Arc::new((AtomicInitCell::new(), AtomicRefCell::new(light)));
-        engine::send_message(EngineMessage::Light(data.clone()));
+        engine::send_message(EngineMessage::Light(data.clone(), Some(transform.inner())));
         PointLight {
             data: data,
         }
     }
 
+    pub fn radius(&self) -> f32 {
+        match self.data.1.borrow().data {
+            LightData::Point { radius } => radius,
+            _ => panic!("Point light had non-point light data"),
+        }
+    }
+
+    pub fn set_radius(&mut self, radius: f32) {
+        self.data.1.borrow_mut().data = LightData::Point { radius: radius };
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.1.borrow().color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.data.1.borrow_mut().color = color;
+    }
+
+    pub fn strength(&self) -> f32 {
+        self.data.1.borrow().strength
+    }
+
+    pub fn set_strength(&mut self, strength: f32) {
+        self.data.1.borrow_mut().strength = strength;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.data.1.borrow().enabled
+    }
+
+    /// Enables the light, making it illuminate the scene again after `disable()` was called.
+    pub fn enable(&mut self) {
+        self.data.1.borrow_mut().enabled = true;
+    }
+
+    /// Disables the light without removing it from the scene.
+    pub fn disable(&mut self) {
+        self.data.1.borrow_mut().enabled = false;
+    }
+
+    pub fn forget(self) {
+        mem::forget(self);
+    }
+}
+
+impl Drop for PointLight {
+    fn drop(&mut self) {
+        engine::send_message(EngineMessage::RemoveLight(self.data.clone()));
+    }
+}
+
+/// A light that shines in a cone from a point, positioned and aimed by a transform.
+///
+/// The light shines along the transform's forward axis. It's at full strength within
+/// `inner_angle` of that axis and fades out towards `outer_angle`, both given in radians. The
+/// light is removed from the scene when it's dropped.
+#[derive(Debug)]
+pub struct SpotLight {
+    data: LightInner,
+}
+
+impl SpotLight {
+    pub fn new(
+        transform: &Transform,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+        color: Color,
+        strength: f32,
+    ) -> SpotLight {
+        let light = Light::spot(range, inner_angle, outer_angle, strength, color);
+        let data = Arc::new((AtomicInitCell::new(), AtomicRefCell::new(light)));
+        engine::send_message(EngineMessage::Light(data.clone(), Some(transform.inner())));
+        SpotLight {
+            data: data,
+        }
+    }
+
+    pub fn range(&self) -> f32 {
+        match self.data.1.borrow().data {
+            LightData::Spot { range, .. } => range,
+            _ => panic!("Spot light had non-spot light data"),
+        }
+    }
+
+    pub fn set_range(&mut self, range: f32) {
+        match self.data.1.borrow_mut().data {
+            LightData::Spot { range: ref mut light_range, .. } => *light_range = range,
+            _ => panic!("Spot light had non-spot light data"),
+        }
+    }
+
+    /// Gets the inner and outer cone angles of the light, in radians.
+    pub fn angles(&self) -> (f32, f32) {
+        match self.data.1.borrow().data {
+            LightData::Spot { inner_angle, outer_angle, .. } => (inner_angle, outer_angle),
+            _ => panic!("Spot light had non-spot light data"),
+        }
+    }
+
+    /// Sets the inner and outer cone angles of the light, in radians.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `inner_angle` is greater than `outer_angle`.
+    pub fn set_angles(&mut self, inner_angle: f32, outer_angle: f32) {
+        assert!(
+            inner_angle <= outer_angle,
+            "Inner angle must not be greater than outer angle, inner: {}, outer: {}",
+            inner_angle,
+            outer_angle
+        );
+
+        match self.data.1.borrow_mut().data {
+            LightData::Spot { inner_angle: ref mut light_inner, outer_angle: ref mut light_outer, .. } => {
+                *light_inner = inner_angle;
+                *light_outer = outer_angle;
+            },
+            _ => panic!("Spot light had non-spot light data"),
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        self.data.1.borrow().color
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.data.1.borrow_mut().color = color;
+    }
+
+    pub fn strength(&self) -> f32 {
+        self.data.1.borrow().strength
+    }
+
+    pub fn set_strength(&mut self, strength: f32) {
+        self.data.1.borrow_mut().strength = strength;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.data.1.borrow().enabled
+    }
+
+    /// Enables the light, making it illuminate the scene again after `disable()` was called.
+    pub fn enable(&mut self) {
+        self.data.1.borrow_mut().enabled = true;
+    }
+
+    /// Disables the light without removing it from the scene.
+    pub fn disable(&mut self) {
+        self.data.1.borrow_mut().enabled = false;
+    }
+
+    pub fn casts_shadows(&self) -> bool {
+        self.data.1.borrow().casts_shadows
+    }
+
+    /// Sets whether or not the light is blocked by mesh renderers. Lights don't cast shadows by
+    /// default.
+    pub fn set_casts_shadows(&mut self, casts_shadows: bool) {
+        self.data.1.borrow_mut().casts_shadows = casts_shadows;
+    }
+
     pub fn forget(self) {
         mem::forget(self);
     }
 }
+
+impl Drop for SpotLight {
+    fn drop(&mut self) {
+        engine::send_message(EngineMessage::RemoveLight(self.data.clone()));
+    }
+}
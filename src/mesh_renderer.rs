@@ -1,43 +1,162 @@
+use cell_extras::atomic_init_cell::AtomicInitCell;
 use engine::{self, EngineMessage};
-use resource::{Mesh, MeshId};
+use math::Color;
+use polygon::mesh_instance::MeshInstanceId;
+use resource::{Material, MaterialId, Mesh, MeshId, Texture, TextureId};
 use transform::Transform;
-use std::marker::PhantomData;
 use std::mem;
+use std::sync::Arc;
 
+// TODO: This shouldn't be fully public, only public within the crate.
+pub type MeshInstanceIdHandle = Arc<AtomicInitCell<MeshInstanceId>>;
+
+/// Draws a mesh at the position of a transform.
+///
+/// The mesh is removed from the scene when the mesh renderer is dropped.
 #[derive(Debug)]
 pub struct MeshRenderer {
-    data: *mut MeshRendererData,
-    _phantom: PhantomData<MeshRendererData>,
+    id: MeshInstanceIdHandle,
+    visible: bool,
+    casts_shadows: bool,
 }
 
 impl MeshRenderer {
     pub fn new(mesh: &Mesh, transform: &Transform) -> MeshRenderer {
-        let mut data = Box::new(MeshRendererData {
+        MeshRenderer::with_layer(mesh, transform, 0)
+    }
+
+    /// Creates a new mesh renderer in the specified layer.
+    ///
+    /// Cameras only render mesh renderers in layers that are part of their layer mask, see
+    /// `CameraData::set_layer_mask()`. `layer` must be less than 32.
+    pub fn with_layer(mesh: &Mesh, transform: &Transform, layer: u32) -> MeshRenderer {
+        MeshRenderer::create(mesh, None, transform, layer)
+    }
+
+    /// Creates a new mesh renderer that draws the mesh using the specified material.
+    ///
+    /// The material is shared with all other mesh renderers using it until one of its properties
+    /// is overridden with `set_color()`, `set_f32()`, or `set_texture()`.
+    pub fn with_material(mesh: &Mesh, material: &Material, transform: &Transform) -> MeshRenderer {
+        MeshRenderer::create(mesh, Some(material.id()), transform, 0)
+    }
+
+    /// Overrides a color property of the mesh renderer's material.
+    ///
+    /// The override only affects this mesh renderer, other mesh renderers using the same
+    /// material are unchanged.
+    pub fn set_color<S: Into<String>>(&mut self, name: S, color: Color) {
+        self.set_property(name.into(), MaterialProperty::Color(color));
+    }
+
+    /// Overrides an `f32` property of the mesh renderer's material.
+    ///
+    /// The override only affects this mesh renderer, other mesh renderers using the same
+    /// material are unchanged.
+    pub fn set_f32<S: Into<String>>(&mut self, name: S, value: f32) {
+        self.set_property(name.into(), MaterialProperty::f32(value));
+    }
+
+    /// Overrides a texture property of the mesh renderer's material.
+    ///
+    /// The override only affects this mesh renderer, other mesh renderers using the same
+    /// material are unchanged.
+    pub fn set_texture<S: Into<String>>(&mut self, name: S, texture: &Texture) {
+        self.set_property(name.into(), MaterialProperty::Texture(texture.id()));
+    }
+
+    /// Gets whether or not the mesh renderer is drawn.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the mesh renderer without removing it from the scene.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        engine::send_message(EngineMessage::MeshInstanceVisible(self.id.clone(), visible));
+    }
+
+    /// Gets whether or not the mesh renderer blocks light from shadow casting lights.
+    pub fn casts_shadows(&self) -> bool {
+        self.casts_shadows
+    }
+
+    /// Sets whether or not the mesh renderer blocks light from shadow casting lights. Mesh
+    /// renderers cast shadows by default.
+    pub fn set_casts_shadows(&mut self, casts_shadows: bool) {
+        self.casts_shadows = casts_shadows;
+        engine::send_message(EngineMessage::MeshInstanceCastsShadows(self.id.clone(), casts_shadows));
+    }
+
+    /// Changes the mesh that the mesh renderer draws.
+    ///
+    /// The mesh renderer keeps its current material, including any overridden properties.
+    pub fn set_mesh(&mut self, mesh: &Mesh) {
+        engine::send_message(EngineMessage::MeshInstanceMesh(self.id.clone(), mesh.id()));
+    }
+
+    pub fn forget(self) {
+        mem::forget(self);
+    }
+
+    fn create(
+        mesh: &Mesh,
+        material_id: Option<MaterialId>,
+        transform: &Transform,
+        layer: u32,
+    ) -> MeshRenderer {
+        assert!(layer < 32, "Layer must be less than 32: {}", layer);
+
+        let data = Box::new(MeshRendererData {
             mesh_id: mesh.id(),
+            material_id: material_id,
+            layer: layer,
         });
 
-        let ptr = &mut *data as *mut _;
+        let id = Arc::new(AtomicInitCell::new());
 
-        engine::send_message(EngineMessage::MeshInstance(data, transform.inner()));
+        engine::send_message(EngineMessage::MeshInstance(data, id.clone(), transform.inner()));
 
         MeshRenderer {
-            data: ptr,
-            _phantom: PhantomData,
+            id: id,
+            visible: true,
+            casts_shadows: true,
         }
     }
 
-    pub fn forget(self) {
-        mem::forget(self);
+    fn set_property(&mut self, name: String, property: MaterialProperty) {
+        engine::send_message(EngineMessage::MaterialProperty(self.id.clone(), name, property));
     }
 }
 
-unsafe impl Send for MeshRenderer {}
+impl Drop for MeshRenderer {
+    fn drop(&mut self) {
+        engine::send_message(EngineMessage::RemoveMeshInstance(self.id.clone()));
+    }
+}
 
 #[derive(Debug)]
 pub struct MeshRendererData {
-    mesh_id: MeshId
+    mesh_id: MeshId,
+    material_id: Option<MaterialId>,
+    layer: u32,
 }
 
 impl MeshRendererData {
     pub fn mesh_id(&self) -> MeshId { self.mesh_id }
+
+    /// Gets the material used by the mesh renderer, or `None` if it uses the default material.
+    pub fn material_id(&self) -> Option<MaterialId> { self.material_id }
+
+    pub fn layer(&self) -> u32 { self.layer }
+}
+
+/// A material property value overridden by a single mesh renderer.
+// TODO: This shouldn't be fully public, only public within the crate.
+#[derive(Debug, Clone)]
+#[allow(bad_style)]
+pub enum MaterialProperty {
+    Color(Color),
+    f32(f32),
+    Texture(TextureId),
 }
@@ -1,12 +1,18 @@
 use engine::{self, EngineMessage};
 use scheduler::{self, Async};
 use polygon::geometry::mesh::{BuildMeshError, MeshBuilder};
+pub use polygon::geometry::primitive::Primitive;
+use polygon::material::MaterialId as PolygonMaterialId;
 use polygon::math::Vector2;
+use polygon::post_process::PostEffect;
+use polygon::texture::Texture2d;
 use obj::{self, Obj};
+use parse_bmp::{self, Bitmap};
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
 use std::path::Path;
+use std::mem;
 use std::string::FromUtf8Error;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use stopwatch::Stopwatch;
@@ -15,6 +21,11 @@ pub mod collada;
 
 static MESH_ID_COUNTER: AtomicUsize = AtomicUsize::new(1);
 static MATERIAL_ID_COUNTER: AtomicUsize = AtomicUsize::new(1);
+static TEXTURE_ID_COUNTER: AtomicUsize = AtomicUsize::new(1);
+
+/// The largest angle in radians between faces that still get smooth normals when generating
+/// normals for a mesh that doesn't have them.
+const SMOOTHING_ANGLE: f32 = 1.0;
 
 /// Load all data from the specified file as an array of bytes.
 pub fn load_file_bytes<'a, P>(path: P) -> Async<'a, Result<Vec<u8>, io::Error>>
@@ -81,6 +92,7 @@ pub fn load_mesh<'a, P>(path: P) -> Async<'a, Result<Mesh, LoadMeshError>>
 {
     scheduler::start(move || {
         let _s = Stopwatch::new("Load mesh");
+        let path_string = path.as_ref().to_string_lossy().into_owned();
         let extension: Option<String> = path.as_ref().extension().map(|ext| ext.to_string_lossy().into_owned());
 
         // Load mesh source and parse mesh data based on file type.
@@ -123,12 +135,18 @@ pub fn load_mesh<'a, P>(path: P) -> Async<'a, Result<Mesh, LoadMeshError>>
                 let indices_count = obj.position_indices().len() as u32 * 3;
                 let indices: Vec<u32> = (0..indices_count).collect();
 
-                MeshBuilder::new()
+                let mut builder = MeshBuilder::new()
                     .set_position_data(&*positions)
                     .set_normal_data(&*normals)
                     .set_texcoord_data(&*texcoords)
-                    .set_indices(&*indices)
-                    .build()?
+                    .set_indices(&*indices);
+
+                // Meshes without normals can't be lit, so generate normals for them.
+                if normals.len() == 0 {
+                    builder = builder.generate_smooth_normals(SMOOTHING_ANGLE);
+                }
+
+                builder.build()?
             },
             _ => {
                 return Err(LoadMeshError::UnsupportedFileType(path.into()));
@@ -140,25 +158,60 @@ pub fn load_mesh<'a, P>(path: P) -> Async<'a, Result<Mesh, LoadMeshError>>
 
         engine::send_message(EngineMessage::Mesh(mesh_id, mesh_data));
 
-        Ok(Mesh(mesh_id))
+        Ok(Mesh {
+            id: mesh_id,
+            path: Some(path_string),
+        })
     })
 }
 
+/// Generates a mesh for one of the built-in primitive shapes.
+///
+/// Unlike `load_mesh()` there's nothing to load from disk, so the mesh data is generated
+/// immediately and the handle is returned directly.
+pub fn primitive(primitive: Primitive) -> Mesh {
+    let mesh_data = primitive.build();
+
+    let mesh_id = MESH_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+    engine::send_message(EngineMessage::Mesh(mesh_id, mesh_data));
+
+    Mesh {
+        id: mesh_id,
+        path: None,
+    }
+}
+
 pub type MeshId = usize;
 
+/// A mesh loaded with `load_mesh()` or generated with `primitive()`.
+///
+/// The mesh data is removed from the renderer when the mesh is dropped, and any mesh renderers
+/// still using it stop being drawn.
 #[derive(Debug)]
-pub struct Mesh(MeshId);
+pub struct Mesh {
+    id: MeshId,
+    path: Option<String>,
+}
 
 impl Mesh {
     // TODO: Make this private to the crate.
     pub fn id(&self) -> MeshId {
-        self.0
+        self.id
+    }
+
+    /// Gets the path the mesh was loaded from, if it was loaded from a file.
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_ref().map(|path| &**path)
+    }
+
+    pub fn forget(self) {
+        mem::forget(self);
     }
 }
 
 impl Drop for Mesh {
     fn drop(&mut self) {
-        // TODO: How do we cleanup a mesh?
+        engine::send_message(EngineMessage::RemoveMesh(self.id));
     }
 }
 
@@ -208,16 +261,54 @@ pub fn load_material<'a, P>(path: P) -> Async<'a, Result<Material, LoadMaterialE
 
         let material_id = MATERIAL_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
 
-        engine::send_message(EngineMessage::Material(material_id, material_source));
+        // The material is built on the engine thread, wait for it to be built so that build
+        // failures can be reported.
+        let (promise, built) = scheduler::promise();
+        engine::send_message(EngineMessage::Material(material_id, material_source, promise));
+        let gpu_material = built.await()?;
 
-        Ok(Material(material_id))
+        Ok(Material {
+            id: material_id,
+            gpu_material: gpu_material,
+        })
     })
 }
 
 pub type MaterialId = usize;
 
+/// A material loaded with `load_material()`.
+///
+/// The material is removed from the renderer when it's dropped, and any mesh renderers or post
+/// effects still using it stop being drawn.
 #[derive(Debug)]
-pub struct Material(MaterialId);
+pub struct Material {
+    id: MaterialId,
+    gpu_material: PolygonMaterialId,
+}
+
+impl Material {
+    // TODO: Make this private to the crate.
+    pub fn id(&self) -> MaterialId {
+        self.id
+    }
+
+    pub fn forget(self) {
+        mem::forget(self);
+    }
+}
+
+impl Drop for Material {
+    fn drop(&mut self) {
+        engine::send_message(EngineMessage::RemoveMaterial(self.id));
+    }
+}
+
+impl<'a> From<&'a Material> for PostEffect {
+    /// Creates a custom post effect that draws with `material`.
+    fn from(material: &'a Material) -> PostEffect {
+        PostEffect::Custom(material.gpu_material)
+    }
+}
 
 #[derive(Debug)]
 pub enum LoadMaterialError {
@@ -243,3 +334,79 @@ impl From<::polygon::BuildMaterialError> for LoadMaterialError {
         LoadMaterialError::BuildMaterialError(from)
     }
 }
+
+/// Loads a texture from disk.
+///
+/// Only bitmap files are supported currently.
+pub fn load_texture<'a, P>(path: P) -> Async<'a, Result<Texture, LoadTextureError>>
+    where
+    P: 'a,
+    P: AsRef<Path> + Send
+{
+    scheduler::start(move || {
+        let _s = Stopwatch::new("Load texture");
+        let bytes = load_file_bytes(path).await()?;
+        let bitmap = Bitmap::from_bytes(&*bytes)?;
+        let texture = Texture2d::from_bitmap(bitmap);
+
+        let texture_id = TEXTURE_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        engine::send_message(EngineMessage::Texture(texture_id, texture));
+
+        Ok(Texture(texture_id))
+    })
+}
+
+/// Creates an empty texture that cameras can render into.
+///
+/// See `CameraData::set_target()` for rendering a camera into the texture. The texture can be
+/// used by materials like any other texture, e.g. to show a security camera's view on a monitor.
+pub fn create_render_texture(width: usize, height: usize) -> Texture {
+    let texture_id = TEXTURE_ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+    engine::send_message(EngineMessage::RenderTexture(texture_id, width, height));
+    Texture(texture_id)
+}
+
+pub type TextureId = usize;
+
+/// A texture loaded with `load_texture()` or created with `create_render_texture()`.
+///
+/// The texture is removed from the renderer when it's dropped. Materials still using it behave
+/// as if the texture had never been set, and cameras rendering into it stop being drawn.
+#[derive(Debug)]
+pub struct Texture(TextureId);
+
+impl Texture {
+    // TODO: Make this private to the crate.
+    pub fn id(&self) -> TextureId {
+        self.0
+    }
+
+    pub fn forget(self) {
+        mem::forget(self);
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        engine::send_message(EngineMessage::RemoveTexture(self.0));
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadTextureError {
+    Io(io::Error),
+    ParseBitmapError(parse_bmp::Error),
+}
+
+impl From<io::Error> for LoadTextureError {
+    fn from(from: io::Error) -> LoadTextureError {
+        LoadTextureError::Io(from)
+    }
+}
+
+impl From<parse_bmp::Error> for LoadTextureError {
+    fn from(from: parse_bmp::Error) -> LoadTextureError {
+        LoadTextureError::ParseBitmapError(from)
+    }
+}
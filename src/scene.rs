@@ -0,0 +1,751 @@
+//! Saving and loading scenes to and from a human-readable text format.
+//!
+//! A scene file is a list of nodes, each of which has a transform and optionally a mesh, a
+//! camera, and a light attached to it:
+//!
+//! ```text
+//! # Comments start with a '#'.
+//! [node camera]
+//! position = 0 0 10
+//! orientation = 0 0 0 1
+//! scale = 1 1 1
+//! camera.fov = 1.0471976
+//! camera.aspect = 1
+//! camera.near = 0.001
+//! camera.far = 1000
+//! # Optional, cameras default to a perspective projection.
+//! camera.projection = orthographic
+//! camera.size = 5
+//!
+//! [node head]
+//! position = 3 0 0
+//! mesh = lib/polygon_rs/resources/meshes/epps_head.obj
+//!
+//! [node sun]
+//! light = directional
+//! light.direction = 0.57735026 -0.57735026 -0.57735026
+//! light.color = 1 1 1 1
+//! light.strength = 0.25
+//!
+//! [node flashlight]
+//! position = 0 2 0
+//! light = spot
+//! light.range = 10
+//! light.inner_angle = 0.3
+//! light.outer_angle = 0.5
+//! ```
+//!
+//! Orientations are written as quaternions in `x y z w` order. Any property that is omitted uses
+//! the same default as the corresponding constructor (e.g. `Transform::new()`).
+//!
+//! Transforms don't yet support parenting, so scenes are a flat list of nodes.
+//!
+//! # Saving
+//!
+//! Build a `SceneData` from live objects with `SceneData::add_node()` and write it out with
+//! `SceneData::save()`. Only meshes that were loaded from a file can be saved, since the mesh is
+//! stored in the scene as its path.
+//!
+//! # Loading
+//!
+//! `load_scene()` asynchronously loads a scene file and recreates all of its objects, loading
+//! meshes with `resource::load_mesh()`. The returned `Scene` owns the created objects. Scene
+//! text can also be parsed into a `SceneData` directly with `str::parse()`.
+
+use camera::{Camera, CameraData, Projection};
+use light::{DirectionalLight, PointLight, SpotLight};
+use math::*;
+use math::quaternion::Quaternion;
+use mesh_renderer::MeshRenderer;
+use resource::{self, LoadMeshError, LoadTextError, Mesh};
+use scheduler::{self, Async};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::str::FromStr;
+use stopwatch::Stopwatch;
+use transform::Transform;
+
+/// The serializable contents of a scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneData {
+    pub nodes: Vec<NodeData>,
+}
+
+impl SceneData {
+    /// Creates a new scene with no nodes.
+    pub fn new() -> SceneData {
+        SceneData {
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Adds a node to the scene that captures the current state of `transform`.
+    ///
+    /// Use the returned `NodeData` to capture any objects attached to the transform.
+    pub fn add_node<S: Into<String>>(&mut self, name: S, transform: &Transform) -> &mut NodeData {
+        self.nodes.push(NodeData {
+            name: name.into(),
+            position: transform.position(),
+            orientation: transform.orientation(),
+            scale: transform.scale(),
+            mesh: None,
+            camera: None,
+            light: None,
+        });
+
+        self.nodes.last_mut().unwrap()
+    }
+
+    /// Writes the scene to the specified file, replacing it if it already exists.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_string().as_bytes())
+    }
+}
+
+impl FromStr for SceneData {
+    type Err = ParseSceneError;
+
+    /// Parses a scene from its text representation.
+    fn from_str(source: &str) -> Result<SceneData, ParseSceneError> {
+        let mut nodes = Vec::new();
+        let mut current: Option<NodeBuilder> = None;
+
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // Start a new node on a `[node <name>]` header.
+            if line.starts_with('[') {
+                if !line.ends_with(']') || !line[1..].starts_with("node ") {
+                    return Err(ParseSceneError::MalformedLine { line: line_number });
+                }
+
+                if let Some(builder) = current.take() {
+                    nodes.push(builder.build());
+                }
+
+                let name = line[1 .. line.len() - 1]["node ".len() ..].trim();
+                current = Some(NodeBuilder::new(name.into()));
+                continue;
+            }
+
+            // Otherwise the line must be a `key = value` property.
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key.trim(), value.trim()),
+                _ => return Err(ParseSceneError::MalformedLine { line: line_number }),
+            };
+
+            match current {
+                Some(ref mut builder) => builder.set_property(key, value, line_number)?,
+                None => return Err(ParseSceneError::PropertyOutsideNode { line: line_number }),
+            }
+        }
+
+        if let Some(builder) = current.take() {
+            nodes.push(builder.build());
+        }
+
+        Ok(SceneData {
+            nodes: nodes,
+        })
+    }
+}
+
+impl Display for SceneData {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        for (index, node) in self.nodes.iter().enumerate() {
+            if index > 0 {
+                writeln!(formatter, "")?;
+            }
+
+            write!(formatter, "{}", node)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single node in a scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeData {
+    pub name: String,
+
+    pub position: Point,
+    pub orientation: Orientation,
+    pub scale: Vector3,
+
+    /// The path to the mesh file rendered at the node.
+    pub mesh: Option<String>,
+    pub camera: Option<CameraNodeData>,
+    pub light: Option<LightNodeData>,
+}
+
+impl NodeData {
+    /// Captures a mesh renderer displaying `mesh` at the node.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mesh` wasn't loaded from a file.
+    pub fn mesh(&mut self, mesh: &Mesh) -> &mut NodeData {
+        let path = mesh.path().expect("Cannot save a mesh that wasn't loaded from a file");
+        self.mesh = Some(path.into());
+        self
+    }
+
+    /// Captures the current settings of `camera`.
+    pub fn camera(&mut self, camera: &Camera) -> &mut NodeData {
+        self.camera = Some(CameraNodeData {
+            fov: camera.fov(),
+            aspect: camera.aspect(),
+            near: camera.near(),
+            far: camera.far(),
+            projection: camera.projection(),
+            size: camera.size(),
+        });
+        self
+    }
+
+    /// Captures the current settings of a directional light.
+    pub fn directional_light(&mut self, light: &DirectionalLight) -> &mut NodeData {
+        self.light = Some(LightNodeData::Directional {
+            direction: light.direction(),
+            color: light.color(),
+            strength: light.strength(),
+        });
+        self
+    }
+
+    /// Captures the current settings of a point light.
+    pub fn point_light(&mut self, light: &PointLight) -> &mut NodeData {
+        self.light = Some(LightNodeData::Point {
+            radius: light.radius(),
+            color: light.color(),
+            strength: light.strength(),
+        });
+        self
+    }
+
+    /// Captures the current settings of a spot light.
+    pub fn spot_light(&mut self, light: &SpotLight) -> &mut NodeData {
+        let (inner_angle, outer_angle) = light.angles();
+        self.light = Some(LightNodeData::Spot {
+            range: light.range(),
+            inner_angle: inner_angle,
+            outer_angle: outer_angle,
+            color: light.color(),
+            strength: light.strength(),
+        });
+        self
+    }
+}
+
+impl Display for NodeData {
+    fn fmt(&self, formatter: &mut Formatter) -> Result<(), fmt::Error> {
+        let quat = self.orientation.0;
+
+        writeln!(formatter, "[node {}]", self.name)?;
+        writeln!(formatter, "position = {} {} {}", self.position.x, self.position.y, self.position.z)?;
+        writeln!(formatter, "orientation = {} {} {} {}", quat.v.x, quat.v.y, quat.v.z, quat.w)?;
+        writeln!(formatter, "scale = {} {} {}", self.scale.x, self.scale.y, self.scale.z)?;
+
+        if let Some(ref mesh) = self.mesh {
+            writeln!(formatter, "mesh = {}", mesh)?;
+        }
+
+        if let Some(ref camera) = self.camera {
+            writeln!(formatter, "camera.fov = {}", camera.fov)?;
+            writeln!(formatter, "camera.aspect = {}", camera.aspect)?;
+            writeln!(formatter, "camera.near = {}", camera.near)?;
+            writeln!(formatter, "camera.far = {}", camera.far)?;
+
+            if camera.projection == Projection::Orthographic {
+                writeln!(formatter, "camera.projection = orthographic")?;
+                writeln!(formatter, "camera.size = {}", camera.size)?;
+            }
+        }
+
+        match self.light {
+            Some(LightNodeData::Directional { direction, color, strength }) => {
+                writeln!(formatter, "light = directional")?;
+                writeln!(formatter, "light.direction = {} {} {}", direction.x, direction.y, direction.z)?;
+                writeln!(formatter, "light.color = {} {} {} {}", color.r, color.g, color.b, color.a)?;
+                writeln!(formatter, "light.strength = {}", strength)?;
+            },
+            Some(LightNodeData::Point { radius, color, strength }) => {
+                writeln!(formatter, "light = point")?;
+                writeln!(formatter, "light.radius = {}", radius)?;
+                writeln!(formatter, "light.color = {} {} {} {}", color.r, color.g, color.b, color.a)?;
+                writeln!(formatter, "light.strength = {}", strength)?;
+            },
+            Some(LightNodeData::Spot { range, inner_angle, outer_angle, color, strength }) => {
+                writeln!(formatter, "light = spot")?;
+                writeln!(formatter, "light.range = {}", range)?;
+                writeln!(formatter, "light.inner_angle = {}", inner_angle)?;
+                writeln!(formatter, "light.outer_angle = {}", outer_angle)?;
+                writeln!(formatter, "light.color = {} {} {} {}", color.r, color.g, color.b, color.a)?;
+                writeln!(formatter, "light.strength = {}", strength)?;
+            },
+            None => {},
+        }
+
+        Ok(())
+    }
+}
+
+/// The settings for a camera in a scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraNodeData {
+    pub fov: f32,
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+    pub projection: Projection,
+    pub size: f32,
+}
+
+impl Default for CameraNodeData {
+    fn default() -> CameraNodeData {
+        let data = CameraData::default();
+        CameraNodeData {
+            fov: data.fov(),
+            aspect: data.aspect(),
+            near: data.near(),
+            far: data.far(),
+            projection: data.projection(),
+            size: data.size(),
+        }
+    }
+}
+
+/// The settings for a light in a scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightNodeData {
+    Directional {
+        direction: Vector3,
+        color: Color,
+        strength: f32,
+    },
+    Point {
+        radius: f32,
+        color: Color,
+        strength: f32,
+    },
+    Spot {
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+        color: Color,
+        strength: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSceneError {
+    /// Indicates that a line was not a node header, a property, a comment, or blank.
+    MalformedLine { line: usize },
+
+    /// Indicates that a property appeared before the first node header.
+    PropertyOutsideNode { line: usize },
+
+    /// Indicates that a property name was not recognized.
+    UnknownProperty { line: usize, key: String },
+
+    /// Indicates that a property's value could not be parsed.
+    InvalidValue { line: usize, key: String },
+
+    /// Indicates that the `light` property named an unsupported type of light.
+    UnknownLightType { line: usize, light_type: String },
+
+    /// Indicates that a property was set for a light that doesn't have it, or that light
+    /// properties were set without specifying the type of light with the `light` property.
+    LightPropertyMismatch { line: usize, key: String },
+}
+
+/// A scene that has been loaded and instantiated.
+///
+/// The scene owns all of the objects created when loading it.
+#[derive(Debug)]
+pub struct Scene {
+    pub nodes: Vec<Node>,
+    pub meshes: HashMap<String, Mesh>,
+}
+
+impl Scene {
+    /// Finds the first node with the specified name.
+    pub fn node(&self, name: &str) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.name == name)
+    }
+
+    /// Finds the first node with the specified name.
+    pub fn node_mut(&mut self, name: &str) -> Option<&mut Node> {
+        self.nodes.iter_mut().find(|node| node.name == name)
+    }
+}
+
+/// A node that was created when loading a scene.
+#[derive(Debug)]
+pub struct Node {
+    pub name: String,
+    pub transform: Transform,
+    pub mesh_renderer: Option<MeshRenderer>,
+    pub camera: Option<Camera>,
+    pub directional_light: Option<DirectionalLight>,
+    pub point_light: Option<PointLight>,
+    pub spot_light: Option<SpotLight>,
+}
+
+/// Loads a scene file and creates all of the objects in it.
+///
+/// All meshes in the scene are loaded concurrently, and each mesh file is only loaded once even
+/// if it's used by multiple nodes.
+pub fn load_scene<'a, P>(path: P) -> Async<'a, Result<Scene, LoadSceneError>>
+    where
+    P: 'a,
+    P: AsRef<Path> + Send,
+{
+    scheduler::start(move || {
+        let _s = Stopwatch::new("Load scene");
+        let text = resource::load_file_text(path).await()?;
+        let scene_data = text.parse::<SceneData>()?;
+
+        // Start loading all meshes before awaiting any of them.
+        let mut pending_meshes = HashMap::new();
+        for node in &scene_data.nodes {
+            if let Some(ref mesh_path) = node.mesh {
+                if !pending_meshes.contains_key(mesh_path) {
+                    pending_meshes.insert(mesh_path.clone(), resource::load_mesh(mesh_path.clone()));
+                }
+            }
+        }
+
+        let mut meshes = HashMap::with_capacity(pending_meshes.len());
+        for (mesh_path, async_mesh) in pending_meshes {
+            meshes.insert(mesh_path, async_mesh.await()?);
+        }
+
+        let nodes = scene_data.nodes
+            .into_iter()
+            .map(|node_data| {
+                let mut transform = Transform::new();
+                transform.set_position(node_data.position);
+                transform.set_orientation(node_data.orientation);
+                transform.set_scale(node_data.scale);
+
+                let mesh_renderer = node_data.mesh.as_ref().map(|mesh_path| {
+                    MeshRenderer::new(&meshes[mesh_path], &transform)
+                });
+
+                let camera = node_data.camera.map(|camera| {
+                    let mut data = CameraData::new(camera.fov, camera.aspect, camera.near, camera.far);
+                    data.set_projection(camera.projection);
+                    data.set_size(camera.size);
+                    Camera::with_data(&transform, data)
+                });
+
+                let mut directional_light = None;
+                let mut point_light = None;
+                let mut spot_light = None;
+                match node_data.light {
+                    Some(LightNodeData::Directional { direction, color, strength }) => {
+                        directional_light = Some(DirectionalLight::new(direction, color, strength));
+                    },
+                    Some(LightNodeData::Point { radius, color, strength }) => {
+                        point_light = Some(PointLight::new(&transform, radius, color, strength));
+                    },
+                    Some(LightNodeData::Spot { range, inner_angle, outer_angle, color, strength }) => {
+                        spot_light = Some(SpotLight::new(&transform, range, inner_angle, outer_angle, color, strength));
+                    },
+                    None => {},
+                }
+
+                Node {
+                    name: node_data.name,
+                    transform: transform,
+                    mesh_renderer: mesh_renderer,
+                    camera: camera,
+                    directional_light: directional_light,
+                    point_light: point_light,
+                    spot_light: spot_light,
+                }
+            })
+            .collect();
+
+        Ok(Scene {
+            nodes: nodes,
+            meshes: meshes,
+        })
+    })
+}
+
+#[derive(Debug)]
+pub enum LoadSceneError {
+    LoadMeshError(LoadMeshError),
+    LoadTextError(LoadTextError),
+    ParseSceneError(ParseSceneError),
+}
+
+impl From<LoadMeshError> for LoadSceneError {
+    fn from(from: LoadMeshError) -> LoadSceneError {
+        LoadSceneError::LoadMeshError(from)
+    }
+}
+
+impl From<LoadTextError> for LoadSceneError {
+    fn from(from: LoadTextError) -> LoadSceneError {
+        LoadSceneError::LoadTextError(from)
+    }
+}
+
+impl From<ParseSceneError> for LoadSceneError {
+    fn from(from: ParseSceneError) -> LoadSceneError {
+        LoadSceneError::ParseSceneError(from)
+    }
+}
+
+/// The type of light being parsed, along with the light's properties as they're parsed.
+#[derive(Debug)]
+enum LightBuilder {
+    Directional { direction: Vector3, color: Color, strength: f32 },
+    Point { radius: f32, color: Color, strength: f32 },
+    Spot { range: f32, inner_angle: f32, outer_angle: f32, color: Color, strength: f32 },
+}
+
+/// Accumulates the properties of a node as it's being parsed.
+#[derive(Debug)]
+struct NodeBuilder {
+    name: String,
+    position: Point,
+    orientation: Orientation,
+    scale: Vector3,
+    mesh: Option<String>,
+    camera: Option<CameraNodeData>,
+    light: Option<LightBuilder>,
+}
+
+impl NodeBuilder {
+    fn new(name: String) -> NodeBuilder {
+        NodeBuilder {
+            name: name,
+            position: Point::origin(),
+            orientation: Orientation::new(),
+            scale: Vector3::one(),
+            mesh: None,
+            camera: None,
+            light: None,
+        }
+    }
+
+    fn set_property(&mut self, key: &str, value: &str, line: usize) -> Result<(), ParseSceneError> {
+        let invalid = || ParseSceneError::InvalidValue { line: line, key: key.into() };
+        let mismatch = || ParseSceneError::LightPropertyMismatch { line: line, key: key.into() };
+
+        match key {
+            "position" => {
+                let xyz = parse_floats::<[f32; 3]>(value).ok_or_else(&invalid)?;
+                self.position = Point::new(xyz[0], xyz[1], xyz[2]);
+            },
+            "orientation" => {
+                let xyzw = parse_floats::<[f32; 4]>(value).ok_or_else(&invalid)?;
+                let quat = Quaternion::new(Vector3::new(xyzw[0], xyzw[1], xyzw[2]), xyzw[3]);
+                if quat.is_zero() {
+                    return Err(invalid());
+                }
+
+                // Only normalize when necessary so that saved orientations load back exactly.
+                self.orientation = if quat.is_normalized() {
+                    Orientation(quat)
+                } else {
+                    Orientation(quat.normalized())
+                };
+            },
+            "scale" => {
+                let xyz = parse_floats::<[f32; 3]>(value).ok_or_else(&invalid)?;
+                self.scale = Vector3::new(xyz[0], xyz[1], xyz[2]);
+            },
+            "mesh" => {
+                if value.is_empty() {
+                    return Err(invalid());
+                }
+                self.mesh = Some(value.into());
+            },
+            "camera.projection" => {
+                let projection = match value {
+                    "perspective" => Projection::Perspective,
+                    "orthographic" => Projection::Orthographic,
+                    _ => return Err(invalid()),
+                };
+                if self.camera.is_none() {
+                    self.camera = Some(CameraNodeData::default());
+                }
+
+                self.camera.as_mut().unwrap().projection = projection;
+            },
+            "camera.fov" | "camera.aspect" | "camera.near" | "camera.far" | "camera.size" => {
+                let value = parse_floats::<[f32; 1]>(value).ok_or_else(&invalid)?[0];
+                if self.camera.is_none() {
+                    self.camera = Some(CameraNodeData::default());
+                }
+
+                let camera = self.camera.as_mut().unwrap();
+                match key {
+                    "camera.fov" => camera.fov = value,
+                    "camera.aspect" => camera.aspect = value,
+                    "camera.near" => camera.near = value,
+                    "camera.far" => camera.far = value,
+                    _ => camera.size = value,
+                }
+            },
+            "light" => {
+                self.light = match value {
+                    "directional" => Some(LightBuilder::Directional {
+                        direction: Vector3::forward(),
+                        color: Color::rgb(1.0, 1.0, 1.0),
+                        strength: 1.0,
+                    }),
+                    "point" => Some(LightBuilder::Point {
+                        radius: 1.0,
+                        color: Color::rgb(1.0, 1.0, 1.0),
+                        strength: 1.0,
+                    }),
+                    "spot" => Some(LightBuilder::Spot {
+                        range: 1.0,
+                        inner_angle: PI / 8.0,
+                        outer_angle: PI / 6.0,
+                        color: Color::rgb(1.0, 1.0, 1.0),
+                        strength: 1.0,
+                    }),
+                    _ => return Err(ParseSceneError::UnknownLightType { line: line, light_type: value.into() }),
+                };
+            },
+            "light.direction" => {
+                let xyz = parse_floats::<[f32; 3]>(value).ok_or_else(&invalid)?;
+                let direction = Vector3::new(xyz[0], xyz[1], xyz[2]);
+                if direction.is_zero() {
+                    return Err(invalid());
+                }
+
+                match self.light {
+                    Some(LightBuilder::Directional { direction: ref mut light_direction, .. }) => *light_direction = direction,
+                    _ => return Err(mismatch()),
+                }
+            },
+            "light.radius" => {
+                let radius = parse_floats::<[f32; 1]>(value).ok_or_else(&invalid)?[0];
+                match self.light {
+                    Some(LightBuilder::Point { radius: ref mut light_radius, .. }) => *light_radius = radius,
+                    _ => return Err(mismatch()),
+                }
+            },
+            "light.range" => {
+                let range = parse_floats::<[f32; 1]>(value).ok_or_else(&invalid)?[0];
+                match self.light {
+                    Some(LightBuilder::Spot { range: ref mut light_range, .. }) => *light_range = range,
+                    _ => return Err(mismatch()),
+                }
+            },
+            "light.inner_angle" => {
+                let angle = parse_floats::<[f32; 1]>(value).ok_or_else(&invalid)?[0];
+                match self.light {
+                    Some(LightBuilder::Spot { inner_angle: ref mut light_angle, .. }) => *light_angle = angle,
+                    _ => return Err(mismatch()),
+                }
+            },
+            "light.outer_angle" => {
+                let angle = parse_floats::<[f32; 1]>(value).ok_or_else(&invalid)?[0];
+                match self.light {
+                    Some(LightBuilder::Spot { outer_angle: ref mut light_angle, .. }) => *light_angle = angle,
+                    _ => return Err(mismatch()),
+                }
+            },
+            "light.color" => {
+                let rgba = parse_floats::<[f32; 4]>(value).ok_or_else(&invalid)?;
+                let color = Color::new(rgba[0], rgba[1], rgba[2], rgba[3]);
+                match self.light {
+                    Some(LightBuilder::Directional { color: ref mut light_color, .. }) |
+                    Some(LightBuilder::Point { color: ref mut light_color, .. }) |
+                    Some(LightBuilder::Spot { color: ref mut light_color, .. }) => *light_color = color,
+                    None => return Err(mismatch()),
+                }
+            },
+            "light.strength" => {
+                let strength = parse_floats::<[f32; 1]>(value).ok_or_else(&invalid)?[0];
+                match self.light {
+                    Some(LightBuilder::Directional { strength: ref mut light_strength, .. }) |
+                    Some(LightBuilder::Point { strength: ref mut light_strength, .. }) |
+                    Some(LightBuilder::Spot { strength: ref mut light_strength, .. }) => *light_strength = strength,
+                    None => return Err(mismatch()),
+                }
+            },
+            _ => return Err(ParseSceneError::UnknownProperty { line: line, key: key.into() }),
+        }
+
+        Ok(())
+    }
+
+    fn build(self) -> NodeData {
+        let light = self.light.map(|light| match light {
+            LightBuilder::Directional { direction, color, strength } => LightNodeData::Directional {
+                direction: direction,
+                color: color,
+                strength: strength,
+            },
+            LightBuilder::Point { radius, color, strength } => LightNodeData::Point {
+                radius: radius,
+                color: color,
+                strength: strength,
+            },
+            LightBuilder::Spot { range, inner_angle, outer_angle, color, strength } => LightNodeData::Spot {
+                range: range,
+                inner_angle: inner_angle,
+                outer_angle: outer_angle,
+                color: color,
+                strength: strength,
+            },
+        });
+
+        NodeData {
+            name: self.name,
+            position: self.position,
+            orientation: self.orientation,
+            scale: self.scale,
+            mesh: self.mesh,
+            camera: self.camera,
+            light: light,
+        }
+    }
+}
+
+/// Parses a whitespace-separated list of exactly `N` floats.
+fn parse_floats<A: FloatArray>(value: &str) -> Option<A> {
+    let mut result = A::default();
+    let mut count = 0;
+    for part in value.split_whitespace() {
+        if count >= result.as_mut().len() {
+            return None;
+        }
+
+        result.as_mut()[count] = match f32::from_str(part) {
+            Ok(value) => value,
+            Err(_) => return None,
+        };
+        count += 1;
+    }
+
+    if count == result.as_mut().len() { Some(result) } else { None }
+}
+
+trait FloatArray: Default + AsMut<[f32]> {}
+
+impl FloatArray for [f32; 1] {}
+impl FloatArray for [f32; 3] {}
+impl FloatArray for [f32; 4] {}
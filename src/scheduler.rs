@@ -30,7 +30,7 @@ use std::marker::PhantomData;
 use std::mem;
 use std::sync::{Condvar, Mutex, Once, ONCE_INIT};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::{self, Receiver, SyncSender};
 use stopwatch;
 
 const DEFAULT_STACK_SIZE: usize = 64 * 1024;
@@ -171,6 +171,50 @@ pub fn start<'a, F, T>(func: F) -> Async<'a, T>
     }
 }
 
+/// Creates an `Async<T>` that completes when the returned `Promise<T>` is fulfilled.
+///
+/// This is useful when the result isn't produced by a single unit of work, e.g. when it's
+/// produced over several frames by a `run_each_frame()` behavior. Awaiting the `Async` suspends
+/// the current fiber until `Promise::complete()` is called, so nothing polls for the result.
+pub fn promise<T>() -> (Promise<T>, Async<'static, T>)
+    where T: Send
+{
+    let (sender, receiver) = mpsc::sync_channel(1);
+
+    let work_id = WorkId(WORK_COUNTER.fetch_add(1, Ordering::Relaxed));
+    Scheduler::with(|scheduler| scheduler.add_pending_work(work_id));
+
+    let promise = Promise {
+        work: work_id,
+        sender: sender,
+    };
+
+    let async = Async {
+        work: work_id,
+        receiver: receiver,
+        _phantom: PhantomData,
+    };
+
+    (promise, async)
+}
+
+/// The sending half of an `Async<T>` created with `promise()`.
+///
+/// If the promise is dropped without being completed then the `Async` never completes.
+#[derive(Debug)]
+pub struct Promise<T> {
+    work: WorkId,
+    sender: SyncSender<T>,
+}
+
+impl<T> Promise<T> {
+    /// Completes the corresponding `Async` with `value`, resuming any fibers awaiting it.
+    pub fn complete(self, value: T) {
+        self.sender.try_send(value).expect("Failed to send promised result");
+        Scheduler::with(|scheduler| scheduler.complete_work(self.work));
+    }
+}
+
 /// Suspends the current fiber and makes the wait fiber active.
 ///
 /// Generally you shouldn't need to call this directly, but if you have one piece of code that
@@ -299,6 +343,11 @@ impl Scheduler {
         CONDVAR.borrow().notify_one();
     }
 
+    /// Marks a unit of work as in progress without scheduling it, see `promise()`.
+    fn add_pending_work(&mut self, work: WorkId) {
+        assert!(self.current_work.insert(work), "Work's ID was already present in current work set");
+    }
+
     /// Adds `dependency` as a dependency of the currently running fiber.
     ///
     /// Returns `true` if work is still in progress and was added as a dependency, false otherwise.
@@ -334,8 +383,18 @@ impl Scheduler {
         self.work_map.insert(current, new_work);
     }
 
-    /// Removes the specified unit of work from the scheduler, updating any dependent work.
+    /// Removes the specified unit of work from the scheduler once the current fiber has finished
+    /// running it.
     fn finish_work(&mut self, finished_work: WorkId) {
+        self.complete_work(finished_work);
+
+        let fiber = fiber::current().unwrap();
+        assert!(self.work_map.remove(&fiber).is_some(), "{:?} didn't have {:?} associated in the work map", fiber, finished_work);
+    }
+
+    /// Removes the specified unit of work from the set of current work, updating any dependent
+    /// work.
+    fn complete_work(&mut self, finished_work: WorkId) {
         // Iterate over all suspended work units, removing `finished_work` as a dependency where
         // necessary. If any of the work units no longer have dependencies then
         let mut ready = Vec::new();
@@ -354,9 +413,7 @@ impl Scheduler {
             }
         }
 
-        let fiber = fiber::current().unwrap();
         assert!(self.current_work.remove(&finished_work), "{:?} wasn't in current work set when it finished", finished_work);
-        assert!(self.work_map.remove(&fiber).is_some(), "{:?} didn't have {:?} associated in the work map", fiber, finished_work);
     }
 
     /// Performs the necessary bookkeeping when a fiber becomes active.
@@ -3,19 +3,16 @@
 //! TODO: Document the transform "component", especially how there's no parent/child setup.
 
 use engine::{self, EngineMessage};
-use collections::atomic_array::AtomicArray;
+use collections::segmented_array::{self, SegmentedArray};
 use cell_extras::atomic_ref_cell::*;
 use std::fmt::{self, Debug, Formatter};
 use std::mem;
 use std::ptr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use math::*;
 use polygon::anchor::AnchorId;
 
-// HACK: This is a temporary upper bound on the number of nodes in a single row until we can
-// better support dynamically allocating more space for a row.
-const ROW_CAPACITY: usize = 1024;
-
 /// A handle to a node in the scene graph.
 pub struct Transform {
     inner: TransformInnerHandle,
@@ -134,6 +131,68 @@ impl Transform {
         self.orientation().back()
     }
 
+    /// Rotates the transform so that its forward direction points at `target`.
+    ///
+    /// The transform's up direction will be as close to `up` as possible while remaining
+    /// perpendicular to the forward direction.
+    pub fn look_at(&mut self, target: Point, up: Vector3) {
+        let forward = target - self.position();
+        self.set_orientation(Orientation::look_rotation(forward, up));
+    }
+
+    /// Transforms `point` from the transform's local space to world space.
+    pub fn transform_point(&self, point: Point) -> Point {
+        let data = self.inner.data();
+        data.position + data.orientation * (point.as_vector3() * data.scale)
+    }
+
+    /// Transforms `vector` from the transform's local space to world space.
+    ///
+    /// Vectors are affected by the transform's orientation and scale but not its position.
+    pub fn transform_vector(&self, vector: Vector3) -> Vector3 {
+        let data = self.inner.data();
+        data.orientation * (vector * data.scale)
+    }
+
+    /// Transforms `point` from world space to the transform's local space.
+    ///
+    /// This is the inverse of `transform_point()`.
+    pub fn inverse_transform_point(&self, point: Point) -> Point {
+        let data = self.inner.data();
+        let offset = data.orientation.inverse() * (point - data.position);
+        Point::origin() + offset * (1.0 / data.scale)
+    }
+
+    /// Transforms `vector` from world space to the transform's local space.
+    ///
+    /// This is the inverse of `transform_vector()`.
+    pub fn inverse_transform_vector(&self, vector: Vector3) -> Vector3 {
+        let data = self.inner.data();
+        (data.orientation.inverse() * vector) * (1.0 / data.scale)
+    }
+
+    /// Calculates the matrix that converts from the transform's local space to world space.
+    pub fn local_to_world_matrix(&self) -> Matrix4 {
+        let data = self.inner.data();
+        let position = Matrix4::from_point(data.position);
+        let orientation = Matrix4::from(data.orientation);
+        let scale = Matrix4::from_scale_vector(data.scale);
+
+        position * (orientation * scale)
+    }
+
+    /// Calculates the matrix that converts from world space to the transform's local space.
+    ///
+    /// This is the inverse of `local_to_world_matrix()`.
+    pub fn world_to_local_matrix(&self) -> Matrix4 {
+        let data = self.inner.data();
+        let inv_scale = Matrix4::from_scale_vector(1.0 / data.scale);
+        let inv_orientation = Matrix4::from(data.orientation).transpose();
+        let inv_position = Matrix4::from_point(-data.position);
+
+        inv_scale * (inv_orientation * inv_position)
+    }
+
     pub fn forget(self) {
         mem::forget(self)
     }
@@ -146,7 +205,8 @@ impl Transform {
 
 impl Drop for Transform {
     fn drop(&mut self) {
-        // TODO: Mark transform and all its children as destroyed in the manager.
+        // TODO: Mark transform and all its children as destroyed in the manager, and unregister
+        // their anchors from the renderer.
         warn_once!("WARNING: Drop hasn't been implemented for Transform yet");
     }
 }
@@ -167,18 +227,45 @@ impl Debug for Transform {
 unsafe impl Send for Transform {}
 
 pub struct TransformGraph {
-    row: AtomicArray<AtomicRefCell<TransformData>>,
+    // NOTE: `TransformInner` holds a raw pointer to its data in the row, so the row's storage
+    // must never move existing elements when growing.
+    row: SegmentedArray<AtomicRefCell<TransformData>>,
+
+    /// Nodes that have been modified since the last call to `take_dirty()`.
+    dirty: DirtyNodes,
 }
 
 impl TransformGraph {
     pub fn new() -> TransformGraph {
         TransformGraph {
-            row: AtomicArray::new(ROW_CAPACITY),
+            row: SegmentedArray::new(),
+            dirty: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
-    pub fn roots(&self) -> &[AtomicRefCell<TransformData>] {
-        self.row.as_slice()
+    pub fn roots(&self) -> segmented_array::Iter<AtomicRefCell<TransformData>> {
+        self.row.iter()
+    }
+
+    /// Gets all nodes that have been modified since the last time `take_dirty()` was called,
+    /// clearing their dirty flags.
+    ///
+    /// Each modified node appears in the list only once, regardless of how many times it was
+    /// modified.
+    pub fn take_dirty(&self) -> Vec<&AtomicRefCell<TransformData>> {
+        let mut dirty = self.dirty.lock().expect("Dirty nodes mutex was poisoned");
+        dirty
+            .drain(..)
+            .map(|data_ptr| {
+                let data = unsafe { &*data_ptr };
+
+                // Clear the flag while we still hold the lock, that way any modification made
+                // after this point will add the node back to the list.
+                data.borrow().inner.dirty.store(false, Ordering::SeqCst);
+
+                data
+            })
+            .collect()
     }
 
     fn create_node(&self) -> TransformInnerHandle {
@@ -186,11 +273,12 @@ impl TransformGraph {
         let inner = Arc::new(TransformInner {
             data: AtomicRefCell::new(ptr::null_mut()),
             anchor: AtomicRefCell::new(None),
+            dirty: AtomicBool::new(false),
+            dirty_nodes: self.dirty.clone(),
         });
 
         // Create transform data with pointer to inner.
-        assert!(self.row.len() < ROW_CAPACITY, "Row 0 exceeded row capacity");
-        self.row.push(AtomicRefCell::new(TransformData {
+        let data = self.row.push(AtomicRefCell::new(TransformData {
             inner: inner.clone(),
 
             position: Point::origin(),
@@ -198,10 +286,11 @@ impl TransformGraph {
             scale: Vector3::one(),
         }));
 
-        // Hook up inner's pointer to data.
+        // Hook up inner's pointer to data. We use the reference returned by `push()` rather than
+        // the last element in the row because other threads may have pushed nodes since.
         {
-            let mut data = inner.data.borrow_mut();
-            *data = self.row.last().unwrap();
+            let mut data_ptr = inner.data.borrow_mut();
+            *data_ptr = data;
         }
 
         engine::send_message(EngineMessage::Anchor(inner.clone()));
@@ -217,6 +306,10 @@ unsafe impl Sync for TransformGraph {}
 pub struct TransformInner {
     data: AtomicRefCell<*const AtomicRefCell<TransformData>>,
     anchor: AtomicRefCell<Option<AnchorId>>,
+
+    /// Set when the node is added to the dirty list, that way each node is only added once.
+    dirty: AtomicBool,
+    dirty_nodes: DirtyNodes,
 }
 
 impl TransformInner {
@@ -226,12 +319,23 @@ impl TransformInner {
         data.borrow()
     }
 
+    /// Mutably borrows the transform's data, marking the transform as dirty.
     pub fn data_mut(&self) -> AtomicRefMut<TransformData> {
+        self.mark_dirty();
+
         let data_ptr = self.data.borrow();
         let data = unsafe { &**data_ptr };
         data.borrow_mut()
     }
 
+    /// Adds the transform to the graph's list of modified nodes if it's not already there.
+    pub fn mark_dirty(&self) {
+        if !self.dirty.swap(true, Ordering::SeqCst) {
+            let mut dirty_nodes = self.dirty_nodes.lock().expect("Dirty nodes mutex was poisoned");
+            dirty_nodes.push(*self.data.borrow());
+        }
+    }
+
     pub fn anchor(&self) -> Option<AnchorId> {
         self.anchor.borrow().clone()
     }
@@ -249,6 +353,8 @@ impl TransformInner {
 
 pub type TransformInnerHandle = Arc<TransformInner>;
 
+type DirtyNodes = Arc<Mutex<Vec<*const AtomicRefCell<TransformData>>>>;
+
 #[derive(Debug)]
 pub struct TransformData {
     pub inner: TransformInnerHandle,
@@ -263,3 +369,60 @@ impl TransformData {
         *self.inner.anchor.borrow()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine::RENDER_MESSAGE_CHANNEL;
+    use std::sync::Arc;
+    use std::sync::mpsc;
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn create_nodes_in_parallel() {
+        const THREADS: usize = 8;
+        const NODES_PER_THREAD: usize = 12_500;
+
+        let graph = Arc::new(TransformGraph::new());
+        let (sender, receiver) = mpsc::channel();
+
+        let workers = (0..THREADS)
+            .map(|thread_index| {
+                let graph = graph.clone();
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    RENDER_MESSAGE_CHANNEL.with(move |channel| { channel.init(sender); });
+
+                    (0..NODES_PER_THREAD)
+                        .map(|node_index| {
+                            let inner = graph.create_node();
+                            inner.data_mut().position = Point::new(thread_index as f32, node_index as f32, 0.0);
+                            inner
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Every node's data pointer should still refer to its own data after the row has grown
+        // past its first segment.
+        for (thread_index, worker) in workers.into_iter().enumerate() {
+            let nodes = worker.join().expect("Worker thread panicked");
+            for (node_index, inner) in nodes.iter().enumerate() {
+                let data = inner.data();
+                assert_eq!(Point::new(thread_index as f32, node_index as f32, 0.0), data.position);
+                assert!(Arc::ptr_eq(inner, &data.inner));
+            }
+        }
+
+        assert_eq!(THREADS * NODES_PER_THREAD, graph.roots().count());
+
+        // Every node was modified, so each should show up as dirty exactly once.
+        assert_eq!(THREADS * NODES_PER_THREAD, graph.take_dirty().len());
+        assert!(graph.take_dirty().is_empty());
+
+        // Each node should have sent exactly one anchor message.
+        ::std::mem::drop(sender);
+        assert_eq!(THREADS * NODES_PER_THREAD, receiver.iter().count());
+    }
+}
@@ -0,0 +1,224 @@
+extern crate gunship;
+
+use gunship::animation::*;
+use gunship::camera::Projection;
+use gunship::math::*;
+use gunship::math::quaternion::Quaternion;
+use gunship::scene::*;
+
+#[test]
+fn scene_round_trip() {
+    let scene = SceneData {
+        nodes: vec![
+            NodeData {
+                name: "camera".into(),
+                position: Point::new(0.0, 1.5, 10.0),
+                orientation: Orientation::from_eulers(0.1, 0.2, 0.3),
+                scale: Vector3::one(),
+                mesh: None,
+                camera: Some(CameraNodeData {
+                    fov: 1.0,
+                    aspect: 1.5,
+                    near: 0.1,
+                    far: 100.0,
+                    projection: Projection::Orthographic,
+                    size: 4.5,
+                }),
+                light: None,
+            },
+            NodeData {
+                name: "head with spaces".into(),
+                position: Point::new(3.0, 0.0, -0.25),
+                orientation: Orientation::new(),
+                scale: Vector3::new(2.0, 2.0, 2.0),
+                mesh: Some("meshes/epps_head.obj".into()),
+                camera: None,
+                light: Some(LightNodeData::Point { radius: 5.0, color: Color::rgb(1.0, 0.5, 0.0), strength: 2.0 }),
+            },
+            NodeData {
+                name: "flashlight".into(),
+                position: Point::new(0.0, 2.0, 0.0),
+                orientation: Orientation::from_eulers(-0.5, 0.0, 0.0),
+                scale: Vector3::one(),
+                mesh: None,
+                camera: None,
+                light: Some(LightNodeData::Spot {
+                    range: 10.0,
+                    inner_angle: 0.3,
+                    outer_angle: 0.5,
+                    color: Color::rgb(1.0, 1.0, 0.8),
+                    strength: 1.5,
+                }),
+            },
+        ],
+    };
+
+    let text = scene.to_string();
+    assert_eq!(Ok(scene), text.parse::<SceneData>());
+}
+
+#[test]
+fn scene_parse_errors() {
+    assert_eq!(
+        Err(ParseSceneError::PropertyOutsideNode { line: 1 }),
+        "position = 0 0 0".parse::<SceneData>(),
+    );
+    assert_eq!(
+        Err(ParseSceneError::InvalidValue { line: 2, key: "position".into() }),
+        "[node foo]\nposition = 0 0".parse::<SceneData>(),
+    );
+    assert_eq!(
+        Err(ParseSceneError::LightPropertyMismatch { line: 3, key: "light.direction".into() }),
+        "[node foo]\nlight = point\nlight.direction = 0 -1 0".parse::<SceneData>(),
+    );
+}
+
+#[test]
+fn track_sample() {
+    let track = Track::new(Interpolation::Linear)
+        .keyframe(2.0, Vector3::new(2.0, 0.0, 0.0))
+        .keyframe(0.0, Vector3::new(0.0, 0.0, 0.0))
+        .keyframe(1.0, Vector3::new(1.0, 1.0, 0.0));
+
+    // Keyframes are sorted regardless of the order they were added in.
+    let times = track.keyframes().iter().map(|keyframe| keyframe.time).collect::<Vec<_>>();
+    assert_eq!(vec![0.0, 1.0, 2.0], times);
+    assert_eq!(2.0, track.duration());
+
+    // Sampling outside the keyframes clamps to the first and last keyframes.
+    assert_eq!(Some(Vector3::new(0.0, 0.0, 0.0)), track.sample(-1.0));
+    assert_eq!(Some(Vector3::new(2.0, 0.0, 0.0)), track.sample(3.0));
+
+    assert_eq!(Some(Vector3::new(1.0, 1.0, 0.0)), track.sample(1.0));
+    assert!((track.sample(0.5).unwrap() - Vector3::new(0.5, 0.5, 0.0)).is_zero());
+    assert!((track.sample(1.75).unwrap() - Vector3::new(1.75, 0.25, 0.0)).is_zero());
+
+    assert_eq!(None, Track::<Vector3>::new(Interpolation::Linear).sample(0.0));
+}
+
+#[test]
+fn track_sample_step() {
+    let track = Track::new(Interpolation::Step)
+        .keyframe(0.0, Vector3::new(0.0, 0.0, 0.0))
+        .keyframe(1.0, Vector3::new(1.0, 0.0, 0.0));
+
+    assert_eq!(Some(Vector3::new(0.0, 0.0, 0.0)), track.sample(0.99));
+    assert_eq!(Some(Vector3::new(1.0, 0.0, 0.0)), track.sample(1.0));
+}
+
+#[test]
+fn track_sample_cubic() {
+    let track = Track::new(Interpolation::Cubic)
+        .keyframe(0.0, Point::new(0.0, 0.0, 0.0))
+        .keyframe(1.0, Point::new(1.0, 2.0, 0.0))
+        .keyframe(2.0, Point::new(2.0, 0.0, 0.0));
+
+    // The curve passes through every keyframe...
+    for keyframe in track.keyframes() {
+        assert!((track.sample(keyframe.time).unwrap() - keyframe.value).is_zero());
+    }
+
+    // ... and overshoots the straight line between keyframes near the peak.
+    let linear_peak = 2.0 * 0.75;
+    assert!(track.sample(0.75).unwrap().y > linear_peak);
+}
+
+#[test]
+fn track_sample_orientation() {
+    let axis = Vector3::new(0.0, 1.0, 0.0);
+    let track = Track::new(Interpolation::Linear)
+        .keyframe(0.0, Orientation::new())
+        .keyframe(1.0, Orientation::axis_angle(axis, 1.0));
+
+    let halfway = Quaternion::from(track.sample(0.5).unwrap());
+    assert!((halfway - Quaternion::from(Orientation::axis_angle(axis, 0.5))).is_zero());
+}
+
+#[test]
+fn track_sample_orientation_cubic() {
+    // Keyframes rotating at a constant rate around one axis.
+    let axis = Vector3::new(0.0, 1.0, 0.0);
+    let mut track = Track::new(Interpolation::Cubic);
+    for index in 0..4 {
+        track = track.keyframe(index as f32, Orientation::axis_angle(axis, index as f32));
+    }
+
+    // The curve passes through every keyframe. Between the middle keyframes, which have keyframes
+    // on both sides, it keeps rotating at the same rate rather than easing in and out of them.
+    for &time in &[0.0, 1.0, 1.25, 1.5, 1.75, 2.0, 3.0] {
+        let sample = Quaternion::from(track.sample(time).unwrap());
+        let expected = Quaternion::from(Orientation::axis_angle(axis, time));
+        assert!((sample - expected).is_zero(), "{:?} != {:?} at {}", sample, expected, time);
+    }
+}
+
+#[test]
+fn easing_apply() {
+    let easings = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+    ];
+
+    for &easing in &easings {
+        // Every easing starts at 0, ends at 1, and never moves backwards.
+        assert!(easing.apply(0.0).is_zero(), "{:?}", easing);
+        assert!((easing.apply(1.0) - 1.0).is_zero(), "{:?}", easing);
+
+        let mut previous = 0.0;
+        for step in 1..101 {
+            let value = easing.apply(step as f32 / 100.0);
+            assert!(value >= previous - 1e-6, "{:?} decreased at step {}", easing, step);
+            previous = value;
+        }
+    }
+
+    assert!((Easing::QuadIn.apply(0.5) - 0.25).is_zero());
+    assert!((Easing::QuadOut.apply(0.5) - 0.75).is_zero());
+    assert!((Easing::CubicIn.apply(0.5) - 0.125).is_zero());
+    assert!((Easing::CubicOut.apply(0.5) - 0.875).is_zero());
+    assert!((Easing::QuadInOut.apply(0.5) - 0.5).is_zero());
+    assert!((Easing::CubicInOut.apply(0.5) - 0.5).is_zero());
+    assert!((Easing::SineInOut.apply(0.5) - 0.5).is_zero());
+}
+
+#[test]
+fn animation_local_time() {
+    let track = Track::new(Interpolation::Linear)
+        .keyframe(0.0, Vector3::new(0.0, 0.0, 0.0))
+        .keyframe(2.0, Vector3::new(1.0, 1.0, 1.0));
+    let once = Animation::new().scale(track.clone());
+    let repeat = Animation::new().scale(track.clone()).looping(Loop::Repeat);
+    let ping_pong = Animation::new().scale(track.clone()).looping(Loop::PingPong);
+    let eased = Animation::new().scale(track.clone()).easing(Easing::QuadIn);
+
+    assert_eq!(2.0, once.duration());
+
+    assert!((once.local_time(1.0) - 1.0).is_zero());
+    assert!((once.local_time(3.0) - 2.0).is_zero());
+    assert!(!once.is_finished(1.5));
+    assert!(once.is_finished(2.0));
+
+    assert!((repeat.local_time(2.5) - 0.5).is_zero());
+    assert!((repeat.local_time(5.0) - 1.0).is_zero());
+    assert!(!repeat.is_finished(100.0));
+
+    assert!((ping_pong.local_time(1.5) - 1.5).is_zero());
+    assert!((ping_pong.local_time(2.5) - 1.5).is_zero());
+    assert!((ping_pong.local_time(3.5) - 0.5).is_zero());
+    assert!((ping_pong.local_time(4.5) - 0.5).is_zero());
+    assert!(!ping_pong.is_finished(100.0));
+
+    // Easing is applied over the whole animation.
+    assert!((eased.local_time(1.0) - 0.5).is_zero());
+
+    // An animation without any keyframes has no duration.
+    assert_eq!(0.0, Animation::new().local_time(1.0));
+}